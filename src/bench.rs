@@ -0,0 +1,167 @@
+use std::time::Duration;
+
+use crate::{
+    board::Board,
+    epd::Epd,
+    search::{Search, SearchInfo, SearchLimits, SearchOptions},
+};
+
+/// Positions searched to a fixed depth to compare node counts between search versions
+pub const BENCH_POSITIONS: [&str; 12] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+    "r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP3PPP/R2QKB1R w KQ - 1 8",
+    "rnbqkb1r/pp3ppp/3p1n2/2pP4/8/2N5/PP2PPPP/R1BQKBNR w KQkq - 0 6",
+    "r2q1rk1/1b2bppp/p2ppn2/1p6/3NP3/1BN1B3/PPP2PPP/R2Q1RK1 w - - 0 11",
+    "2r3k1/pp3ppp/2n1b3/3pP3/3P4/P4N2/1P3PPP/2R3K1 w - - 0 22",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "4rrk1/pp1n3p/3q2pQ/2p1pb2/2PP4/2P3N1/P2B2PP/4RRK1 b - - 7 19",
+    "6k1/5p2/6p1/8/7p/8/6PP/6K1 b - - 0 1",
+    "8/8/4k3/3n4/8/4K3/3B4/8 w - - 0 1",
+    "r3r1k1/2p2ppp/p1p1bn2/8/1q2P3/2NPQN2/PPP3PP/R4RK1 b - - 2 15",
+];
+
+/// Outcome of searching the bench positions
+#[derive(Clone, Debug, PartialEq)]
+pub struct BenchResult {
+    pub nodes: u64,
+    pub time: Duration,
+}
+
+impl BenchResult {
+    pub fn get_nps(&self) -> u64 {
+        (self.nodes as f64 / self.time.as_secs_f64().max(0.001)) as u64
+    }
+}
+
+/// Searches every bench position to given depth with fresh search state, so node
+/// counts are deterministic, calling `on_position` after each one
+pub fn run_bench(
+    depth: u32,
+    options: &SearchOptions,
+    mut on_position: impl FnMut(&Board, &SearchInfo),
+) -> BenchResult {
+    let limits = SearchLimits {
+        depth: Some(depth),
+        ..SearchLimits::default()
+    };
+    let mut result = BenchResult {
+        nodes: 0,
+        time: Duration::ZERO,
+    };
+
+    for fen in BENCH_POSITIONS {
+        let board = Board::new_from_fen(fen).expect("bench positions are valid");
+        let mut search = Search::new();
+        search.set_options(options.clone());
+        let info = search.run(&board, &limits, |_| {});
+
+        result.nodes += info.nodes;
+        result.time += info.time;
+        on_position(&board, &info);
+    }
+    result
+}
+
+/// Outcome of running the search over an EPD test suite
+#[derive(Clone, Debug, PartialEq)]
+pub struct SuiteResult {
+    pub solved: usize,
+    pub total: usize,
+    pub nodes: u64,
+    pub time: Duration,
+    /// Ids of unsolved positions, or their FEN when they have no id
+    pub failed: Vec<String>,
+}
+
+/// Position is solved if the search picks one of its "bm" moves and none of its "am" moves
+pub fn is_solved(epd: &Epd, info: &SearchInfo) -> bool {
+    let Some(best_move) = info.get_best_move() else {
+        return false;
+    };
+    epd.get_best_moves()
+        .is_none_or(|moves| moves.contains(&best_move))
+        && epd
+            .get_avoid_moves()
+            .is_none_or(|moves| !moves.contains(&best_move))
+}
+
+/// Searches every position with given limits, calling `on_position` after each one
+pub fn run_epd_suite(
+    suite: &[Epd],
+    limits: &SearchLimits,
+    options: &SearchOptions,
+    mut on_position: impl FnMut(&Epd, &SearchInfo, bool),
+) -> SuiteResult {
+    let mut result = SuiteResult {
+        solved: 0,
+        total: suite.len(),
+        nodes: 0,
+        time: Duration::ZERO,
+        failed: Vec::new(),
+    };
+
+    for epd in suite {
+        let mut search = Search::new();
+        search.set_options(options.clone());
+        let info = search.run(epd.get_board(), limits, |_| {});
+
+        let solved = is_solved(epd, &info);
+        if solved {
+            result.solved += 1;
+        } else {
+            let name = epd
+                .get_id()
+                .map_or_else(|| epd.get_board().get_fen(), String::from);
+            result.failed.push(name);
+        }
+        result.nodes += info.nodes;
+        result.time += info.time;
+        on_position(epd, &info, solved);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_runs_epd_suite() {
+        let suite = Epd::parse_all(
+            "6k1/5ppp/8/8/8/8/8/R3K3 w Q - bm Ra8#; id \"mate\";\n\
+             4k3/8/8/3q4/8/8/3R4/4K3 w - - am Rd1; id \"queen\";\n\
+             4k3/8/8/3q4/8/8/3R4/4K3 w - - bm Ke2; id \"wrong\";",
+        )
+        .unwrap();
+        let limits = SearchLimits {
+            depth: Some(2),
+            ..SearchLimits::default()
+        };
+        let mut reported = 0;
+        let result = run_epd_suite(&suite, &limits, &SearchOptions::default(), |_, _, _| {
+            reported += 1
+        });
+
+        assert_eq!(reported, 3);
+        assert_eq!(result.solved, 2);
+        assert_eq!(result.total, 3);
+        assert_eq!(result.failed, vec![String::from("wrong")]);
+        assert!(result.nodes > 0);
+    }
+
+    #[test]
+    fn it_runs_deterministic_bench() {
+        let mut positions = 0;
+        let first = run_bench(2, &SearchOptions::default(), |_, info| {
+            positions += 1;
+            assert_eq!(info.depth, 2);
+        });
+        let second = run_bench(2, &SearchOptions::default(), |_, _| {});
+
+        assert_eq!(positions, BENCH_POSITIONS.len());
+        assert_eq!(first.nodes, second.nodes);
+        assert!(first.get_nps() > 0);
+    }
+}
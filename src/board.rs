@@ -1,33 +1,150 @@
-use std::ops::Add;
-
-use iced::futures::io::empty;
-
 use crate::{
-    piece::Piece,
+    board_renderer::BoardRenderer,
+    castling_rights::{CastlingRights, CastlingSide},
+    chess_move::Move,
+    mate_search::MateSearch,
     piece::PieceColor,
-    position::{BoardIndex, Position}, castling_rights::CastlingRights,
+    piece::{Piece, PieceKind, KING_OFFSETS, KNIGHT_OFFSETS},
+    position::{BoardIndex, Position},
+    zobrist,
 };
 
 type BoardType = [[Option<Piece>; 8]; 8];
+#[derive(Clone, Debug, PartialEq)]
 pub struct Board {
     board: BoardType,
     to_move: PieceColor,
     castling_rights: CastlingRights,
+    en_passant: Option<Position>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    /// Castling moves are encoded as king capturing own rook (e1h1) instead of king target (e1g1)
+    chess960: bool,
 }
 
 impl Default for Board {
     fn default() -> Self {
-        let mut board = Board {
-            board: std::array::from_fn(|_| std::array::from_fn(|_| None)),
-            to_move: PieceColor::White,
-            castling_rights: CastlingRights::default()
-        };
+        let mut board = Board::empty();
         board.add_default_pieces();
         board
     }
 }
 
 impl Board {
+    fn empty() -> Board {
+        Board {
+            board: std::array::from_fn(|_| std::array::from_fn(|_| None)),
+            to_move: PieceColor::White,
+            castling_rights: CastlingRights::default(),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            chess960: false,
+        }
+    }
+
+    /// Creates board from FEN string
+    ///
+    /// Move counters might be omitted, in that case they default to "0 1"
+    pub fn new_from_fen(fen: &str) -> Result<Board, String> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if !(4..=6).contains(&fields.len()) {
+            return Err(format!("FEN needs 4 to 6 fields, got {}", fields.len()));
+        }
+
+        let mut board = Board::empty();
+        let ranks: Vec<&str> = fields[0].split('/').collect();
+        if ranks.len() != 8 {
+            return Err(String::from("FEN board needs to have 8 ranks"));
+        }
+        // FEN starts from 8th rank
+        for (rank_index, rank) in ranks.iter().rev().enumerate() {
+            let mut file_index = 0;
+            for c in rank.chars() {
+                if let Some(empty_squares) = c.to_digit(10) {
+                    file_index += empty_squares as usize;
+                    continue;
+                }
+                if file_index > 7 {
+                    return Err(format!("Too many squares in rank {}", rank_index + 1));
+                }
+                let pos = Position::new_from_indices(BoardIndex(file_index, rank_index));
+                let piece = Piece::from_fen_char(c, pos)
+                    .ok_or_else(|| format!("Invalid piece character '{}'", c))?;
+                board.add_piece(piece);
+                file_index += 1;
+            }
+            if file_index != 8 {
+                return Err(format!("Rank {} needs to have 8 squares", rank_index + 1));
+            }
+        }
+
+        board.to_move = match fields[1] {
+            "w" => PieceColor::White,
+            "b" => PieceColor::Black,
+            other => return Err(format!("Invalid side to move '{}'", other)),
+        };
+        board.castling_rights = CastlingRights::from_fen(fields[2], &board)?;
+        board.chess960 = !board.castling_rights.is_standard()
+            || [PieceColor::White, PieceColor::Black].iter().any(|color| {
+                (board
+                    .castling_rights
+                    .is_available(*color, CastlingSide::Kingside)
+                    || board
+                        .castling_rights
+                        .is_available(*color, CastlingSide::Queenside))
+                    && board.get_king_position(*color) != Some(Position('e', color.back_rank()))
+            });
+        board.en_passant = match fields[3] {
+            "-" => None,
+            square => Some(
+                Position::parse(square)
+                    .ok_or_else(|| format!("Invalid en passant square '{}'", square))?,
+            ),
+        };
+        if let Some(halfmove_clock) = fields.get(4) {
+            board.halfmove_clock = halfmove_clock
+                .parse()
+                .map_err(|_| format!("Invalid halfmove clock '{}'", halfmove_clock))?;
+        }
+        if let Some(fullmove_number) = fields.get(5) {
+            board.fullmove_number = fullmove_number
+                .parse()
+                .map_err(|_| format!("Invalid fullmove number '{}'", fullmove_number))?;
+        }
+
+        Ok(board)
+    }
+
+    pub fn get_to_move(&self) -> PieceColor {
+        self.to_move
+    }
+
+    pub fn get_castling_rights(&self) -> &CastlingRights {
+        &self.castling_rights
+    }
+
+    pub fn get_en_passant(&self) -> Option<Position> {
+        self.en_passant
+    }
+
+    pub fn get_halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    pub fn get_fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    pub fn is_chess960(&self) -> bool {
+        self.chess960
+    }
+
+    /// Switches castling move encoding, it's detected from FEN for non-standard setups
+    pub fn set_chess960(&mut self, chess960: bool) {
+        self.chess960 = chess960;
+    }
+
     pub fn get_piece_by_position(&self, pos: Position) -> &Option<Piece> {
         let BoardIndex(file, rank) = pos.get_indices();
         &self.board[file][rank]
@@ -45,15 +162,334 @@ impl Board {
         let BoardIndex(from_file, from_rank) = from.get_indices();
         let BoardIndex(to_file, to_rank) = to.get_indices();
 
-        let piece = self.board[from_file][from_rank].take();
-        if piece.is_some() {
-            let new_piece = piece.unwrap().copy_with_new_position(to);
-            self.board[to_file][to_rank] = Some(new_piece);
+        match self.board[from_file][from_rank].take() {
+            Some(piece) => {
+                self.board[to_file][to_rank] = Some(piece.copy_with_new_position(to));
+                Ok(())
+            }
+            None => Err("Moved from position with no piece"),
+        }
+    }
+
+    pub fn get_king_position(&self, color: PieceColor) -> Option<Position> {
+        self.get_flat_pieces()
+            .into_iter()
+            .find(|piece| piece.get_kind() == PieceKind::King && *piece.get_color() == color)
+            .map(|piece| *piece.get_position())
+    }
+
+    /// Checks if any piece of color `by` attacks given square
+    pub fn is_square_attacked(&self, pos: Position, by: PieceColor) -> bool {
+        let attacked_by = |offset: Option<Position>, kinds: &[PieceKind]| match offset {
+            Some(from) => match self.get_piece_by_position(from) {
+                Some(piece) => *piece.get_color() == by && kinds.contains(&piece.get_kind()),
+                None => false,
+            },
+            None => false,
+        };
+
+        // Pawns attack diagonally forward, so we look backwards from attacked square
+        let pawn_rank = -by.pawn_direction();
+        if attacked_by(pos.offset(-1, pawn_rank), &[PieceKind::Pawn])
+            || attacked_by(pos.offset(1, pawn_rank), &[PieceKind::Pawn])
+        {
+            return true;
+        }
+        if KNIGHT_OFFSETS
+            .iter()
+            .any(|(file, rank)| attacked_by(pos.offset(*file, *rank), &[PieceKind::Knight]))
+        {
+            return true;
+        }
+        if KING_OFFSETS
+            .iter()
+            .any(|(file, rank)| attacked_by(pos.offset(*file, *rank), &[PieceKind::King]))
+        {
+            return true;
+        }
+
+        let slides = [
+            ((1, 0), PieceKind::Rook),
+            ((-1, 0), PieceKind::Rook),
+            ((0, 1), PieceKind::Rook),
+            ((0, -1), PieceKind::Rook),
+            ((1, 1), PieceKind::Bishop),
+            ((1, -1), PieceKind::Bishop),
+            ((-1, 1), PieceKind::Bishop),
+            ((-1, -1), PieceKind::Bishop),
+        ];
+        for ((file, rank), kind) in slides {
+            let mut current = pos;
+            while let Some(next) = current.offset(file, rank) {
+                if self.get_piece_by_position(next).is_some() {
+                    if attacked_by(Some(next), &[kind, PieceKind::Queen]) {
+                        return true;
+                    }
+                    break;
+                }
+                current = next;
+            }
+        }
+        false
+    }
+
+    pub fn is_in_check(&self) -> bool {
+        match self.get_king_position(self.to_move) {
+            Some(king) => self.is_square_attacked(king, self.to_move.opposite()),
+            None => false,
+        }
+    }
 
-            Ok(())
+    /// Checks castling rights, empty squares between king and rook and that king doesn't pass through check
+    ///
+    /// Works for Chess960 too, squares the king and rook travel over need to be empty apart from themselves
+    pub fn can_castle(&self, color: PieceColor, side: CastlingSide) -> bool {
+        let rook_file = match self.castling_rights.get_rook_file(color, side) {
+            Some(file) => file,
+            None => return false,
+        };
+        let rank = color.back_rank();
+        let king_file = match self.get_king_position(color) {
+            Some(king) if king.1 == rank => king.0,
+            _ => return false,
+        };
+        let rook = Position(rook_file, rank);
+        if *self.get_piece_by_position(rook) != Some(Piece::Rook(color, rook)) {
+            return false;
+        }
+
+        let king_target = side.get_king_target_file();
+        let rook_target = side.get_rook_target_file();
+        let span = |a: char, b: char| if a < b { a..=b } else { b..=a };
+
+        let path_is_empty = span(king_file, king_target)
+            .chain(span(rook_file, rook_target))
+            .filter(|file| *file != king_file && *file != rook_file)
+            .all(|file| self.get_piece_by_position(Position(file, rank)).is_none());
+        path_is_empty
+            && span(king_file, king_target)
+                .all(|file| !self.is_square_attacked(Position(file, rank), color.opposite()))
+    }
+
+    /// Returns castling side if the move is castling, either as king move by two files or king taking own rook
+    pub fn get_castling_side(&self, mv: &Move) -> Option<CastlingSide> {
+        let king = self.get_piece_by_position(mv.from).as_ref()?;
+        if king.get_kind() != PieceKind::King {
+            return None;
+        }
+        let color = *king.get_color();
+
+        let takes_own_rook = *self.get_piece_by_position(mv.to) == Some(Piece::Rook(color, mv.to));
+        let from_file = mv.from.get_indices().0 as i32;
+        let to_file = mv.to.get_indices().0 as i32;
+        if !takes_own_rook && (mv.from.0 != 'e' || (to_file - from_file).abs() != 2) {
+            return None;
+        }
+
+        if to_file > from_file {
+            Some(CastlingSide::Kingside)
         } else {
-            Err("Moved from position with no piece")
+            Some(CastlingSide::Queenside)
+        }
+    }
+
+    /// Encodes castling move for this board, see `chess960` field
+    pub fn get_castling_move(&self, color: PieceColor, side: CastlingSide) -> Option<Move> {
+        let king = self.get_king_position(color)?;
+        let rank = color.back_rank();
+        if self.chess960 {
+            let rook_file = self.castling_rights.get_rook_file(color, side)?;
+            Some(Move::new(king, Position(rook_file, rank)))
+        } else {
+            Some(Move::new(king, Position(side.get_king_target_file(), rank)))
+        }
+    }
+
+    /// Checks whether moving piece from one square to another leaves own king safe
+    ///
+    /// Doesn't check if piece can actually move there
+    pub fn is_move_legal(&self, from: Position, to: Position) -> bool {
+        let color = match self.get_piece_by_position(from) {
+            Some(piece) => *piece.get_color(),
+            None => return false,
+        };
+        let mut board = self.clone();
+        board.make_move_unchecked(&Move::new(from, to));
+
+        match board.get_king_position(color) {
+            Some(king) => !board.is_square_attacked(king, color.opposite()),
+            None => true,
+        }
+    }
+
+    /// Zobrist hash of the position, see `zobrist::get_hash`
+    pub fn get_hash(&self) -> u64 {
+        zobrist::get_hash(self)
+    }
+
+    /// Returns all legal moves for side to move, promotions are expanded to all pieces
+    pub fn get_legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        for piece in self.get_flat_pieces() {
+            if *piece.get_color() != self.to_move {
+                continue;
+            }
+            let from = *piece.get_position();
+            for to in piece.get_legal_moves(self) {
+                if piece.get_kind() == PieceKind::Pawn && (to.1 == '1' || to.1 == '8') {
+                    for kind in [
+                        PieceKind::Queen,
+                        PieceKind::Rook,
+                        PieceKind::Bishop,
+                        PieceKind::Knight,
+                    ] {
+                        moves.push(Move::new_promotion(from, to, kind));
+                    }
+                } else {
+                    moves.push(Move::new(from, to));
+                }
+            }
         }
+        moves
+    }
+
+    /// Counts leaf nodes of the legal move tree, used for verifying move generation
+    pub fn perft(&self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = self.get_legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+
+        moves
+            .iter()
+            .map(|mv| {
+                let mut board = self.clone();
+                board.make_move_unchecked(mv);
+                board.perft(depth - 1)
+            })
+            .sum()
+    }
+
+    /// Shortest forced mate in at most `moves` moves for side to move, see `MateSearch`
+    pub fn find_mate(&self, moves: u32) -> Option<Vec<Move>> {
+        MateSearch::default().find_mate(self, moves)
+    }
+
+    pub fn is_checkmate(&self) -> bool {
+        self.is_in_check() && self.get_legal_moves().is_empty()
+    }
+
+    pub fn is_stalemate(&self) -> bool {
+        !self.is_in_check() && self.get_legal_moves().is_empty()
+    }
+
+    /// Plays a move if it's legal for the side to move
+    pub fn make_move(&mut self, mv: &Move) -> Result<(), &str> {
+        if !self.get_legal_moves().contains(mv) {
+            return Err("Illegal move");
+        }
+        self.make_move_unchecked(mv);
+        Ok(())
+    }
+
+    /// Plays a move without checking its legality
+    ///
+    /// Handles castling, en passant, promotions, castling rights and move counters
+    pub fn make_move_unchecked(&mut self, mv: &Move) {
+        let piece = match self.get_piece_by_position(mv.from) {
+            Some(piece) => *piece,
+            None => return,
+        };
+        let color = *piece.get_color();
+        let castling_side = self.get_castling_side(mv);
+        let is_capture = self.get_piece_by_position(mv.to).is_some() && castling_side.is_none();
+        let mut en_passant = None;
+
+        match piece.get_kind() {
+            PieceKind::Pawn => {
+                if !is_capture && mv.from.0 != mv.to.0 {
+                    // En passant, captured pawn stands next to the moving one
+                    let BoardIndex(file, rank) = Position(mv.to.0, mv.from.1).get_indices();
+                    self.board[file][rank] = None;
+                }
+                let from_rank = mv.from.get_indices().1 as i32;
+                let to_rank = mv.to.get_indices().1 as i32;
+                if (to_rank - from_rank).abs() == 2 {
+                    en_passant = mv.from.offset(0, color.pawn_direction());
+                }
+            }
+            PieceKind::King => {
+                if let Some(side) = castling_side {
+                    self.castle(color, side, mv);
+                }
+                self.castling_rights.remove_all(color);
+            }
+            _ => {}
+        }
+
+        for side in [CastlingSide::Kingside, CastlingSide::Queenside] {
+            for rook_color in [PieceColor::White, PieceColor::Black] {
+                if let Some(rook_file) = self.castling_rights.get_rook_file(rook_color, side) {
+                    let rook_square = Position(rook_file, rook_color.back_rank());
+                    if mv.from == rook_square || mv.to == rook_square {
+                        self.castling_rights.remove(rook_color, side);
+                    }
+                }
+            }
+        }
+
+        if castling_side.is_none() {
+            let _ = self.move_piece_from_to(mv.from, mv.to);
+        }
+        if let Some(kind) = mv.promotion {
+            self.add_piece(Piece::new(kind, color, mv.to));
+        }
+
+        if is_capture || piece.get_kind() == PieceKind::Pawn {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if color == PieceColor::Black {
+            self.fullmove_number += 1;
+        }
+        self.en_passant = en_passant;
+        self.to_move = color.opposite();
+    }
+
+    /// Passes the turn to the opponent without moving, used by null move pruning
+    pub fn make_null_move(&mut self) {
+        self.en_passant = None;
+        self.halfmove_clock += 1;
+        if self.to_move == PieceColor::Black {
+            self.fullmove_number += 1;
+        }
+        self.to_move = self.to_move.opposite();
+    }
+
+    /// Moves king and rook to their castling squares, in Chess960 they might land on each other's squares
+    fn castle(&mut self, color: PieceColor, side: CastlingSide, mv: &Move) {
+        let rank = color.back_rank();
+        let rook_file = self
+            .castling_rights
+            .get_rook_file(color, side)
+            .unwrap_or(side.get_rook_file());
+
+        for square in [mv.from, Position(rook_file, rank)] {
+            let BoardIndex(file, rank) = square.get_indices();
+            self.board[file][rank] = None;
+        }
+        self.add_piece(Piece::King(
+            color,
+            Position(side.get_king_target_file(), rank),
+        ));
+        self.add_piece(Piece::Rook(
+            color,
+            Position(side.get_rook_target_file(), rank),
+        ));
     }
 
     pub fn get_flat_pieces(&self) -> Vec<&Piece> {
@@ -64,7 +500,17 @@ impl Board {
             .collect::<Vec<&Piece>>()
     }
 
-    fn get_fen(&self) -> String {
+    /// FEN with castling rights in X-FEN, which is the same as standard FEN for standard positions
+    pub fn get_fen(&self) -> String {
+        self.get_fen_with_castling(&self.castling_rights.get_fen(self))
+    }
+
+    /// FEN with castling rights as rook files, e.g. "HAha"
+    pub fn get_shredder_fen(&self) -> String {
+        self.get_fen_with_castling(&self.castling_rights.get_shredder_fen())
+    }
+
+    fn get_fen_with_castling(&self, castling_rights: &str) -> String {
         let mut fen = String::new();
         // FEN is created from 8th rank
         // board is stored as [file][rank] ([column][row]), this way we need to do this weird loop
@@ -103,19 +549,19 @@ impl Board {
         });
         fen.push(' ');
 
-        fen.push_str(self.castling_rights.get_fen().as_str());
+        fen.push_str(castling_rights);
         fen.push(' ');
 
-        // TODO: En pessant 
-        fen.push('-');
+        match self.en_passant {
+            Some(pos) => fen.push_str(&pos.to_string()),
+            None => fen.push('-'),
+        }
         fen.push(' ');
 
-        // TODO: Half-moves since last capture or pawn move
-        fen.push('0');
+        fen.push_str(&self.halfmove_clock.to_string());
         fen.push(' ');
 
-        // TODO: No of full moves
-        fen.push('1');
+        fen.push_str(&self.fullmove_number.to_string());
 
         fen
     }
@@ -146,20 +592,7 @@ impl Board {
 
 impl std::fmt::Display for Board {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        // because we store board as [file][rank] ([column][row]) we can't simply iterate over board, because we can't return to previous lines
-        // starting from the last to show white at bottom
-        for column in (0..8).rev() {
-            for row in 0..8 {
-                let piece = &self.board[row][column];
-                let piece_char = match piece {
-                    Some(v) => v.get_char(true),
-                    None => ' ',
-                };
-                write!(f, "{}", piece_char)?;
-            }
-            writeln!(f)?;
-        }
-        Ok(())
+        write!(f, "{}", BoardRenderer::default().render(self))
     }
 }
 
@@ -225,13 +658,175 @@ mod tests {
         assert!(res.is_err())
     }
 
-    // #[test]
-    // fn it_creates_default_board_from_fen() {
-    //     let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
-    //     let fen_board = Board::new_from_fen(fen);
-    //     let default_board = Board::default();
+    #[test]
+    fn it_creates_default_board_from_fen() {
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let fen_board = Board::new_from_fen(fen).unwrap();
+        let default_board = Board::default();
 
-    // }
+        assert!(fen_board == default_board);
+    }
+
+    #[test]
+    fn it_round_trips_fen() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b Kq e3 12 34";
+        let board = Board::new_from_fen(fen).unwrap();
+
+        assert_eq!(board.get_fen(), fen);
+    }
+
+    #[test]
+    fn it_returns_error_for_invalid_fen() {
+        assert!(Board::new_from_fen("").is_err());
+        assert!(Board::new_from_fen("8/8/8/8/8/8/8 w - - 0 1").is_err());
+        assert!(Board::new_from_fen("9/8/8/8/8/8/8/8 w - - 0 1").is_err());
+        assert!(Board::new_from_fen("8/8/8/8/8/8/8/7x w - - 0 1").is_err());
+        assert!(Board::new_from_fen("8/8/8/8/8/8/8/8 x - - 0 1").is_err());
+        assert!(Board::new_from_fen("8/8/8/8/8/8/8/8 w - z9 0 1").is_err());
+    }
+
+    #[test]
+    fn it_generates_legal_moves_from_default_position() {
+        let board = Board::default();
+        assert_eq!(board.get_legal_moves().len(), 20);
+    }
+
+    #[test]
+    fn it_updates_fen_after_moves() {
+        let mut board = Board::default();
+        board
+            .make_move(&Move::new(Position('e', '2'), Position('e', '4')))
+            .unwrap();
+        assert_eq!(
+            board.get_fen(),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+        );
+
+        board
+            .make_move(&Move::new(Position('g', '8'), Position('f', '6')))
+            .unwrap();
+        assert_eq!(
+            board.get_fen(),
+            "rnbqkb1r/pppppppp/5n2/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 1 2"
+        );
+    }
+
+    #[test]
+    fn it_rejects_illegal_move() {
+        let mut board = Board::default();
+        let res = board.make_move(&Move::new(Position('e', '2'), Position('e', '5')));
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn it_castles_and_captures_en_passant() {
+        let mut board = Board::new_from_fen("r3k2r/8/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+        board
+            .make_move(&Move::new(Position('e', '5'), Position('d', '6')))
+            .unwrap();
+        assert!(board.get_piece_by_position(Position('d', '5')).is_none());
+
+        board
+            .make_move(&Move::new(Position('e', '8'), Position('c', '8')))
+            .unwrap();
+        assert!(
+            *board.get_piece_by_position(Position('d', '8'))
+                == Some(Piece::Rook(PieceColor::Black, Position('d', '8')))
+        );
+        assert_eq!(board.get_castling_rights().get_fen(&board), "KQ");
+    }
+
+    #[test]
+    fn it_makes_null_move() {
+        let mut board =
+            Board::new_from_fen("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 2")
+                .unwrap();
+        board.make_null_move();
+        assert_eq!(
+            board.get_fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 1 3"
+        );
+    }
+
+    #[test]
+    fn it_detects_checkmate_and_stalemate() {
+        let mate = Board::new_from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        assert!(mate.is_checkmate());
+
+        let stalemate = Board::new_from_fen("7k/5Q2/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        assert!(stalemate.is_stalemate());
+    }
+
+    #[test]
+    fn it_counts_perft_nodes() {
+        assert_eq!(Board::default().perft(3), 8902);
+
+        let kiwipete = Board::new_from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+        assert_eq!(kiwipete.perft(2), 2039);
+
+        let promotions =
+            Board::new_from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1")
+                .unwrap();
+        assert_eq!(promotions.perft(2), 264);
+
+        let endgame = Board::new_from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap();
+        assert_eq!(endgame.perft(3), 2812);
+    }
+
+    #[test]
+    fn it_counts_chess960_perft_nodes() {
+        let board = Board::new_from_fen(
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        )
+        .unwrap();
+        assert!(board.is_chess960());
+        assert_eq!(board.perft(3), 12189);
+
+        let board =
+            Board::new_from_fen("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9")
+                .unwrap();
+        assert_eq!(board.perft(3), 18002);
+    }
+
+    #[test]
+    fn it_castles_in_chess960() {
+        // King on b1 castles queenside by taking own rook on a1
+        let mut board = Board::new_from_fen("rk5r/8/8/8/8/8/8/RK4R1 w GAha - 0 1").unwrap();
+        board
+            .make_move(&Move::new(Position('b', '1'), Position('a', '1')))
+            .unwrap();
+
+        assert!(
+            *board.get_piece_by_position(Position('c', '1'))
+                == Some(Piece::King(PieceColor::White, Position('c', '1')))
+        );
+        assert!(
+            *board.get_piece_by_position(Position('d', '1'))
+                == Some(Piece::Rook(PieceColor::White, Position('d', '1')))
+        );
+        assert_eq!(board.get_fen(), "rk5r/8/8/8/8/8/8/2KR2R1 b kq - 1 1");
+        assert_eq!(
+            board.get_shredder_fen(),
+            "rk5r/8/8/8/8/8/8/2KR2R1 b ha - 1 1"
+        );
+    }
+
+    #[test]
+    fn it_keeps_standard_castling_fen() {
+        let board = Board::default();
+        assert!(!board.is_chess960());
+        assert_eq!(
+            board.get_shredder_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1"
+        );
+
+        let board = Board::new_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w HAh - 0 1").unwrap();
+        assert!(!board.is_chess960());
+        assert_eq!(board.get_fen(), "r3k2r/8/8/8/8/8/8/R3K2R w KQk - 0 1");
+    }
 
     #[test]
     fn it_creates_fen_from_default_position() {
@@ -245,10 +840,6 @@ mod tests {
     }
 
     fn create_empty_board() -> Board {
-        Board {
-            board: std::array::from_fn(|_| std::array::from_fn(|_| None)),
-            to_move: PieceColor::White,
-            castling_rights: CastlingRights::default()
-        }
+        Board::empty()
     }
 }
@@ -0,0 +1,214 @@
+use crate::{
+    board::Board,
+    chess_move::Move,
+    piece::PieceColor,
+    position::{BoardIndex, Position},
+};
+
+const ANSI_RESET: &str = "\x1b[0m";
+const ANSI_LIGHT_SQUARE: &str = "\x1b[48;5;180m";
+const ANSI_DARK_SQUARE: &str = "\x1b[48;5;137m";
+const ANSI_LAST_MOVE: &str = "\x1b[48;5;143m";
+const ANSI_CHECK: &str = "\x1b[48;5;160m";
+const ANSI_WHITE_PIECE: &str = "\x1b[1;97m";
+const ANSI_BLACK_PIECE: &str = "\x1b[1;30m";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Glyphs {
+    /// FEN letters, uppercase for White, "." for empty squares
+    Ascii,
+    /// Chess figurines, "·" for empty squares
+    Unicode,
+}
+
+/// Text rendering of a board for terminals and logs
+///
+/// Every square is three characters wide. Without ANSI colors highlighted squares
+/// are marked with brackets, e.g. "[P]", otherwise with background color.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoardRenderer {
+    pub glyphs: Glyphs,
+    /// Rank numbers on the left and file letters below the board
+    pub coordinates: bool,
+    /// Shows board from Black's side
+    pub flipped: bool,
+    /// Colors light and dark squares and pieces with ANSI escape codes
+    pub ansi_colors: bool,
+    /// Highlights origin and destination square of this move
+    pub last_move: Option<Move>,
+    /// Highlights king of the side to move if it's in check
+    pub highlight_check: bool,
+}
+
+impl Default for BoardRenderer {
+    fn default() -> Self {
+        BoardRenderer {
+            glyphs: Glyphs::Unicode,
+            coordinates: true,
+            flipped: false,
+            ansi_colors: false,
+            last_move: None,
+            highlight_check: true,
+        }
+    }
+}
+
+impl BoardRenderer {
+    pub fn render(&self, board: &Board) -> String {
+        let mut output = String::new();
+        let checked_king = if self.highlight_check && board.is_in_check() {
+            board.get_king_position(board.get_to_move())
+        } else {
+            None
+        };
+
+        let ranks: Vec<usize> = if self.flipped {
+            (0..8).collect()
+        } else {
+            (0..8).rev().collect()
+        };
+        let files: Vec<usize> = if self.flipped {
+            (0..8).rev().collect()
+        } else {
+            (0..8).collect()
+        };
+
+        for rank in &ranks {
+            if self.coordinates {
+                output.push_str(&format!("{} ", rank + 1));
+            }
+            for file in &files {
+                let pos = Position::new_from_indices(BoardIndex(*file, *rank));
+                let is_highlighted = self
+                    .last_move
+                    .is_some_and(|mv| mv.from == pos || mv.to == pos);
+                let is_check = checked_king == Some(pos);
+                output.push_str(&self.render_square(board, pos, is_highlighted, is_check));
+            }
+            if self.ansi_colors {
+                output.push_str(ANSI_RESET);
+            }
+            output.push('\n');
+        }
+
+        if self.coordinates {
+            output.push_str("  ");
+            for file in &files {
+                let file = Position::new_from_indices(BoardIndex(*file, 0)).0;
+                output.push_str(&format!(" {} ", file));
+            }
+            output.push('\n');
+        }
+        output
+    }
+
+    fn render_square(
+        &self,
+        board: &Board,
+        pos: Position,
+        is_highlighted: bool,
+        is_check: bool,
+    ) -> String {
+        let piece = board.get_piece_by_position(pos);
+        let glyph = match (piece, self.glyphs) {
+            (Some(piece), Glyphs::Ascii) => piece.get_char(false),
+            (Some(piece), Glyphs::Unicode) => piece.get_char(true),
+            (None, Glyphs::Ascii) if !self.ansi_colors => '.',
+            (None, Glyphs::Unicode) if !self.ansi_colors => '·',
+            (None, _) => ' ',
+        };
+
+        if !self.ansi_colors {
+            return if is_highlighted || is_check {
+                format!("[{}]", glyph)
+            } else {
+                format!(" {} ", glyph)
+            };
+        }
+
+        let BoardIndex(file, rank) = pos.get_indices();
+        let background = if is_check {
+            ANSI_CHECK
+        } else if is_highlighted {
+            ANSI_LAST_MOVE
+        } else if (file + rank) % 2 == 0 {
+            ANSI_DARK_SQUARE
+        } else {
+            ANSI_LIGHT_SQUARE
+        };
+        let foreground = match piece {
+            Some(piece) if *piece.get_color() == PieceColor::White => ANSI_WHITE_PIECE,
+            _ => ANSI_BLACK_PIECE,
+        };
+        format!("{}{} {} ", background, foreground, glyph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_renders_ascii_board_with_coordinates() {
+        let renderer = BoardRenderer {
+            glyphs: Glyphs::Ascii,
+            ..BoardRenderer::default()
+        };
+        let output = renderer.render(&Board::default());
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines[0], "8  r  n  b  q  k  b  n  r ");
+        assert_eq!(lines[4], "4  .  .  .  .  .  .  .  . ");
+        assert_eq!(lines[7], "1  R  N  B  Q  K  B  N  R ");
+        assert_eq!(lines[8], "   a  b  c  d  e  f  g  h ");
+    }
+
+    #[test]
+    fn it_renders_flipped_board_without_coordinates() {
+        let renderer = BoardRenderer {
+            glyphs: Glyphs::Unicode,
+            coordinates: false,
+            flipped: true,
+            ..BoardRenderer::default()
+        };
+        let output = renderer.render(&Board::default());
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines.len(), 8);
+        assert_eq!(lines[0], " ♖  ♘  ♗  ♔  ♕  ♗  ♘  ♖ ");
+        assert_eq!(lines[7], " ♜  ♞  ♝  ♚  ♛  ♝  ♞  ♜ ");
+    }
+
+    #[test]
+    fn it_highlights_last_move_and_check() {
+        let mut board = Board::new_from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        let mv = Move::new(Position('a', '1'), Position('a', '8'));
+        board.make_move(&mv).unwrap();
+
+        let renderer = BoardRenderer {
+            glyphs: Glyphs::Ascii,
+            coordinates: false,
+            last_move: Some(mv),
+            ..BoardRenderer::default()
+        };
+        let output = renderer.render(&board);
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines[0], "[R] .  .  . [k] .  .  . ");
+        assert_eq!(lines[7], "[.] .  .  .  K  .  .  . ");
+    }
+
+    #[test]
+    fn it_renders_ansi_colors() {
+        let renderer = BoardRenderer {
+            ansi_colors: true,
+            ..BoardRenderer::default()
+        };
+        let output = renderer.render(&Board::default());
+
+        assert!(output.contains(ANSI_LIGHT_SQUARE));
+        assert!(output.contains(ANSI_DARK_SQUARE));
+        assert!(output.contains(ANSI_RESET));
+        assert!(!output.contains(ANSI_CHECK));
+    }
+}
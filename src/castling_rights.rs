@@ -1,30 +1,88 @@
-use crate::piece::PieceColor;
+use crate::{
+    board::Board,
+    piece::{Piece, PieceColor},
+    position::Position,
+};
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum CastlingSide {
     Kingside,
     Queenside,
 }
 
+impl CastlingSide {
+    /// File the king lands on after castling
+    pub fn get_king_target_file(&self) -> char {
+        match self {
+            CastlingSide::Kingside => 'g',
+            CastlingSide::Queenside => 'c',
+        }
+    }
+
+    /// File the rook lands on after castling
+    pub fn get_rook_target_file(&self) -> char {
+        match self {
+            CastlingSide::Kingside => 'f',
+            CastlingSide::Queenside => 'd',
+        }
+    }
+
+    /// File the rook starts from in standard chess
+    pub fn get_rook_file(&self) -> char {
+        match self {
+            CastlingSide::Kingside => 'h',
+            CastlingSide::Queenside => 'a',
+        }
+    }
+}
+
+/// Castling rights stored as the file of the rook each side may castle with
+///
+/// Storing files instead of flags allows Chess960 positions, where rooks don't start on a- and h-file
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct CastlingRights {
-    white_kingside: bool,
-    white_queenside: bool,
-    black_kingside: bool,
-    black_queenside: bool,
+    white_kingside: Option<char>,
+    white_queenside: Option<char>,
+    black_kingside: Option<char>,
+    black_queenside: Option<char>,
 }
 
 impl Default for CastlingRights {
     fn default() -> Self {
         CastlingRights {
-            white_kingside: true,
-            white_queenside: true,
-            black_kingside: true,
-            black_queenside: true,
+            white_kingside: Some('h'),
+            white_queenside: Some('a'),
+            black_kingside: Some('h'),
+            black_queenside: Some('a'),
         }
     }
 }
 
 impl CastlingRights {
-    fn is_available(&self, color: PieceColor, side: CastlingSide) -> bool {
+    pub fn none() -> Self {
+        CastlingRights {
+            white_kingside: None,
+            white_queenside: None,
+            black_kingside: None,
+            black_queenside: None,
+        }
+    }
+
+    fn get_right_mut(&mut self, color: PieceColor, side: CastlingSide) -> &mut Option<char> {
+        match color {
+            PieceColor::White => match side {
+                CastlingSide::Kingside => &mut self.white_kingside,
+                CastlingSide::Queenside => &mut self.white_queenside,
+            },
+            PieceColor::Black => match side {
+                CastlingSide::Kingside => &mut self.black_kingside,
+                CastlingSide::Queenside => &mut self.black_queenside,
+            },
+        }
+    }
+
+    /// File of the rook that can castle on given side, if castling is still available
+    pub fn get_rook_file(&self, color: PieceColor, side: CastlingSide) -> Option<char> {
         match color {
             PieceColor::White => match side {
                 CastlingSide::Kingside => self.white_kingside,
@@ -37,27 +95,201 @@ impl CastlingRights {
         }
     }
 
-    pub fn get_fen(&self) -> String {
-        let mut ret = String::new();
-        if !(self.white_kingside
-            && self.white_queenside
-            && self.black_kingside
-            && self.black_queenside)
-        {
-            return String::from('-');
-        }
-        if self.white_kingside {
-            ret.push('K');
+    pub fn is_available(&self, color: PieceColor, side: CastlingSide) -> bool {
+        self.get_rook_file(color, side).is_some()
+    }
+
+    /// Checks if any castling right uses rook other than on a- or h-file
+    pub fn is_standard(&self) -> bool {
+        [PieceColor::White, PieceColor::Black].iter().all(|color| {
+            [CastlingSide::Kingside, CastlingSide::Queenside]
+                .iter()
+                .all(|side| match self.get_rook_file(*color, *side) {
+                    Some(file) => file == side.get_rook_file(),
+                    None => true,
+                })
+        })
+    }
+
+    pub fn set(&mut self, color: PieceColor, side: CastlingSide, rook_file: char) {
+        *self.get_right_mut(color, side) = Some(rook_file);
+    }
+
+    pub fn remove(&mut self, color: PieceColor, side: CastlingSide) {
+        *self.get_right_mut(color, side) = None;
+    }
+
+    pub fn remove_all(&mut self, color: PieceColor) {
+        self.remove(color, CastlingSide::Kingside);
+        self.remove(color, CastlingSide::Queenside);
+    }
+
+    /// Parses castling field of X-FEN ("KQkq", "Kkb") or Shredder-FEN ("HAha")
+    ///
+    /// K and Q refer to the outermost rook on given side of the king, so board with pieces is needed
+    pub fn from_fen(fen: &str, board: &Board) -> Result<CastlingRights, String> {
+        let mut rights = CastlingRights::none();
+        if fen == "-" {
+            return Ok(rights);
         }
-        if self.white_queenside {
-            ret.push('Q');
+
+        for c in fen.chars() {
+            let color = if c.is_ascii_uppercase() {
+                PieceColor::White
+            } else {
+                PieceColor::Black
+            };
+            let king_file = board
+                .get_king_position(color)
+                .filter(|king| king.1 == color.back_rank())
+                .map_or('e', |king| king.0);
+
+            let (side, rook_file) = match c.to_ascii_lowercase() {
+                'k' => {
+                    let side = CastlingSide::Kingside;
+                    let rook_file = get_outermost_rook_file(board, color, side, king_file);
+                    (side, rook_file.unwrap_or(side.get_rook_file()))
+                }
+                'q' => {
+                    let side = CastlingSide::Queenside;
+                    let rook_file = get_outermost_rook_file(board, color, side, king_file);
+                    (side, rook_file.unwrap_or(side.get_rook_file()))
+                }
+                file @ 'a'..='h' => {
+                    if file > king_file {
+                        (CastlingSide::Kingside, file)
+                    } else {
+                        (CastlingSide::Queenside, file)
+                    }
+                }
+                _ => return Err(format!("Invalid castling rights character '{}'", c)),
+            };
+            rights.set(color, side, rook_file);
         }
-        if self.black_kingside {
-            ret.push('k');
+        Ok(rights)
+    }
+
+    /// Castling rights in X-FEN, which is the same as standard FEN unless
+    /// castling rook isn't the outermost one, then its file is used instead
+    pub fn get_fen(&self, board: &Board) -> String {
+        self.get_fen_with(|color, side, rook_file| {
+            let king_file = board.get_king_position(color).map_or('e', |king| king.0);
+            match get_outermost_rook_file(board, color, side, king_file) {
+                Some(outermost) if outermost != rook_file => rook_file.to_ascii_uppercase(),
+                _ => match side {
+                    CastlingSide::Kingside => 'K',
+                    CastlingSide::Queenside => 'Q',
+                },
+            }
+        })
+    }
+
+    /// Castling rights in Shredder-FEN, always using rook files, e.g. "HAha"
+    pub fn get_shredder_fen(&self) -> String {
+        self.get_fen_with(|_, _, rook_file| rook_file.to_ascii_uppercase())
+    }
+
+    /// Creates FEN field with uppercase characters from `to_char`, lowercasing them for Black
+    fn get_fen_with(&self, to_char: impl Fn(PieceColor, CastlingSide, char) -> char) -> String {
+        let mut ret = String::new();
+        for color in [PieceColor::White, PieceColor::Black] {
+            for side in [CastlingSide::Kingside, CastlingSide::Queenside] {
+                if let Some(rook_file) = self.get_rook_file(color, side) {
+                    let c = to_char(color, side, rook_file);
+                    ret.push(match color {
+                        PieceColor::White => c,
+                        PieceColor::Black => c.to_ascii_lowercase(),
+                    });
+                }
+            }
         }
-        if self.black_queenside {
-            ret.push('q');
+        if ret.is_empty() {
+            return String::from('-');
         }
         ret
     }
 }
+
+/// File of the rook furthest from the king on given side of the back rank
+fn get_outermost_rook_file(
+    board: &Board,
+    color: PieceColor,
+    side: CastlingSide,
+    king_file: char,
+) -> Option<char> {
+    let rank = color.back_rank();
+    let is_rook = |file: &char| {
+        *board.get_piece_by_position(Position(*file, rank))
+            == Some(Piece::Rook(color, Position(*file, rank)))
+    };
+    match side {
+        CastlingSide::Kingside => ('a'..='h')
+            .rev()
+            .filter(|file| *file > king_file)
+            .find(is_rook),
+        CastlingSide::Queenside => ('a'..='h').filter(|file| *file < king_file).find(is_rook),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_creates_fen_for_partial_rights() {
+        let board = Board::default();
+        let mut rights = CastlingRights::default();
+        rights.remove(PieceColor::White, CastlingSide::Queenside);
+        rights.remove_all(PieceColor::Black);
+
+        assert_eq!(rights.get_fen(&board), "K");
+        assert_eq!(CastlingRights::none().get_fen(&board), "-");
+        assert_eq!(CastlingRights::default().get_fen(&board), "KQkq");
+    }
+
+    #[test]
+    fn it_parses_fen() {
+        let board = Board::default();
+        let rights = CastlingRights::from_fen("Kq", &board).unwrap();
+
+        assert!(rights.is_available(PieceColor::White, CastlingSide::Kingside));
+        assert!(!rights.is_available(PieceColor::White, CastlingSide::Queenside));
+        assert!(!rights.is_available(PieceColor::Black, CastlingSide::Kingside));
+        assert!(rights.is_available(PieceColor::Black, CastlingSide::Queenside));
+        assert!(CastlingRights::from_fen("-", &board).unwrap() == CastlingRights::none());
+        assert!(CastlingRights::from_fen("KX", &board).is_err());
+    }
+
+    #[test]
+    fn it_parses_shredder_fen() {
+        let board =
+            Board::new_from_fen("bqnbrkrn/pppppppp/8/8/8/8/PPPPPPPP/BQNBRKRN w - - 0 1").unwrap();
+        let rights = CastlingRights::from_fen("GEge", &board).unwrap();
+
+        assert_eq!(
+            rights.get_rook_file(PieceColor::White, CastlingSide::Kingside),
+            Some('g')
+        );
+        assert_eq!(
+            rights.get_rook_file(PieceColor::Black, CastlingSide::Queenside),
+            Some('e')
+        );
+        assert_eq!(rights.get_shredder_fen(), "GEge");
+        assert_eq!(rights.get_fen(&board), "KQkq");
+    }
+
+    #[test]
+    fn it_uses_file_letters_for_inner_rooks_in_x_fen() {
+        // Both white rooks are on the kingside, castling with the inner one needs its file
+        let board = Board::new_from_fen("4k3/8/8/8/8/8/8/4K1RR w - - 0 1").unwrap();
+        let rights = CastlingRights::from_fen("G", &board).unwrap();
+
+        assert_eq!(rights.get_fen(&board), "G");
+        assert_eq!(
+            CastlingRights::from_fen("K", &board)
+                .unwrap()
+                .get_rook_file(PieceColor::White, CastlingSide::Kingside),
+            Some('h')
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{piece::PieceKind, position::Position};
+
+/// Move from one square to another
+///
+/// Castling is stored as king move (e1g1), or as king taking own rook (e1h1) on Chess960 boards.
+/// En passant is stored as pawn move to the en passant square
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Move {
+    pub from: Position,
+    pub to: Position,
+    pub promotion: Option<PieceKind>,
+}
+
+impl Move {
+    pub fn new(from: Position, to: Position) -> Move {
+        Move {
+            from,
+            to,
+            promotion: None,
+        }
+    }
+
+    pub fn new_promotion(from: Position, to: Position, promotion: PieceKind) -> Move {
+        Move {
+            from,
+            to,
+            promotion: Some(promotion),
+        }
+    }
+
+    /// Parses move in long algebraic (UCI) notation, e.g. "e2e4" or "e7e8q"
+    pub fn from_uci(uci: &str) -> Option<Move> {
+        if !uci.is_ascii() || !(4..=5).contains(&uci.len()) {
+            return None;
+        }
+        let from = Position::parse(&uci[0..2])?;
+        let to = Position::parse(&uci[2..4])?;
+        let promotion = match uci[4..].chars().next() {
+            Some(c) if c.is_ascii_lowercase() => match PieceKind::from_char(c)? {
+                PieceKind::King | PieceKind::Pawn => return None,
+                kind => Some(kind),
+            },
+            Some(_) => return None,
+            None => None,
+        };
+
+        Some(Move {
+            from,
+            to,
+            promotion,
+        })
+    }
+
+    pub fn get_uci(&self) -> String {
+        let mut uci = format!("{}{}", self.from, self.to);
+        if let Some(kind) = self.promotion {
+            uci.push(kind.get_char().to_ascii_lowercase());
+        }
+        uci
+    }
+}
+
+impl std::fmt::Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.get_uci())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_uci_move() {
+        let mv = Move::from_uci("e2e4").unwrap();
+        assert!(mv == Move::new(Position('e', '2'), Position('e', '4')));
+
+        let promotion = Move::from_uci("a7a8n").unwrap();
+        assert_eq!(promotion.promotion, Some(PieceKind::Knight));
+    }
+
+    #[test]
+    fn it_rejects_invalid_uci_move() {
+        assert!(Move::from_uci("e2").is_none());
+        assert!(Move::from_uci("e2e9").is_none());
+        assert!(Move::from_uci("a7a8k").is_none());
+        assert!(Move::from_uci("a7a8Q").is_none());
+    }
+
+    #[test]
+    fn it_returns_uci_string() {
+        assert_eq!(
+            Move::new_promotion(Position('b', '2'), Position('b', '1'), PieceKind::Queen).get_uci(),
+            "b2b1q"
+        );
+    }
+}
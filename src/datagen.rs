@@ -0,0 +1,447 @@
+use std::{
+    io::Write,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    board::Board,
+    chess_move::Move,
+    eval,
+    move_ordering::is_tactical,
+    piece::{PieceColor, PieceKind},
+    position::{BoardIndex, Position},
+    rng::Rng,
+    search::{Score, Search, SearchLimits},
+};
+
+/// Size of a position in the binary format
+///
+/// Occupancy bitboard with a1 as the lowest bit, 4-bit piece codes in occupancy order, score
+/// from White's point of view as i16, result (0 Black wins, 1 draw, 2 White wins) and side
+/// to move (0 White, 1 Black). Everything is little endian, castling and en passant are lost.
+pub const ENTRY_SIZE: usize = 28;
+
+/// Piece codes of the binary format
+const PIECE_CHARS: [char; 12] = ['P', 'N', 'B', 'R', 'Q', 'K', 'p', 'n', 'b', 'r', 'q', 'k'];
+/// Openings scored beyond this after the random moves are rerolled
+const MAX_OPENING_SCORE: i32 = 400;
+/// Games still going after this many plies are drawn
+const MAX_GAME_PLIES: usize = 400;
+/// Positions where search and static evaluation differ by more than this are tactical
+const TACTICAL_MARGIN: i32 = 150;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataFormat {
+    /// One "FEN | score | result" line per position
+    Text,
+    /// ENTRY_SIZE bytes per position
+    Binary,
+}
+
+/// Self-play settings, skipped positions are played but not recorded
+#[derive(Clone, Debug, PartialEq)]
+pub struct DatagenOptions {
+    pub games: usize,
+    /// Nodes searched for every move
+    pub nodes: u64,
+    /// Uniformly random moves played from the starting position before the search takes over
+    pub random_plies: u32,
+    pub seed: u64,
+    /// Skip positions where the side to move is in check
+    pub skip_in_check: bool,
+    /// Skip positions where the best move is a capture or promotion
+    pub skip_captures: bool,
+    /// Skip positions where the search score is far from the static evaluation
+    pub skip_tactical: bool,
+}
+
+impl Default for DatagenOptions {
+    fn default() -> Self {
+        DatagenOptions {
+            games: 100,
+            nodes: 5000,
+            random_plies: 8,
+            seed: 0,
+            skip_in_check: false,
+            skip_captures: false,
+            skip_tactical: false,
+        }
+    }
+}
+
+/// Position labelled with search score and game result
+#[derive(Clone, Debug, PartialEq)]
+pub struct DataEntry {
+    pub board: Board,
+    /// Centipawns from White's point of view
+    pub score: i32,
+    /// 1.0 when White won, 0.5 for a draw, 0.0 when Black won
+    pub result: f64,
+}
+
+impl DataEntry {
+    pub fn to_text(&self) -> String {
+        format!(
+            "{} | {} | {:.1}",
+            self.board.get_fen(),
+            self.score,
+            self.result
+        )
+    }
+
+    pub fn parse_text(line: &str) -> Result<DataEntry, String> {
+        let [fen, score, result] = line.split('|').map(str::trim).collect::<Vec<_>>()[..] else {
+            return Err(format!("Invalid data line '{}'", line));
+        };
+        Ok(DataEntry {
+            board: Board::new_from_fen(fen)?,
+            score: score
+                .parse()
+                .map_err(|_| format!("Invalid score '{}'", score))?,
+            result: parse_result(result)?,
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0; ENTRY_SIZE];
+        let mut occupancy: u64 = 0;
+        let mut count = 0;
+        for square in 0..64 {
+            let Some(piece) = self.board.get_piece_by_position(get_position(square)) else {
+                continue;
+            };
+            let code = PIECE_CHARS
+                .iter()
+                .position(|&c| c == piece.get_char(false))
+                .expect("pieces have FEN characters") as u8;
+            occupancy |= 1 << square;
+            bytes[8 + count / 2] |= code << (4 * (count % 2));
+            count += 1;
+        }
+        bytes[..8].copy_from_slice(&occupancy.to_le_bytes());
+        let score = self.score.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+        bytes[24..26].copy_from_slice(&score.to_le_bytes());
+        bytes[26] = (self.result * 2.0).round() as u8;
+        bytes[27] = match self.board.get_to_move() {
+            PieceColor::White => 0,
+            PieceColor::Black => 1,
+        };
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<DataEntry, String> {
+        let bytes: &[u8; ENTRY_SIZE] = bytes
+            .try_into()
+            .map_err(|_| format!("Entry must have {} bytes", ENTRY_SIZE))?;
+        let occupancy = u64::from_le_bytes(bytes[..8].try_into().unwrap());
+        if occupancy.count_ones() > 32 {
+            return Err(String::from("Entry has more than 32 pieces"));
+        }
+
+        let mut grid = [[None; 8]; 8];
+        for (i, square) in (0..64)
+            .filter(|square| occupancy & (1 << square) != 0)
+            .enumerate()
+        {
+            let code = (bytes[8 + i / 2] >> (4 * (i % 2))) & 0xf;
+            let c = *PIECE_CHARS
+                .get(code as usize)
+                .ok_or_else(|| format!("Invalid piece code {}", code))?;
+            grid[square / 8][square % 8] = Some(c);
+        }
+        let to_move = match bytes[27] {
+            0 => 'w',
+            1 => 'b',
+            side => return Err(format!("Invalid side to move {}", side)),
+        };
+        if bytes[26] > 2 {
+            return Err(format!("Invalid result {}", bytes[26]));
+        }
+
+        let placement: Vec<String> = grid
+            .iter()
+            .rev()
+            .map(|rank| {
+                let mut text = String::new();
+                let mut empty = 0;
+                for square in rank {
+                    match square {
+                        Some(c) => {
+                            if empty > 0 {
+                                text.push_str(&empty.to_string());
+                                empty = 0;
+                            }
+                            text.push(*c);
+                        }
+                        None => empty += 1,
+                    }
+                }
+                if empty > 0 {
+                    text.push_str(&empty.to_string());
+                }
+                text
+            })
+            .collect();
+        Ok(DataEntry {
+            board: Board::new_from_fen(&format!("{} {} - - 0 1", placement.join("/"), to_move))?,
+            score: i16::from_le_bytes([bytes[24], bytes[25]]) as i32,
+            result: bytes[26] as f64 / 2.0,
+        })
+    }
+
+    pub fn write(&self, format: DataFormat, output: &mut impl Write) -> Result<(), String> {
+        match format {
+            DataFormat::Text => writeln!(output, "{}", self.to_text()),
+            DataFormat::Binary => output.write_all(&self.to_bytes()),
+        }
+        .map_err(|e| e.to_string())
+    }
+}
+
+/// Game result as 1.0, 0.5 or 0.0, also accepts PGN results like "1-0"
+pub fn parse_result(result: &str) -> Result<f64, String> {
+    match result.trim_matches('"') {
+        "1" | "1.0" | "1-0" => Ok(1.0),
+        "0.5" | "1/2-1/2" => Ok(0.5),
+        "0" | "0.0" | "0-1" => Ok(0.0),
+        _ => Err(format!("Invalid result '{}'", result)),
+    }
+}
+
+/// Outcome of generating data
+#[derive(Clone, Debug, PartialEq)]
+pub struct DatagenResult {
+    pub games: usize,
+    pub positions: usize,
+    pub time: Duration,
+}
+
+/// Plays self-play games searching a fixed number of nodes per move and writes recorded
+/// positions of each game once it's over, calling `on_game` with its result and position count
+///
+/// Same options and seed always produce the same data.
+pub fn run_datagen(
+    options: &DatagenOptions,
+    format: DataFormat,
+    output: &mut impl Write,
+    mut on_game: impl FnMut(f64, usize),
+) -> Result<DatagenResult, String> {
+    let start = Instant::now();
+    let mut rng = Rng::new(options.seed);
+    let mut search = Search::new();
+    let mut result = DatagenResult {
+        games: 0,
+        positions: 0,
+        time: Duration::ZERO,
+    };
+
+    for _ in 0..options.games {
+        search.clear_hash();
+        search.clear_heuristics();
+        let (entries, game_result) = play_game(&mut search, &mut rng, options);
+        for entry in &entries {
+            entry.write(format, output)?;
+        }
+        result.games += 1;
+        result.positions += entries.len();
+        on_game(game_result, entries.len());
+    }
+    output.flush().map_err(|e| e.to_string())?;
+    result.time = start.elapsed();
+    Ok(result)
+}
+
+fn play_game(
+    search: &mut Search,
+    rng: &mut Rng,
+    options: &DatagenOptions,
+) -> (Vec<DataEntry>, f64) {
+    let limits = SearchLimits {
+        nodes: Some(options.nodes),
+        ..SearchLimits::default()
+    };
+    let mut board = get_random_opening(search, rng, options.random_plies, &limits);
+    let mut history = Vec::new();
+    let mut recorded = Vec::new();
+
+    let result = loop {
+        if let Some(result) = get_result(&board, &history) {
+            break result;
+        }
+        search.set_history(history.clone());
+        let info = search.run(&board, &limits, |_| {});
+        let Some(mv) = info.get_best_move() else {
+            break 0.5;
+        };
+        if let Score::Centipawns(score) = info.score {
+            if !is_skipped(&board, &mv, score, options) {
+                let white_score = match board.get_to_move() {
+                    PieceColor::White => score,
+                    PieceColor::Black => -score,
+                };
+                recorded.push((board.clone(), white_score));
+            }
+        }
+        history.push(board.get_hash());
+        board.make_move_unchecked(&mv);
+    };
+
+    let entries = recorded
+        .into_iter()
+        .map(|(board, score)| DataEntry {
+            board,
+            score,
+            result,
+        })
+        .collect();
+    (entries, result)
+}
+
+/// Position after random moves which the search doesn't consider lost for either side
+fn get_random_opening(
+    search: &mut Search,
+    rng: &mut Rng,
+    plies: u32,
+    limits: &SearchLimits,
+) -> Board {
+    loop {
+        let mut board = Board::default();
+        for _ in 0..plies {
+            let moves = board.get_legal_moves();
+            if moves.is_empty() {
+                break;
+            }
+            board.make_move_unchecked(&moves[rng.below(moves.len())]);
+        }
+        if board.get_legal_moves().is_empty() {
+            continue;
+        }
+        let info = search.run(&board, limits, |_| {});
+        if matches!(info.score, Score::Centipawns(score) if score.abs() <= MAX_OPENING_SCORE) {
+            return board;
+        }
+    }
+}
+
+fn is_skipped(board: &Board, mv: &Move, score: i32, options: &DatagenOptions) -> bool {
+    (options.skip_in_check && board.is_in_check())
+        || (options.skip_captures && is_tactical(board, mv))
+        || (options.skip_tactical && (score - eval::evaluate(board)).abs() > TACTICAL_MARGIN)
+}
+
+/// Result from White's point of view when the game is over, `history` are hashes of
+/// positions before the current one
+fn get_result(board: &Board, history: &[u64]) -> Option<f64> {
+    if board.is_checkmate() {
+        return Some(match board.get_to_move() {
+            PieceColor::White => 0.0,
+            PieceColor::Black => 1.0,
+        });
+    }
+    let hash = board.get_hash();
+    let draw = board.is_stalemate()
+        || board.get_halfmove_clock() >= 100
+        || history.len() >= MAX_GAME_PLIES
+        || history.iter().filter(|&&previous| previous == hash).count() >= 2
+        || is_insufficient_material(board);
+    draw.then_some(0.5)
+}
+
+/// Only kings, or kings and a single minor piece
+fn is_insufficient_material(board: &Board) -> bool {
+    let pieces: Vec<PieceKind> = board
+        .get_flat_pieces()
+        .iter()
+        .map(|piece| piece.get_kind())
+        .filter(|&kind| kind != PieceKind::King)
+        .collect();
+    matches!(pieces[..], [] | [PieceKind::Knight] | [PieceKind::Bishop])
+}
+
+fn get_position(square: usize) -> Position {
+    Position::new_from_indices(BoardIndex(square % 8, square / 8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_round_trips_entries() {
+        let entry = DataEntry {
+            board: Board::new_from_fen(
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b - - 0 1",
+            )
+            .unwrap(),
+            score: -35,
+            result: 0.5,
+        };
+        assert_eq!(
+            entry.to_text(),
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b - - 0 1 | -35 | 0.5"
+        );
+        assert_eq!(DataEntry::parse_text(&entry.to_text()), Ok(entry.clone()));
+        assert_eq!(DataEntry::from_bytes(&entry.to_bytes()), Ok(entry));
+
+        assert!(DataEntry::parse_text("8/8/8/8/8/8/8/8 w - - 0 1 | 10").is_err());
+        assert_eq!(parse_result("1-0"), Ok(1.0));
+    }
+
+    #[test]
+    fn it_generates_repeatable_self_play_data() {
+        let options = DatagenOptions {
+            games: 1,
+            nodes: 50,
+            ..DatagenOptions::default()
+        };
+        let mut text = Vec::new();
+        let mut results = Vec::new();
+        let result = run_datagen(&options, DataFormat::Text, &mut text, |result, _| {
+            results.push(result)
+        })
+        .unwrap();
+
+        let text = String::from_utf8(text).unwrap();
+        let entries: Vec<DataEntry> = text
+            .lines()
+            .map(|line| DataEntry::parse_text(line).unwrap())
+            .collect();
+        assert_eq!(result.games, 1);
+        assert_eq!(result.positions, entries.len());
+        assert!(entries.len() > 10);
+        assert_eq!(entries.last().unwrap().result, results[0]);
+
+        let mut binary = Vec::new();
+        run_datagen(&options, DataFormat::Binary, &mut binary, |_, _| {}).unwrap();
+        assert_eq!(binary.len(), entries.len() * ENTRY_SIZE);
+        assert_eq!(
+            DataEntry::from_bytes(&binary[..ENTRY_SIZE]).unwrap().score,
+            entries[0].score
+        );
+    }
+
+    #[test]
+    fn it_skips_filtered_positions() {
+        let options = DatagenOptions {
+            skip_in_check: true,
+            skip_captures: true,
+            ..DatagenOptions::default()
+        };
+        let board = Board::new_from_fen("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1").unwrap();
+        let capture = Move::from_uci("d2d5").unwrap();
+        let quiet = Move::from_uci("e1e2").unwrap();
+        assert!(is_skipped(&board, &capture, 0, &options));
+        assert!(!is_skipped(&board, &quiet, 0, &options));
+
+        let check = Board::new_from_fen("4k3/8/8/8/8/8/4r3/4K3 w - - 0 1").unwrap();
+        assert!(is_skipped(
+            &check,
+            &Move::from_uci("e1d1").unwrap(),
+            0,
+            &options
+        ));
+        assert!(is_insufficient_material(
+            &Board::new_from_fen("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1").unwrap()
+        ));
+    }
+}
@@ -0,0 +1,199 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{
+    board::Board,
+    chess_move::Move,
+    search::{Search, SearchInfo, SearchLimits},
+};
+
+/// Game state and a search running in the background, shared by the protocol front-ends
+pub struct Engine {
+    board: Board,
+    /// Positions before the current one, oldest first
+    previous: Vec<Board>,
+    /// Taken by the search thread while it runs
+    search: Option<Search>,
+    thread: Option<JoinHandle<Search>>,
+    stop: Arc<AtomicBool>,
+    ponder: Arc<AtomicBool>,
+    /// Set by `stop`, ends infinite and pondering searches waiting to report their result
+    stopped: Arc<AtomicBool>,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        let search = Search::new();
+        Engine {
+            board: Board::default(),
+            previous: Vec::new(),
+            stop: search.get_stop_handle(),
+            ponder: search.get_ponder_handle(),
+            search: Some(search),
+            thread: None,
+            stopped: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl Engine {
+    pub fn new() -> Engine {
+        Engine::default()
+    }
+
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Positions before the current one, oldest first
+    pub fn get_previous_boards(&self) -> &[Board] {
+        &self.previous
+    }
+
+    /// Search used by the next `start_search`, waits for a running search to finish
+    pub fn get_search(&mut self) -> &mut Search {
+        self.wait();
+        self.search.as_mut().expect("search is back after wait")
+    }
+
+    /// Forgets everything learned in previous games and sets up starting position
+    pub fn new_game(&mut self) {
+        let search = self.get_search();
+        search.clear_hash();
+        search.clear_heuristics();
+        self.set_position(Board::default());
+    }
+
+    pub fn set_position(&mut self, board: Board) {
+        self.wait();
+        self.board = board;
+        self.previous.clear();
+    }
+
+    pub fn make_move(&mut self, mv: &Move) -> Result<(), String> {
+        self.wait();
+        let mut board = self.board.clone();
+        board.make_move(mv).map_err(String::from)?;
+        self.previous
+            .push(std::mem::replace(&mut self.board, board));
+        Ok(())
+    }
+
+    /// Takes back the last move, returns false when there is none
+    pub fn undo_move(&mut self) -> bool {
+        self.wait();
+        match self.previous.pop() {
+            Some(board) => {
+                self.board = board;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Searches current position on a background thread
+    ///
+    /// `on_iteration` is called after each completed depth and `on_finish` with the final
+    /// result. Infinite and pondering searches report the result only after `stop`, or after
+    /// `ponderhit` when their limits are reached.
+    pub fn start_search(
+        &mut self,
+        limits: SearchLimits,
+        on_iteration: impl FnMut(&SearchInfo) + Send + 'static,
+        on_finish: impl FnOnce(&SearchInfo) + Send + 'static,
+    ) {
+        self.wait();
+        let mut search = self.search.take().expect("search is back after wait");
+        search.set_history(self.previous.iter().map(Board::get_hash).collect());
+
+        self.stop.store(false, Ordering::Relaxed);
+        self.stopped.store(false, Ordering::Relaxed);
+        self.ponder.store(limits.ponder, Ordering::Relaxed);
+        let board = self.board.clone();
+        let (ponder, stopped) = (Arc::clone(&self.ponder), Arc::clone(&self.stopped));
+        self.thread = Some(thread::spawn(move || {
+            let info = search.run(&board, &limits, on_iteration);
+            while (limits.infinite || ponder.load(Ordering::Relaxed))
+                && !stopped.load(Ordering::Relaxed)
+            {
+                thread::sleep(Duration::from_millis(1));
+            }
+            on_finish(&info);
+            search
+        }));
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.thread
+            .as_ref()
+            .is_some_and(|thread| !thread.is_finished())
+    }
+
+    /// Ends running search, which then reports its result
+    pub fn stop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        self.stop.store(true, Ordering::Relaxed);
+        self.wait();
+    }
+
+    /// Opponent played the expected move, pondering search continues as a normal one
+    pub fn ponderhit(&self) {
+        self.ponder.store(false, Ordering::Relaxed);
+    }
+
+    /// Blocks until running search finishes
+    pub fn wait(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            self.search = Some(thread.join().expect("search thread doesn't panic"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn it_plays_and_undoes_moves() {
+        let mut engine = Engine::new();
+        engine.make_move(&Move::from_uci("e2e4").unwrap()).unwrap();
+        assert!(engine.make_move(&Move::from_uci("e2e4").unwrap()).is_err());
+        assert_eq!(engine.get_previous_boards(), &[Board::default()]);
+
+        assert!(engine.undo_move());
+        assert_eq!(engine.get_board(), &Board::default());
+        assert!(!engine.undo_move());
+    }
+
+    #[test]
+    fn it_searches_in_background_until_stopped() {
+        let mut engine = Engine::new();
+        let (sender, receiver) = mpsc::channel();
+        let limits = SearchLimits {
+            depth: Some(1),
+            infinite: true,
+            ..SearchLimits::default()
+        };
+        engine.start_search(
+            limits,
+            |_| {},
+            move |info| sender.send(info.get_best_move()).unwrap(),
+        );
+
+        // Depth is reached, but infinite search waits for stop
+        thread::sleep(Duration::from_millis(50));
+        assert!(engine.is_searching());
+        assert!(receiver.try_recv().is_err());
+
+        engine.stop();
+        assert!(!engine.is_searching());
+        assert!(receiver.recv().unwrap().is_some());
+    }
+}
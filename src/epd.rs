@@ -0,0 +1,399 @@
+use crate::{
+    board::Board,
+    chess_move::Move,
+    san::{move_to_san, san_to_move},
+};
+
+/// Single EPD operation, known opcodes are parsed into typed values
+#[derive(Clone, Debug, PartialEq)]
+pub enum EpdOperation {
+    /// bm
+    BestMoves(Vec<Move>),
+    /// am
+    AvoidMoves(Vec<Move>),
+    /// pm
+    PredictedMove(Move),
+    /// pv, moves are played one after another
+    PredictedVariation(Vec<Move>),
+    /// id
+    Id(String),
+    /// c0 to c9
+    Comment(u8, String),
+    /// ce
+    CentipawnEvaluation(i32),
+    /// dm
+    DirectMate(u32),
+    /// acd
+    AnalysisDepth(u32),
+    /// acn
+    AnalysisNodes(u64),
+    /// hmvc
+    HalfmoveClock(u32),
+    /// fmvn
+    FullmoveNumber(u32),
+    /// Any other opcode with its operands
+    Other(String, Vec<String>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Epd {
+    board: Board,
+    pub operations: Vec<EpdOperation>,
+}
+
+impl Epd {
+    pub fn new(board: Board) -> Epd {
+        Epd {
+            board,
+            operations: Vec::new(),
+        }
+    }
+
+    /// Board is created from four FEN fields, move counters come from hmvc and fmvn operations
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn get_best_moves(&self) -> Option<&[Move]> {
+        self.operations
+            .iter()
+            .find_map(|operation| match operation {
+                EpdOperation::BestMoves(moves) => Some(moves.as_slice()),
+                _ => None,
+            })
+    }
+
+    pub fn get_avoid_moves(&self) -> Option<&[Move]> {
+        self.operations
+            .iter()
+            .find_map(|operation| match operation {
+                EpdOperation::AvoidMoves(moves) => Some(moves.as_slice()),
+                _ => None,
+            })
+    }
+
+    pub fn get_id(&self) -> Option<&str> {
+        self.operations
+            .iter()
+            .find_map(|operation| match operation {
+                EpdOperation::Id(id) => Some(id.as_str()),
+                _ => None,
+            })
+    }
+
+    /// Parses single EPD record
+    pub fn parse(epd: &str) -> Result<Epd, String> {
+        let epd = epd.trim();
+        let mut fields = Vec::new();
+        let mut rest = epd;
+        for _ in 0..4 {
+            rest = rest.trim_start();
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            if end == 0 {
+                return Err(format!("EPD needs 4 position fields: '{}'", epd));
+            }
+            fields.push(&rest[..end]);
+            rest = &rest[end..];
+        }
+
+        let raw_operations = tokenize_operations(rest)?;
+        let mut halfmove_clock = 0;
+        let mut fullmove_number = 1;
+        for (opcode, operands) in &raw_operations {
+            let counter = || -> Result<u32, String> {
+                operands
+                    .first()
+                    .and_then(|operand| operand.parse().ok())
+                    .ok_or_else(|| format!("Invalid {} operand", opcode))
+            };
+            match opcode.as_str() {
+                "hmvc" => halfmove_clock = counter()?,
+                "fmvn" => fullmove_number = counter()?,
+                _ => {}
+            }
+        }
+
+        let board = Board::new_from_fen(&format!(
+            "{} {} {}",
+            fields.join(" "),
+            halfmove_clock,
+            fullmove_number
+        ))?;
+        let operations = raw_operations
+            .into_iter()
+            .map(|(opcode, operands)| parse_operation(&board, opcode, operands))
+            .collect::<Result<Vec<EpdOperation>, String>>()?;
+
+        Ok(Epd { board, operations })
+    }
+
+    /// Parses EPD records one per line, skipping empty lines
+    pub fn parse_all(epd: &str) -> Result<Vec<Epd>, String> {
+        epd.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(Epd::parse)
+            .collect()
+    }
+
+    pub fn to_epd(&self) -> String {
+        let fen = self.board.get_fen();
+        let mut epd = fen.split(' ').take(4).collect::<Vec<&str>>().join(" ");
+
+        for operation in &self.operations {
+            let (opcode, operands) = self.format_operation(operation);
+            epd.push(' ');
+            epd.push_str(&opcode);
+            for operand in operands {
+                epd.push(' ');
+                epd.push_str(&operand);
+            }
+            epd.push(';');
+        }
+        epd
+    }
+
+    fn format_operation(&self, operation: &EpdOperation) -> (String, Vec<String>) {
+        let san_list = |moves: &[Move]| -> Vec<String> {
+            moves
+                .iter()
+                .map(|mv| move_to_san(&self.board, mv))
+                .collect()
+        };
+
+        match operation {
+            EpdOperation::BestMoves(moves) => (String::from("bm"), san_list(moves)),
+            EpdOperation::AvoidMoves(moves) => (String::from("am"), san_list(moves)),
+            EpdOperation::PredictedMove(mv) => {
+                (String::from("pm"), vec![move_to_san(&self.board, mv)])
+            }
+            EpdOperation::PredictedVariation(moves) => {
+                let mut board = self.board.clone();
+                let mut operands = Vec::new();
+                for mv in moves {
+                    operands.push(move_to_san(&board, mv));
+                    board.make_move_unchecked(mv);
+                }
+                (String::from("pv"), operands)
+            }
+            EpdOperation::Id(id) => (String::from("id"), vec![quote(id)]),
+            EpdOperation::Comment(index, comment) => (format!("c{}", index), vec![quote(comment)]),
+            EpdOperation::CentipawnEvaluation(score) => {
+                (String::from("ce"), vec![score.to_string()])
+            }
+            EpdOperation::DirectMate(moves) => (String::from("dm"), vec![moves.to_string()]),
+            EpdOperation::AnalysisDepth(depth) => (String::from("acd"), vec![depth.to_string()]),
+            EpdOperation::AnalysisNodes(nodes) => (String::from("acn"), vec![nodes.to_string()]),
+            EpdOperation::HalfmoveClock(clock) => (String::from("hmvc"), vec![clock.to_string()]),
+            EpdOperation::FullmoveNumber(number) => {
+                (String::from("fmvn"), vec![number.to_string()])
+            }
+            EpdOperation::Other(opcode, operands) => (
+                opcode.clone(),
+                operands
+                    .iter()
+                    .map(|operand| {
+                        if operand.is_empty() || operand.contains([' ', ';', '"', '\\']) {
+                            quote(operand)
+                        } else {
+                            operand.clone()
+                        }
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Quotes a string operand, escaping backslashes and quotes like PGN tag values
+fn quote(value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("\"{}\"", escaped)
+}
+
+/// Splits operations on semicolons, respecting quoted operands
+fn tokenize_operations(operations: &str) -> Result<Vec<(String, Vec<String>)>, String> {
+    let mut result = Vec::new();
+    let mut tokens: Vec<String> = Vec::new();
+    let mut chars = operations.chars().peekable();
+
+    loop {
+        match chars.next() {
+            Some('"') => {
+                let mut token = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => match chars.next() {
+                            Some(c) => token.push(c),
+                            None => return Err(String::from("Unterminated EPD string operand")),
+                        },
+                        Some('"') => break,
+                        Some(c) => token.push(c),
+                        None => return Err(String::from("Unterminated EPD string operand")),
+                    }
+                }
+                tokens.push(token);
+            }
+            Some(';') | None => {
+                if !tokens.is_empty() {
+                    let opcode = tokens.remove(0);
+                    result.push((opcode, std::mem::take(&mut tokens)));
+                }
+                if chars.peek().is_none() {
+                    break;
+                }
+            }
+            Some(c) if c.is_whitespace() => {}
+            Some(c) => {
+                let mut token = String::from(c);
+                while let Some(c) = chars.peek() {
+                    if c.is_whitespace() || *c == ';' || *c == '"' {
+                        break;
+                    }
+                    token.push(*c);
+                    chars.next();
+                }
+                tokens.push(token);
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn parse_operation(
+    board: &Board,
+    opcode: String,
+    operands: Vec<String>,
+) -> Result<EpdOperation, String> {
+    let san_list = || -> Result<Vec<Move>, String> {
+        operands.iter().map(|san| san_to_move(board, san)).collect()
+    };
+    fn number<T: std::str::FromStr>(opcode: &str, operands: &[String]) -> Result<T, String> {
+        match operands {
+            [operand] => operand
+                .parse()
+                .map_err(|_| format!("Invalid {} operand '{}'", opcode, operand)),
+            _ => Err(format!("{} needs exactly one operand", opcode)),
+        }
+    }
+    fn string(opcode: &str, operands: &[String]) -> Result<String, String> {
+        match operands {
+            [operand] => Ok(operand.clone()),
+            _ => Err(format!("{} needs exactly one operand", opcode)),
+        }
+    }
+
+    let operation = match opcode.as_str() {
+        "bm" => EpdOperation::BestMoves(san_list()?),
+        "am" => EpdOperation::AvoidMoves(san_list()?),
+        "pm" => match san_list()?.as_slice() {
+            [mv] => EpdOperation::PredictedMove(*mv),
+            _ => return Err(String::from("pm needs exactly one operand")),
+        },
+        "pv" => {
+            let mut board = board.clone();
+            let mut moves = Vec::new();
+            for san in &operands {
+                let mv = san_to_move(&board, san)?;
+                board.make_move_unchecked(&mv);
+                moves.push(mv);
+            }
+            EpdOperation::PredictedVariation(moves)
+        }
+        "id" => EpdOperation::Id(string(&opcode, &operands)?),
+        "ce" => EpdOperation::CentipawnEvaluation(number(&opcode, &operands)?),
+        "dm" => EpdOperation::DirectMate(number(&opcode, &operands)?),
+        "acd" => EpdOperation::AnalysisDepth(number(&opcode, &operands)?),
+        "acn" => EpdOperation::AnalysisNodes(number(&opcode, &operands)?),
+        "hmvc" => EpdOperation::HalfmoveClock(number(&opcode, &operands)?),
+        "fmvn" => EpdOperation::FullmoveNumber(number(&opcode, &operands)?),
+        comment if comment.len() == 2 && comment.starts_with('c') => {
+            match comment[1..].parse::<u8>() {
+                Ok(index) => EpdOperation::Comment(index, string(&opcode, &operands)?),
+                Err(_) => EpdOperation::Other(opcode, operands),
+            }
+        }
+        _ => EpdOperation::Other(opcode, operands),
+    };
+    Ok(operation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::Position;
+
+    const WAC_001: &str =
+        "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id \"WAC.001\";";
+
+    #[test]
+    fn it_parses_epd_with_opcodes() {
+        let epd = Epd::parse(WAC_001).unwrap();
+
+        assert_eq!(
+            epd.get_board().get_fen(),
+            "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1"
+        );
+        assert!(
+            epd.get_best_moves().unwrap() == [Move::new(Position('g', '3'), Position('g', '6'))]
+        );
+        assert_eq!(epd.get_id(), Some("WAC.001"));
+    }
+
+    #[test]
+    fn it_parses_typed_and_raw_operations() {
+        let epd = Epd::parse(
+            "4k3/8/8/8/8/8/4P3/4K3 w - - am Kd2 Kf2; c0 \"quoted; text\"; ce -35; hmvc 3; fmvn 41; \
+             pv e4 Kd7; noop; xyz a \"b c\";",
+        )
+        .unwrap();
+
+        assert_eq!(epd.get_avoid_moves().unwrap().len(), 2);
+        assert!(epd.operations[1] == EpdOperation::Comment(0, String::from("quoted; text")));
+        assert!(epd.operations[2] == EpdOperation::CentipawnEvaluation(-35));
+        assert_eq!(epd.get_board().get_halfmove_clock(), 3);
+        assert_eq!(epd.get_board().get_fullmove_number(), 41);
+        assert!(
+            epd.operations[5]
+                == EpdOperation::PredictedVariation(vec![
+                    Move::new(Position('e', '2'), Position('e', '4')),
+                    Move::new(Position('e', '8'), Position('d', '7')),
+                ])
+        );
+        assert!(epd.operations[6] == EpdOperation::Other(String::from("noop"), Vec::new()));
+        assert!(
+            epd.operations[7]
+                == EpdOperation::Other(
+                    String::from("xyz"),
+                    vec![String::from("a"), String::from("b c")]
+                )
+        );
+    }
+
+    #[test]
+    fn it_writes_epd() {
+        let epd = Epd::parse(WAC_001).unwrap();
+        assert_eq!(epd.to_epd(), WAC_001);
+
+        let line = "4k3/8/8/8/8/8/4P3/4K3 w - - pv e4 Kd7; c1 \"a b\"; xyz a \"b c\";";
+        assert_eq!(Epd::parse(line).unwrap().to_epd(), line);
+
+        let line = r#"4k3/8/8/8/8/8/4P3/4K3 w - - id "say \"hi\" \\ bye"; xyz a\b;"#;
+        let epd = Epd::parse(line).unwrap();
+        assert_eq!(epd.get_id(), Some(r#"say "hi" \ bye"#));
+        assert_eq!(Epd::parse(&epd.to_epd()).unwrap(), epd);
+    }
+
+    #[test]
+    fn it_parses_multiple_lines() {
+        let epds = Epd::parse_all(&format!("{}\n\n{}\n", WAC_001, WAC_001)).unwrap();
+        assert_eq!(epds.len(), 2);
+    }
+
+    #[test]
+    fn it_returns_error_for_invalid_epd() {
+        assert!(Epd::parse("4k3/8/8/8/8/8/4P3/4K3 w -").is_err());
+        assert!(Epd::parse("4k3/8/8/8/8/8/4P3/4K3 w - - bm Ke3;").is_err());
+        assert!(Epd::parse("4k3/8/8/8/8/8/4P3/4K3 w - - ce abc;").is_err());
+        assert!(Epd::parse("4k3/8/8/8/8/8/4P3/4K3 w - - id \"open;").is_err());
+    }
+}
@@ -0,0 +1,1011 @@
+use std::{
+    fmt,
+    ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
+    slice,
+};
+
+use crate::{
+    board::Board,
+    piece::{
+        PieceColor, PieceKind, BISHOP_DIRECTIONS, KING_OFFSETS, KNIGHT_OFFSETS, ROOK_DIRECTIONS,
+    },
+    position::BoardIndex,
+};
+
+/// Game phase of the starting position, each minor piece counts 1, rook 2 and queen 4
+pub const MAX_PHASE: i32 = 24;
+
+/// Number of attacked squares considered neutral for knight, bishop, rook and queen mobility
+const MOBILITY_BASELINE: [i32; 4] = [4, 7, 7, 14];
+
+/// Pair of middlegame and endgame values, interpolated by game phase
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Weight {
+    pub mg: i32,
+    pub eg: i32,
+}
+
+pub const fn w(mg: i32, eg: i32) -> Weight {
+    Weight { mg, eg }
+}
+
+impl Weight {
+    /// Interpolates between middlegame and endgame value, phase goes from MAX_PHASE down to 0
+    pub fn taper(&self, phase: i32) -> i32 {
+        let phase = phase.clamp(0, MAX_PHASE);
+        (self.mg * phase + self.eg * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+
+impl Add for Weight {
+    type Output = Weight;
+
+    fn add(self, other: Weight) -> Weight {
+        w(self.mg + other.mg, self.eg + other.eg)
+    }
+}
+
+impl Sub for Weight {
+    type Output = Weight;
+
+    fn sub(self, other: Weight) -> Weight {
+        w(self.mg - other.mg, self.eg - other.eg)
+    }
+}
+
+impl Neg for Weight {
+    type Output = Weight;
+
+    fn neg(self) -> Weight {
+        w(-self.mg, -self.eg)
+    }
+}
+
+impl Mul<i32> for Weight {
+    type Output = Weight;
+
+    fn mul(self, factor: i32) -> Weight {
+        w(self.mg * factor, self.eg * factor)
+    }
+}
+
+impl AddAssign for Weight {
+    fn add_assign(&mut self, other: Weight) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Weight {
+    fn sub_assign(&mut self, other: Weight) {
+        *self = *self - other;
+    }
+}
+
+/// All evaluation weights, so they can be tuned in one place
+///
+/// Tables indexed by piece are in order pawn, knight, bishop, rook, queen, king.
+/// Piece-square tables are from White's point of view, starting with a8 and ending with h1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvalParams {
+    pub piece_values: [Weight; 6],
+    pub piece_square_tables: [[Weight; 64]; 6],
+    /// Per pawn with another own pawn in front of it on the same file
+    pub doubled_pawn: Weight,
+    /// Per pawn without own pawns on adjacent files
+    pub isolated_pawn: Weight,
+    /// By rank counted from the pawn's own side, 0 being its back rank
+    pub passed_pawn: [Weight; 8],
+    /// Per attacked square above the baseline for knight, bishop, rook and queen
+    pub mobility: [Weight; 4],
+    /// Per own pawn on the three files around the king, one or two ranks in front of it
+    pub king_shield: Weight,
+    /// Per square next to the king attacked by an enemy piece, counted for every attacker
+    pub king_zone_attack: Weight,
+    /// Added to `king_zone_attack` for the side to move's attacks only, from its own point
+    /// of view, so it values attacking the enemy king more than defending its own
+    pub king_zone_attack_to_move: Weight,
+    pub bishop_pair: Weight,
+    /// Rook on a file without pawns
+    pub rook_open_file: Weight,
+    /// Rook on a file without own pawns, but with enemy ones
+    pub rook_semi_open_file: Weight,
+}
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        let mut piece_square_tables = [[Weight::default(); 64]; 6];
+        for (piece, table) in piece_square_tables.iter_mut().enumerate() {
+            for (square, weight) in table.iter_mut().enumerate() {
+                *weight = w(MG_TABLES[piece][square], EG_TABLES[piece][square]);
+            }
+        }
+
+        EvalParams {
+            piece_values: [
+                w(82, 94),
+                w(337, 281),
+                w(365, 297),
+                w(477, 512),
+                w(1025, 936),
+                w(0, 0),
+            ],
+            piece_square_tables,
+            doubled_pawn: w(-10, -20),
+            isolated_pawn: w(-10, -10),
+            passed_pawn: [
+                w(0, 0),
+                w(0, 5),
+                w(5, 10),
+                w(10, 20),
+                w(20, 40),
+                w(35, 70),
+                w(55, 110),
+                w(0, 0),
+            ],
+            mobility: [w(4, 4), w(5, 5), w(2, 4), w(1, 2)],
+            king_shield: w(10, 0),
+            king_zone_attack: w(-8, -2),
+            king_zone_attack_to_move: w(0, 0),
+            bishop_pair: w(25, 45),
+            rook_open_file: w(25, 10),
+            rook_semi_open_file: w(12, 6),
+        }
+    }
+}
+
+impl EvalParams {
+    /// Weights of every field by name, piece-square tables flattened piece by piece
+    fn get_fields(&self) -> [(&'static str, &[Weight]); 12] {
+        [
+            ("piece_values", &self.piece_values),
+            (
+                "piece_square_tables",
+                self.piece_square_tables.as_flattened(),
+            ),
+            ("doubled_pawn", slice::from_ref(&self.doubled_pawn)),
+            ("isolated_pawn", slice::from_ref(&self.isolated_pawn)),
+            ("passed_pawn", &self.passed_pawn),
+            ("mobility", &self.mobility),
+            ("king_shield", slice::from_ref(&self.king_shield)),
+            ("king_zone_attack", slice::from_ref(&self.king_zone_attack)),
+            (
+                "king_zone_attack_to_move",
+                slice::from_ref(&self.king_zone_attack_to_move),
+            ),
+            ("bishop_pair", slice::from_ref(&self.bishop_pair)),
+            ("rook_open_file", slice::from_ref(&self.rook_open_file)),
+            (
+                "rook_semi_open_file",
+                slice::from_ref(&self.rook_semi_open_file),
+            ),
+        ]
+    }
+
+    fn get_fields_mut(&mut self) -> [&mut [Weight]; 12] {
+        [
+            &mut self.piece_values,
+            self.piece_square_tables.as_flattened_mut(),
+            slice::from_mut(&mut self.doubled_pawn),
+            slice::from_mut(&mut self.isolated_pawn),
+            &mut self.passed_pawn,
+            &mut self.mobility,
+            slice::from_mut(&mut self.king_shield),
+            slice::from_mut(&mut self.king_zone_attack),
+            slice::from_mut(&mut self.king_zone_attack_to_move),
+            slice::from_mut(&mut self.bishop_pair),
+            slice::from_mut(&mut self.rook_open_file),
+            slice::from_mut(&mut self.rook_semi_open_file),
+        ]
+    }
+
+    /// Every weight in field order, for tuning
+    pub fn get_weights(&self) -> Vec<Weight> {
+        self.get_fields()
+            .iter()
+            .flat_map(|(_, weights)| weights.iter().copied())
+            .collect()
+    }
+
+    /// Replaces every weight, in the order of `get_weights`
+    pub fn set_weights(&mut self, weights: &[Weight]) {
+        let mut weights = weights.iter();
+        for field in self.get_fields_mut() {
+            for weight in field {
+                *weight = *weights.next().expect("one weight for every parameter");
+            }
+        }
+    }
+
+    /// Rust source laid out like the `Default` implementation and the piece-square table
+    /// constants, so tuned values can be pasted over them
+    pub fn to_rust_source(&self) -> String {
+        let mut source = String::from("EvalParams {\n");
+        for (name, weights) in self.get_fields() {
+            match weights {
+                _ if name == "piece_square_tables" => source.push_str("    piece_square_tables,\n"),
+                [weight] => {
+                    source.push_str(&format!("    {}: w({}, {}),\n", name, weight.mg, weight.eg))
+                }
+                _ => {
+                    source.push_str(&format!("    {}: [\n", name));
+                    for weight in weights {
+                        source.push_str(&format!("        w({}, {}),\n", weight.mg, weight.eg));
+                    }
+                    source.push_str("    ],\n");
+                }
+            }
+        }
+        source.push_str("}\n");
+
+        for (name, is_endgame) in [("MG_TABLES", false), ("EG_TABLES", true)] {
+            source.push_str(&format!(
+                "\n#[rustfmt::skip]\nconst {}: [[i32; 64]; 6] = [\n",
+                name
+            ));
+            for table in &self.piece_square_tables {
+                source.push_str("    [\n");
+                for row in table.chunks(8) {
+                    let values: Vec<String> = row
+                        .iter()
+                        .map(|weight| {
+                            format!("{:>3}", if is_endgame { weight.eg } else { weight.mg })
+                        })
+                        .collect();
+                    source.push_str(&format!("        {},\n", values.join(", ")));
+                }
+                source.push_str("    ],\n");
+            }
+            source.push_str("];\n");
+        }
+        source
+    }
+}
+
+/// Playing style, nudges evaluation weights away from the defaults
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Style {
+    #[default]
+    Normal,
+    /// Values attacks on the enemy king by the side to move and piece activity
+    Aggressive,
+    /// Values pawn structure, bishop pair and rook files
+    Positional,
+}
+
+impl Style {
+    pub const ALL: [Style; 3] = [Style::Normal, Style::Aggressive, Style::Positional];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Style::Normal => "Normal",
+            Style::Aggressive => "Aggressive",
+            Style::Positional => "Positional",
+        }
+    }
+
+    /// Case insensitive
+    pub fn from_name(name: &str) -> Option<Style> {
+        Style::ALL
+            .into_iter()
+            .find(|style| style.get_name().eq_ignore_ascii_case(name))
+    }
+}
+
+impl EvalParams {
+    /// Default weights adjusted to a playing style
+    pub fn new_with_style(style: Style) -> EvalParams {
+        let mut params = EvalParams::default();
+        let scale = |weight: &mut Weight, percent: i32| {
+            *weight = w(weight.mg * percent / 100, weight.eg * percent / 100)
+        };
+        match style {
+            Style::Normal => {}
+            Style::Aggressive => {
+                // Own attacks count 12 per square against 8 for the opponent's
+                params.king_zone_attack_to_move = w(4, 1);
+                params
+                    .mobility
+                    .iter_mut()
+                    .for_each(|weight| scale(weight, 125));
+            }
+            Style::Positional => {
+                scale(&mut params.doubled_pawn, 150);
+                scale(&mut params.isolated_pawn, 150);
+                params
+                    .passed_pawn
+                    .iter_mut()
+                    .for_each(|weight| scale(weight, 125));
+                scale(&mut params.bishop_pair, 125);
+                scale(&mut params.rook_open_file, 125);
+                scale(&mut params.rook_semi_open_file, 125);
+            }
+        }
+        params
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Term {
+    Material,
+    PieceSquare,
+    Pawns,
+    Mobility,
+    KingSafety,
+    BishopPair,
+    Rooks,
+}
+
+pub const TERM_COUNT: usize = 7;
+
+impl Term {
+    pub const ALL: [Term; TERM_COUNT] = [
+        Term::Material,
+        Term::PieceSquare,
+        Term::Pawns,
+        Term::Mobility,
+        Term::KingSafety,
+        Term::BishopPair,
+        Term::Rooks,
+    ];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Term::Material => "Material",
+            Term::PieceSquare => "Piece-square",
+            Term::Pawns => "Pawns",
+            Term::Mobility => "Mobility",
+            Term::KingSafety => "King safety",
+            Term::BishopPair => "Bishop pair",
+            Term::Rooks => "Rooks",
+        }
+    }
+}
+
+/// Breakdown of the evaluation into terms, with middlegame and endgame value for each color
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvalTrace {
+    terms: [[Weight; 2]; TERM_COUNT],
+    phase: i32,
+    to_move: PieceColor,
+}
+
+impl EvalTrace {
+    fn add(&mut self, term: Term, color: PieceColor, weight: Weight) {
+        self.terms[term as usize][color_index(color)] += weight;
+    }
+
+    /// Value of a term for given color from that color's point of view
+    pub fn get(&self, term: Term, color: PieceColor) -> Weight {
+        self.terms[term as usize][color_index(color)]
+    }
+
+    /// Value of a term from White's point of view
+    pub fn get_difference(&self, term: Term) -> Weight {
+        self.get(term, PieceColor::White) - self.get(term, PieceColor::Black)
+    }
+
+    pub fn get_phase(&self) -> i32 {
+        self.phase
+    }
+
+    /// Sum of all terms from White's point of view, before tapering
+    pub fn get_white_total(&self) -> Weight {
+        Term::ALL.iter().fold(Weight::default(), |total, term| {
+            total + self.get_difference(*term)
+        })
+    }
+
+    /// Tapered score from White's point of view
+    pub fn get_white_score(&self) -> i32 {
+        self.get_white_total().taper(self.phase)
+    }
+
+    /// Tapered score from the side to move's point of view, same as `evaluate`
+    pub fn get_score(&self) -> i32 {
+        match self.to_move {
+            PieceColor::White => self.get_white_score(),
+            PieceColor::Black => -self.get_white_score(),
+        }
+    }
+}
+
+/// Table with a row per term, values are in pawns
+impl fmt::Display for EvalTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pawns = |value: i32| format!("{:6.2}", value as f64 / 100.0);
+        let columns = |weights: [Weight; 3]| {
+            weights
+                .iter()
+                .map(|weight| format!(" {} {}", pawns(weight.mg), pawns(weight.eg)))
+                .collect::<Vec<_>>()
+                .join(" |")
+        };
+        let separator = format!("{:-<14}+{:-<15}+{:-<15}+{:-<14}", "", "", "", "");
+
+        writeln!(
+            f,
+            "{:>13} |{:^15}|{:^15}|{:^15}",
+            "Term", "White", "Black", "Total"
+        )?;
+        writeln!(f, "{:>13} |{}", "", ["    MG     EG"; 3].join("  |"))?;
+        writeln!(f, "{}", separator)?;
+
+        let mut totals = [Weight::default(); 3];
+        for term in Term::ALL {
+            let row = [
+                self.get(term, PieceColor::White),
+                self.get(term, PieceColor::Black),
+                self.get_difference(term),
+            ];
+            for (total, weight) in totals.iter_mut().zip(row) {
+                *total += weight;
+            }
+            writeln!(f, "{:>13} |{}", term.get_name(), columns(row))?;
+        }
+
+        writeln!(f, "{}", separator)?;
+        writeln!(f, "{:>13} |{}", "Total", columns(totals))?;
+        writeln!(f)?;
+        writeln!(f, "Phase: {}/{}", self.phase, MAX_PHASE)?;
+        writeln!(
+            f,
+            "Evaluation: {:+.2} (White side), {:+.2} (side to move)",
+            self.get_white_score() as f64 / 100.0,
+            self.get_score() as f64 / 100.0
+        )
+    }
+}
+
+type Grid = [[Option<(PieceKind, PieceColor)>; 8]; 8];
+
+/// Static evaluation in centipawns from the side to move's point of view
+pub fn evaluate(board: &Board) -> i32 {
+    evaluate_with_params(board, &EvalParams::default())
+}
+
+pub fn evaluate_with_params(board: &Board, params: &EvalParams) -> i32 {
+    get_evaluation(board, params).get_score()
+}
+
+/// Evaluation split into terms, to explain what the score is made of
+pub fn trace(board: &Board) -> EvalTrace {
+    trace_with_params(board, &EvalParams::default())
+}
+
+pub fn trace_with_params(board: &Board, params: &EvalParams) -> EvalTrace {
+    get_evaluation(board, params)
+}
+
+/// Game phase from MAX_PHASE with all pieces on board down to 0 with only kings and pawns
+pub fn get_phase(board: &Board) -> i32 {
+    let phase: i32 = board
+        .get_flat_pieces()
+        .iter()
+        .map(|piece| get_phase_weight(piece.get_kind()))
+        .sum();
+    phase.min(MAX_PHASE)
+}
+
+fn get_phase_weight(kind: PieceKind) -> i32 {
+    match kind {
+        PieceKind::Knight | PieceKind::Bishop => 1,
+        PieceKind::Rook => 2,
+        PieceKind::Queen => 4,
+        PieceKind::King | PieceKind::Pawn => 0,
+    }
+}
+
+fn get_evaluation(board: &Board, params: &EvalParams) -> EvalTrace {
+    let mut grid: Grid = [[None; 8]; 8];
+    let mut pawn_counts = [[0; 8]; 2];
+    let mut bishop_counts = [0; 2];
+    let mut kings = [None; 2];
+    for piece in board.get_flat_pieces() {
+        let BoardIndex(file, rank) = piece.get_position().get_indices();
+        let color = *piece.get_color();
+        grid[file][rank] = Some((piece.get_kind(), color));
+        match piece.get_kind() {
+            PieceKind::Pawn => pawn_counts[color_index(color)][file] += 1,
+            PieceKind::Bishop => bishop_counts[color_index(color)] += 1,
+            PieceKind::King => kings[color_index(color)] = Some((file as i32, rank as i32)),
+            _ => {}
+        }
+    }
+
+    let mut eval = EvalTrace {
+        terms: [[Weight::default(); 2]; TERM_COUNT],
+        phase: get_phase(board),
+        to_move: board.get_to_move(),
+    };
+
+    for file in 0..8 {
+        for rank in 0..8 {
+            let Some((kind, color)) = grid[file][rank] else {
+                continue;
+            };
+            let piece = piece_index(kind);
+            let own = color_index(color);
+            let enemy = 1 - own;
+            let (file, rank) = (file as i32, rank as i32);
+
+            eval.add(Term::Material, color, params.piece_values[piece]);
+            eval.add(
+                Term::PieceSquare,
+                color,
+                params.piece_square_tables[piece][get_table_index(file, rank, color)],
+            );
+
+            match kind {
+                PieceKind::Pawn => {
+                    evaluate_pawn(&mut eval, params, &grid, &pawn_counts, file, rank, color)
+                }
+                PieceKind::Rook if pawn_counts[own][file as usize] == 0 => {
+                    let bonus = if pawn_counts[enemy][file as usize] == 0 {
+                        params.rook_open_file
+                    } else {
+                        params.rook_semi_open_file
+                    };
+                    eval.add(Term::Rooks, color, bonus);
+                }
+                _ => {}
+            }
+
+            if matches!(kind, PieceKind::King | PieceKind::Pawn) {
+                continue;
+            }
+            let attacks = get_attacks(&grid, kind, file, rank);
+            let mobility = attacks
+                .iter()
+                .filter(
+                    |(f, r)| !matches!(grid[*f as usize][*r as usize], Some((_, c)) if c == color),
+                )
+                .count() as i32;
+            eval.add(
+                Term::Mobility,
+                color,
+                params.mobility[piece - 1] * (mobility - MOBILITY_BASELINE[piece - 1]),
+            );
+
+            if let Some((king_file, king_rank)) = kings[enemy] {
+                let zone_attacks = attacks
+                    .iter()
+                    .filter(|(f, r)| (f - king_file).abs() <= 1 && (r - king_rank).abs() <= 1)
+                    .count() as i32;
+                eval.add(
+                    Term::KingSafety,
+                    color.opposite(),
+                    params.king_zone_attack * zone_attacks,
+                );
+                if color == eval.to_move {
+                    eval.add(
+                        Term::KingSafety,
+                        color,
+                        params.king_zone_attack_to_move * zone_attacks,
+                    );
+                }
+            }
+        }
+    }
+
+    for color in [PieceColor::White, PieceColor::Black] {
+        if bishop_counts[color_index(color)] >= 2 {
+            eval.add(Term::BishopPair, color, params.bishop_pair);
+        }
+        if let Some((king_file, king_rank)) = kings[color_index(color)] {
+            let shield = count_shield_pawns(&grid, color, king_file, king_rank);
+            eval.add(Term::KingSafety, color, params.king_shield * shield);
+        }
+    }
+    eval
+}
+
+fn evaluate_pawn(
+    eval: &mut EvalTrace,
+    params: &EvalParams,
+    grid: &Grid,
+    pawn_counts: &[[i32; 8]; 2],
+    file: i32,
+    rank: i32,
+    color: PieceColor,
+) {
+    let own = &pawn_counts[color_index(color)];
+    let adjacent_pawns: i32 = [file - 1, file + 1]
+        .iter()
+        .filter(|f| (0..8).contains(*f))
+        .map(|f| own[*f as usize])
+        .sum();
+
+    let direction = color.pawn_direction();
+    let is_doubled = (1..8)
+        .map(|distance| rank + distance * direction)
+        .take_while(|r| (0..8).contains(r))
+        .any(|r| grid[file as usize][r as usize] == Some((PieceKind::Pawn, color)));
+    if is_doubled {
+        eval.add(Term::Pawns, color, params.doubled_pawn);
+    }
+    if adjacent_pawns == 0 {
+        eval.add(Term::Pawns, color, params.isolated_pawn);
+    }
+
+    // Only the front pawn of doubled ones can be passed
+    let is_passed = !is_doubled
+        && (file - 1..=file + 1)
+            .filter(|f| (0..8).contains(f))
+            .all(|f| {
+                let mut r = rank + direction;
+                while (0..8).contains(&r) {
+                    if grid[f as usize][r as usize] == Some((PieceKind::Pawn, color.opposite())) {
+                        return false;
+                    }
+                    r += direction;
+                }
+                true
+            });
+    if is_passed {
+        let relative_rank = match color {
+            PieceColor::White => rank,
+            PieceColor::Black => 7 - rank,
+        };
+        eval.add(
+            Term::Pawns,
+            color,
+            params.passed_pawn[relative_rank as usize],
+        );
+    }
+}
+
+fn count_shield_pawns(grid: &Grid, color: PieceColor, king_file: i32, king_rank: i32) -> i32 {
+    let mut count = 0;
+    for file in king_file - 1..=king_file + 1 {
+        for distance in 1..=2 {
+            let rank = king_rank + distance * color.pawn_direction();
+            if (0..8).contains(&file)
+                && (0..8).contains(&rank)
+                && grid[file as usize][rank as usize] == Some((PieceKind::Pawn, color))
+            {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+/// Squares attacked by a knight or slider, including ones occupied by any piece
+fn get_attacks(grid: &Grid, kind: PieceKind, file: i32, rank: i32) -> Vec<(i32, i32)> {
+    let on_board = |f: i32, r: i32| (0..8).contains(&f) && (0..8).contains(&r);
+    let mut attacks = Vec::new();
+
+    let directions: Vec<(i32, i32)> = match kind {
+        PieceKind::Knight => {
+            return KNIGHT_OFFSETS
+                .iter()
+                .map(|(df, dr)| (file + df, rank + dr))
+                .filter(|(f, r)| on_board(*f, *r))
+                .collect();
+        }
+        PieceKind::King => {
+            return KING_OFFSETS
+                .iter()
+                .map(|(df, dr)| (file + df, rank + dr))
+                .filter(|(f, r)| on_board(*f, *r))
+                .collect();
+        }
+        PieceKind::Bishop => BISHOP_DIRECTIONS.to_vec(),
+        PieceKind::Rook => ROOK_DIRECTIONS.to_vec(),
+        PieceKind::Queen => [ROOK_DIRECTIONS, BISHOP_DIRECTIONS].concat(),
+        PieceKind::Pawn => return attacks,
+    };
+
+    for (df, dr) in directions {
+        let (mut f, mut r) = (file + df, rank + dr);
+        while on_board(f, r) {
+            attacks.push((f, r));
+            if grid[f as usize][r as usize].is_some() {
+                break;
+            }
+            f += df;
+            r += dr;
+        }
+    }
+    attacks
+}
+
+/// Index into piece-square tables, which are written from White's side starting at a8
+fn get_table_index(file: i32, rank: i32, color: PieceColor) -> usize {
+    let row = match color {
+        PieceColor::White => 7 - rank,
+        PieceColor::Black => rank,
+    };
+    (row * 8 + file) as usize
+}
+
+fn piece_index(kind: PieceKind) -> usize {
+    match kind {
+        PieceKind::Pawn => 0,
+        PieceKind::Knight => 1,
+        PieceKind::Bishop => 2,
+        PieceKind::Rook => 3,
+        PieceKind::Queen => 4,
+        PieceKind::King => 5,
+    }
+}
+
+fn color_index(color: PieceColor) -> usize {
+    match color {
+        PieceColor::White => 0,
+        PieceColor::Black => 1,
+    }
+}
+
+#[rustfmt::skip]
+const MG_TABLES: [[i32; 64]; 6] = [
+    [
+          0,   0,   0,   0,   0,   0,   0,   0,
+         98, 134,  61,  95,  68, 126,  34, -11,
+         -6,   7,  26,  31,  65,  56,  25, -20,
+        -14,  13,   6,  21,  23,  12,  17, -23,
+        -27,  -2,  -5,  12,  17,   6,  10, -25,
+        -26,  -4,  -4, -10,   3,   3,  33, -12,
+        -35,  -1, -20, -23, -15,  24,  38, -22,
+          0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    [
+       -167, -89, -34, -49,  61, -97, -15,-107,
+        -73, -41,  72,  36,  23,  62,   7, -17,
+        -47,  60,  37,  65,  84, 129,  73,  44,
+         -9,  17,  19,  53,  37,  69,  18,  22,
+        -13,   4,  16,  13,  28,  19,  21,  -8,
+        -23,  -9,  12,  10,  19,  17,  25, -16,
+        -29, -53, -12,  -3,  -1,  18, -14, -19,
+       -105, -21, -58, -33, -17, -28, -19, -23,
+    ],
+    [
+        -29,   4, -82, -37, -25, -42,   7,  -8,
+        -26,  16, -18, -13,  30,  59,  18, -47,
+        -16,  37,  43,  40,  35,  50,  37,  -2,
+         -4,   5,  19,  50,  37,  37,   7,  -2,
+         -6,  13,  13,  26,  34,  12,  10,   4,
+          0,  15,  15,  15,  14,  27,  18,  10,
+          4,  15,  16,   0,   7,  21,  33,   1,
+        -33,  -3, -14, -21, -13, -12, -39, -21,
+    ],
+    [
+         32,  42,  32,  51,  63,   9,  31,  43,
+         27,  32,  58,  62,  80,  67,  26,  44,
+         -5,  19,  26,  36,  17,  45,  61,  16,
+        -24, -11,   7,  26,  24,  35,  -8, -20,
+        -36, -26, -12,  -1,   9,  -7,   6, -23,
+        -45, -25, -16, -17,   3,   0,  -5, -33,
+        -44, -16, -20,  -9,  -1,  11,  -6, -71,
+        -19, -13,   1,  17,  16,   7, -37, -26,
+    ],
+    [
+        -28,   0,  29,  12,  59,  44,  43,  45,
+        -24, -39,  -5,   1, -16,  57,  28,  54,
+        -13, -17,   7,   8,  29,  56,  47,  57,
+        -27, -27, -16, -16,  -1,  17,  -2,   1,
+         -9, -26,  -9, -10,  -2,  -4,   3,  -3,
+        -14,   2, -11,  -2,  -5,   2,  14,   5,
+        -35,  -8,  11,   2,   8,  15,  -3,   1,
+         -1, -18,  -9,  10, -15, -25, -31, -50,
+    ],
+    [
+        -65,  23,  16, -15, -56, -34,   2,  13,
+         29,  -1, -20,  -7,  -8,  -4, -38, -29,
+         -9,  24,   2, -16, -20,   6,  22, -22,
+        -17, -20, -12, -27, -30, -25, -14, -36,
+        -49,  -1, -27, -39, -46, -44, -33, -51,
+        -14, -14, -22, -46, -44, -30, -15, -27,
+          1,   7,  -8, -64, -43, -16,   9,   8,
+        -15,  36,  12, -54,   8, -28,  24,  14,
+    ],
+];
+
+#[rustfmt::skip]
+const EG_TABLES: [[i32; 64]; 6] = [
+    [
+          0,   0,   0,   0,   0,   0,   0,   0,
+        178, 173, 158, 134, 147, 132, 165, 187,
+         94, 100,  85,  67,  56,  53,  82,  84,
+         32,  24,  13,   5,  -2,   4,  17,  17,
+         13,   9,  -3,  -7,  -7,  -8,   3,  -1,
+          4,   7,  -6,   1,   0,  -5,  -1,  -8,
+         13,   8,   8,  10,  13,   0,   2,  -7,
+          0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    [
+        -58, -38, -13, -28, -31, -27, -63, -99,
+        -25,  -8, -25,  -2,  -9, -25, -24, -52,
+        -24, -20,  10,   9,  -1,  -9, -19, -41,
+        -17,   3,  22,  22,  22,  11,   8, -18,
+        -18,  -6,  16,  25,  16,  17,   4, -18,
+        -23,  -3,  -1,  15,  10,  -3, -20, -22,
+        -42, -20, -10,  -5,  -2, -20, -23, -44,
+        -29, -51, -23, -15, -22, -18, -50, -64,
+    ],
+    [
+        -14, -21, -11,  -8,  -7,  -9, -17, -24,
+         -8,  -4,   7, -12,  -3, -13,  -4, -14,
+          2,  -8,   0,  -1,  -2,   6,   0,   4,
+         -3,   9,  12,   9,  14,  10,   3,   2,
+         -6,   3,  13,  19,   7,  10,  -3,  -9,
+        -12,  -3,   8,  10,  13,   3,  -7, -15,
+        -14, -18,  -7,  -1,   4,  -9, -15, -27,
+        -23,  -9, -23,  -5,  -9, -16,  -5, -17,
+    ],
+    [
+         13,  10,  18,  15,  12,  12,   8,   5,
+         11,  13,  13,  11,  -3,   3,   8,   3,
+          7,   7,   7,   5,   4,  -3,  -5,  -3,
+          4,   3,  13,   1,   2,   1,  -1,   2,
+          3,   5,   8,   4,  -5,  -6,  -8, -11,
+         -4,   0,  -5,  -1,  -7, -12,  -8, -16,
+         -6,  -6,   0,   2,  -9,  -9, -11,  -3,
+         -9,   2,   3,  -1,  -5, -13,   4, -20,
+    ],
+    [
+         -9,  22,  22,  27,  27,  19,  10,  20,
+        -17,  20,  32,  41,  58,  25,  30,   0,
+        -20,   6,   9,  49,  47,  35,  19,   9,
+          3,  22,  24,  45,  57,  40,  57,  36,
+        -18,  28,  19,  47,  31,  34,  39,  23,
+        -16, -27,  15,   6,   9,  17,  10,   5,
+        -22, -23, -30, -16, -16, -23, -36, -32,
+        -33, -28, -22, -43,  -5, -32, -20, -41,
+    ],
+    [
+        -74, -35, -18, -18, -11,  15,   4, -17,
+        -12,  17,  14,  17,  17,  38,  23,  11,
+         10,  17,  23,  15,  20,  45,  44,  13,
+         -8,  22,  24,  27,  26,  33,  26,   3,
+        -18,  -4,  21,  24,  27,  23,   9, -11,
+        -19,  -3,  11,  21,  23,  16,   7,  -9,
+        -27, -11,   4,  13,  14,   4,  -5, -17,
+        -53, -34, -21, -11, -28, -14, -24, -43,
+    ],
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_evaluates_starting_position_as_equal() {
+        assert_eq!(evaluate(&Board::default()), 0);
+        assert_eq!(get_phase(&Board::default()), MAX_PHASE);
+    }
+
+    #[test]
+    fn it_is_symmetric_for_both_colors() {
+        let white = Board::new_from_fen(
+            "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+        )
+        .unwrap();
+        let black = Board::new_from_fen(
+            "rnbqk2r/pppp1ppp/5n2/2b1p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R b KQkq - 4 4",
+        )
+        .unwrap();
+        assert_eq!(evaluate(&white), evaluate(&black));
+    }
+
+    #[test]
+    fn it_prefers_extra_material() {
+        let board = Board::new_from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+        assert!(evaluate(&board) > 800);
+
+        let board = Board::new_from_fen("4k3/8/8/8/8/8/8/3QK3 b - - 0 1").unwrap();
+        assert!(evaluate(&board) < -800);
+    }
+
+    #[test]
+    fn it_traces_evaluation_terms() {
+        let board = Board::new_from_fen("4k3/pppp4/8/8/8/8/4PPPP/1BBQK3 b - - 0 1").unwrap();
+        let trace = trace(&board);
+
+        assert_eq!(trace.get_score(), evaluate(&board));
+        assert_eq!(trace.get_white_score(), -evaluate(&board));
+        assert_eq!(
+            trace.get(Term::BishopPair, PieceColor::White),
+            EvalParams::default().bishop_pair
+        );
+        assert_eq!(
+            trace.get_difference(Term::Material),
+            w(365 * 2 + 1025, 297 * 2 + 936)
+        );
+        assert_eq!(trace.get_phase(), 6);
+
+        let table = trace.to_string();
+        assert_eq!(table.lines().count(), 15);
+        assert!(table.contains("Bishop pair |   0.25   0.45 |   0.00   0.00 |   0.25   0.45"));
+    }
+
+    #[test]
+    fn it_tapers_between_middlegame_and_endgame() {
+        assert_eq!(w(100, 200).taper(MAX_PHASE), 100);
+        assert_eq!(w(100, 200).taper(0), 200);
+        assert_eq!(w(100, 200).taper(MAX_PHASE / 2), 150);
+    }
+
+    #[test]
+    fn it_scores_pawn_structure() {
+        let params = EvalParams::default();
+        // Doubled and isolated pawns on the c-file against healthy pawns
+        let weak = Board::new_from_fen("4k3/pp6/8/8/8/2P5/2P5/4K3 w - - 0 1").unwrap();
+        let eval = get_evaluation(&weak, &params);
+        let pawns = eval.terms[Term::Pawns as usize];
+
+        assert!(pawns[0].eg < 0);
+        assert!(pawns[1].eg > 0);
+    }
+
+    #[test]
+    fn it_rewards_bishop_pair_and_rooks_on_open_files() {
+        let params = EvalParams::default();
+        let board = Board::new_from_fen("4k3/pppp4/8/8/8/8/4PPPP/1BB1KR2 w - - 0 1").unwrap();
+        let eval = get_evaluation(&board, &params);
+
+        assert_eq!(
+            eval.terms[Term::BishopPair as usize],
+            [params.bishop_pair, w(0, 0)]
+        );
+        assert_eq!(eval.terms[Term::Rooks as usize][0], w(0, 0));
+
+        let board = Board::new_from_fen("4k3/pppp4/8/8/8/8/4PPPP/1BB1K2R w - - 0 1").unwrap();
+        let eval = get_evaluation(&board, &params);
+        assert_eq!(eval.terms[Term::Rooks as usize][0], w(0, 0));
+
+        let board = Board::new_from_fen("4k3/ppp5/8/8/8/8/4PPPP/1BBRK3 w - - 0 1").unwrap();
+        let eval = get_evaluation(&board, &params);
+        assert_eq!(eval.terms[Term::Rooks as usize][0], params.rook_open_file);
+    }
+
+    #[test]
+    fn it_adjusts_weights_to_style() {
+        let normal = EvalParams::default();
+        let aggressive = EvalParams::new_with_style(Style::Aggressive);
+        let positional = EvalParams::new_with_style(Style::Positional);
+        assert_eq!(EvalParams::new_with_style(Style::Normal), normal);
+        assert!(positional.isolated_pawn.eg < normal.isolated_pawn.eg);
+        assert_eq!(positional.piece_values, normal.piece_values);
+        assert_eq!(Style::from_name("aggressive"), Some(Style::Aggressive));
+        assert_eq!(Style::from_name("wild"), None);
+
+        // White's queen attacks the squares around Black's king
+        let king_safety = |fen: &str, params: &EvalParams| {
+            let board = Board::new_from_fen(fen).unwrap();
+            let eval = get_evaluation(&board, params);
+            eval.get_difference(Term::KingSafety).mg
+        };
+        let white_to_move = "6k1/5ppp/8/3Q4/8/8/5PPP/6K1 w - - 0 1";
+        let black_to_move = "6k1/5ppp/8/3Q4/8/8/5PPP/6K1 b - - 0 1";
+        assert!(king_safety(white_to_move, &aggressive) > king_safety(white_to_move, &normal));
+        // Opponent's attacks are feared as much as with the default weights
+        assert_eq!(
+            king_safety(black_to_move, &aggressive),
+            king_safety(black_to_move, &normal)
+        );
+        assert_eq!(
+            king_safety(black_to_move, &positional),
+            king_safety(black_to_move, &normal)
+        );
+    }
+
+    #[test]
+    fn it_writes_weights_back() {
+        let mut params = EvalParams::default();
+        let mut weights = params.get_weights();
+        assert_eq!(weights.len(), 6 + 6 * 64 + 2 + 8 + 4 + 6);
+        assert_eq!(weights[6 + 64], w(MG_TABLES[1][0], EG_TABLES[1][0]));
+
+        weights[0] = w(100, 120);
+        params.set_weights(&weights);
+        assert_eq!(params.piece_values[0], w(100, 120));
+        assert_eq!(params.get_weights(), weights);
+
+        let source = params.to_rust_source();
+        assert!(source.starts_with("EvalParams {\n    piece_values: [\n        w(100, 120),\n"));
+        assert!(source.contains("    doubled_pawn: w(-10, -20),\n"));
+        assert!(source.contains("const EG_TABLES: [[i32; 64]; 6] = [\n    [\n          0,   0,"));
+        assert!(source.contains("        178, 173, 158, 134, 147, 132, 165, 187,\n"));
+    }
+}
@@ -0,0 +1,32 @@
+pub mod bench;
+pub mod board;
+pub mod board_renderer;
+
+pub mod piece;
+
+pub mod ui;
+
+pub mod castling_rights;
+pub mod chess_move;
+pub mod datagen;
+pub mod engine;
+pub mod epd;
+pub mod eval;
+pub mod mate_search;
+pub mod move_ordering;
+pub mod nnue;
+pub mod pgn;
+pub mod position;
+pub mod raster_renderer;
+pub mod rng;
+pub mod san;
+pub mod search;
+pub mod see;
+pub mod skill;
+pub mod svg_renderer;
+pub mod time_manager;
+pub mod transposition_table;
+pub mod tune;
+pub mod uci;
+pub mod xboard;
+pub mod zobrist;
@@ -1,16 +1,314 @@
-use crate::board::Board;
+use std::{
+    env, fs,
+    io::{self, BufWriter},
+    process,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-mod board;
+use szpess::{
+    bench,
+    board::Board,
+    datagen::{self, DataFormat, DatagenOptions},
+    epd::Epd,
+    eval::{self, EvalParams},
+    nnue::Network,
+    pgn::PgnGame,
+    raster_renderer::RasterRenderer,
+    san::move_to_san,
+    search::{SearchLimits, SearchOptions},
+    tune::{self, TuneOptions, TuningEntry},
+    uci, xboard,
+};
 
-mod piece;
+const USAGE: &str = "Usage:
+  szpess                           print starting position
+  szpess uci                       run Universal Chess Interface engine
+  szpess xboard                    run XBoard protocol engine
+  szpess eval [fen] [--nnue <file>]
+                                   print evaluation breakdown of a position,
+                                   and its evaluation by a network
+  szpess epdtest <suite.epd> [movetime_ms] [--no-<option>...]
+                                   search test suite positions and count solved ones
+  szpess bench [depth] [--no-<option>...]
+                                   search bench positions to fixed depth and count nodes,
+                                   options: quiescence, killers, countermoves, history,
+                                   null-move, late-move-reductions, futility,
+                                   reverse-futility, late-move-pruning, razoring,
+                                   check-extensions, singular-extensions
+  szpess datagen <output> [games] [--nodes <n>] [--random-plies <n>] [--seed <n>]
+                 [--binary] [--skip-in-check] [--skip-captures] [--skip-tactical]
+                                   play fixed-node self-play games and record positions
+                                   with search score and game result
+  szpess tune <dataset> <output.rs> [--iterations <n>] [--learning-rate <x>]
+                                   tune evaluation weights on labelled positions, from
+                                   datagen output or EPD with results in c9, and write
+                                   them as Rust source
+  szpess png <fen> <output.png>    render position to PNG
+  szpess gif <game.pgn> <output.gif> [delay_ms]
+                                   render first game of a PGN file to animated GIF";
 
-mod ui;
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        None => {
+            print!("{}", Board::default());
+            Ok(())
+        }
+        Some("uci") => uci::run(io::stdin().lock(), Arc::new(Mutex::new(io::stdout()))),
+        Some("xboard") => xboard::run(
+            io::BufReader::new(io::stdin()),
+            Arc::new(Mutex::new(io::stdout())),
+        ),
+        Some("eval") => print_eval(&args[1..]),
+        Some("epdtest") => run_epd_test(&args[1..]),
+        Some("bench") => run_bench(&args[1..]),
+        Some("datagen") => run_datagen(&args[1..]),
+        Some("tune") => run_tune(&args[1..]),
+        Some("png") => render_png(&args[1..]),
+        Some("gif") => render_gif(&args[1..]),
+        Some(_) => Err(String::from(USAGE)),
+    };
 
-mod position;
-mod castling_rights;
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
 
+fn print_eval(args: &[String]) -> Result<(), String> {
+    let (args, network) = match args {
+        [args @ .., flag, path] if flag == "--nnue" => (args, Some(Network::load(path)?)),
+        args => (args, None),
+    };
+    let board = match args {
+        [] => Board::default(),
+        [fen] => Board::new_from_fen(fen)?,
+        _ => return Err(String::from(USAGE)),
+    };
+    print!("{}\n{}", board, eval::trace(&board));
+    if let Some(network) = network {
+        println!("NNUE: {}", network.evaluate_board(&board));
+    }
+    Ok(())
+}
 
-fn main() {
-    let brd = Board::default();
-    print!("{}", brd);
+/// Splits `--no-<option>` flags, which switch search options off, from other arguments
+fn parse_search_options(args: &[String]) -> Result<(SearchOptions, Vec<&String>), String> {
+    let mut options = SearchOptions::default();
+    let mut positional = Vec::new();
+    for arg in args {
+        match arg.strip_prefix("--no-") {
+            Some(name) => options.set(name, false)?,
+            None => positional.push(arg),
+        }
+    }
+    Ok((options, positional))
+}
+
+fn run_epd_test(args: &[String]) -> Result<(), String> {
+    let (options, positional) = parse_search_options(args)?;
+    let (input, movetime) = match positional.as_slice() {
+        [input] => (input, 1000),
+        [input, movetime] => (
+            input,
+            movetime
+                .parse()
+                .map_err(|_| format!("Invalid move time '{}'", movetime))?,
+        ),
+        _ => return Err(String::from(USAGE)),
+    };
+
+    let suite = Epd::parse_all(&fs::read_to_string(input).map_err(|e| e.to_string())?)?;
+    let limits = SearchLimits {
+        movetime: Some(Duration::from_millis(movetime)),
+        ..SearchLimits::default()
+    };
+    let result = bench::run_epd_suite(&suite, &limits, &options, |epd, info, solved| {
+        let best_move = info
+            .get_best_move()
+            .map_or_else(|| String::from("-"), |mv| move_to_san(epd.get_board(), &mv));
+        println!(
+            "{:<12} {:<8} {:<7} score {:<7} depth {:<3} nodes {}",
+            epd.get_id().unwrap_or("?"),
+            best_move,
+            if solved { "solved" } else { "failed" },
+            info.score.to_string(),
+            info.depth,
+            info.nodes
+        );
+    });
+
+    println!(
+        "\nSolved {}/{}, {} nodes in {:.1}s",
+        result.solved,
+        result.total,
+        result.nodes,
+        result.time.as_secs_f64()
+    );
+    Ok(())
+}
+
+fn run_bench(args: &[String]) -> Result<(), String> {
+    let (options, positional) = parse_search_options(args)?;
+    let depth = match positional.as_slice() {
+        [] => 8,
+        [depth] => depth
+            .parse()
+            .map_err(|_| format!("Invalid depth '{}'", depth))?,
+        _ => return Err(String::from(USAGE)),
+    };
+
+    let result = bench::run_bench(depth, &options, |board, info| {
+        let best_move = info
+            .get_best_move()
+            .map_or_else(|| String::from("-"), |mv| move_to_san(board, &mv));
+        println!(
+            "{:<8} score {:<7} nodes {:<9} {}",
+            best_move,
+            info.score.to_string(),
+            info.nodes,
+            board.get_fen()
+        );
+    });
+
+    println!(
+        "\n{} nodes in {:.1}s, {} nps",
+        result.nodes,
+        result.time.as_secs_f64(),
+        result.get_nps()
+    );
+    Ok(())
+}
+
+fn run_datagen(args: &[String]) -> Result<(), String> {
+    let mut options = DatagenOptions {
+        seed: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64),
+        ..DatagenOptions::default()
+    };
+    let mut format = DataFormat::Text;
+    let mut positional = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--nodes" => options.nodes = parse_number(arg, args.next())?,
+            "--random-plies" => options.random_plies = parse_number(arg, args.next())?,
+            "--seed" => options.seed = parse_number(arg, args.next())?,
+            "--binary" => format = DataFormat::Binary,
+            "--skip-in-check" => options.skip_in_check = true,
+            "--skip-captures" => options.skip_captures = true,
+            "--skip-tactical" => options.skip_tactical = true,
+            _ => positional.push(arg),
+        }
+    }
+    let output = match positional.as_slice() {
+        [output] => output,
+        [output, games] => {
+            options.games = parse_number("games", Some(games))?;
+            output
+        }
+        _ => return Err(String::from(USAGE)),
+    };
+
+    let mut writer = BufWriter::new(fs::File::create(output).map_err(|e| e.to_string())?);
+    println!("Seed {}", options.seed);
+    let mut game = 0;
+    let result = datagen::run_datagen(&options, format, &mut writer, |result, positions| {
+        game += 1;
+        println!(
+            "Game {}/{}: {} {} positions",
+            game,
+            options.games,
+            match result {
+                1.0 => "1-0    ",
+                0.0 => "0-1    ",
+                _ => "1/2-1/2",
+            },
+            positions
+        );
+    })?;
+
+    println!(
+        "\n{} positions from {} games in {:.1}s",
+        result.positions,
+        result.games,
+        result.time.as_secs_f64()
+    );
+    Ok(())
+}
+
+fn run_tune(args: &[String]) -> Result<(), String> {
+    let mut options = TuneOptions::default();
+    let mut positional = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--iterations" => options.iterations = parse_number(arg, args.next())?,
+            "--learning-rate" => options.learning_rate = parse_number(arg, args.next())?,
+            _ => positional.push(arg),
+        }
+    }
+    let [input, output] = positional.as_slice() else {
+        return Err(String::from(USAGE));
+    };
+
+    let positions = if input.ends_with(".bin") {
+        tune::parse_binary_dataset(&fs::read(input).map_err(|e| e.to_string())?)?
+    } else {
+        tune::parse_dataset(&fs::read_to_string(input).map_err(|e| e.to_string())?)?
+    };
+    println!("Loaded {} positions", positions.len());
+    let entries: Vec<TuningEntry> = positions
+        .iter()
+        .map(|(board, result)| TuningEntry::new(board, *result))
+        .collect();
+
+    let result = tune::tune(
+        &entries,
+        &EvalParams::default(),
+        &options,
+        |iteration, error| println!("Iteration {:<6} error {:.6}", iteration, error),
+    );
+    println!("K {:.4}, error {:.6}", result.k, result.error);
+    fs::write(output, result.params.to_rust_source()).map_err(|e| e.to_string())
+}
+
+/// Parses value of a numeric argument
+fn parse_number<T: std::str::FromStr>(name: &str, value: Option<&String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("Missing value for '{}'", name))?;
+    value
+        .parse()
+        .map_err(|_| format!("Invalid {} '{}'", name.trim_start_matches('-'), value))
+}
+
+fn render_png(args: &[String]) -> Result<(), String> {
+    let [fen, output] = args else {
+        return Err(String::from(USAGE));
+    };
+    let board = Board::new_from_fen(fen)?;
+    let png = RasterRenderer::default().render_png(&board)?;
+    fs::write(output, png).map_err(|e| e.to_string())
+}
+
+fn render_gif(args: &[String]) -> Result<(), String> {
+    let (input, output, delay) = match args {
+        [input, output] => (input, output, None),
+        [input, output, delay] => (input, output, Some(delay)),
+        _ => return Err(String::from(USAGE)),
+    };
+
+    let mut renderer = RasterRenderer::default();
+    if let Some(delay) = delay {
+        renderer.frame_delay_ms = delay
+            .parse()
+            .map_err(|_| format!("Invalid frame delay '{}'", delay))?;
+    }
+
+    let pgn = fs::read_to_string(input).map_err(|e| e.to_string())?;
+    let game = PgnGame::parse(&pgn)?;
+    let moves: Vec<_> = game.moves.iter().map(|pgn_move| pgn_move.mv).collect();
+    let gif = renderer.render_gif(game.get_starting_board(), &moves)?;
+    fs::write(output, gif).map_err(|e| e.to_string())
 }
@@ -0,0 +1,292 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::{
+    board::Board, chess_move::Move, move_ordering::get_mvv_lva, search::SearchLimits,
+    time_manager::TimeManager,
+};
+
+/// Stop flag and time are checked once every this many nodes, fewer than in the normal
+/// search as every node generates replies to all its moves
+const CHECK_INTERVAL: u64 = 64;
+
+/// How the mate search of a search with `SearchLimits::mate` ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MateSearchStatus {
+    Found,
+    /// Search completed, there is no mate in the given number of moves
+    Refuted,
+    /// Search was stopped or ran out of time or nodes before proving or refuting the mate
+    Aborted,
+}
+
+/// Proves or refutes forced mates with a depth-bounded AND/OR search
+///
+/// The attacking side tries checks first, then captures and quiet moves, and its last move
+/// must give check. Every defence has to be answered by a mate, the longest one is reported.
+pub struct MateSearch {
+    stop: Arc<AtomicBool>,
+    /// Cleared on ponder hit, see `SearchLimits::ponder`
+    ponder: Arc<AtomicBool>,
+    pondering: bool,
+    time_manager: TimeManager,
+    max_nodes: Option<u64>,
+    nodes: u64,
+    aborted: bool,
+    /// Most moves known not to be enough to mate from a position, by hash
+    refuted: HashMap<u64, u32>,
+    /// Defence which refuted the attack last time, by moves left
+    refutations: Vec<Option<Move>>,
+}
+
+impl Default for MateSearch {
+    fn default() -> Self {
+        MateSearch::new(Arc::new(AtomicBool::new(false)))
+    }
+}
+
+impl MateSearch {
+    /// Search stops early when `stop` is set
+    pub fn new(stop: Arc<AtomicBool>) -> MateSearch {
+        MateSearch {
+            stop,
+            ponder: Arc::new(AtomicBool::new(false)),
+            pondering: false,
+            time_manager: TimeManager::default(),
+            max_nodes: None,
+            nodes: 0,
+            aborted: false,
+            refuted: HashMap::new(),
+            refutations: Vec::new(),
+        }
+    }
+
+    /// Search also stops at the node limit and the hard deadline of `time_manager`, which
+    /// applies once `ponder` is cleared when pondering
+    pub fn set_limits(
+        &mut self,
+        limits: &SearchLimits,
+        time_manager: TimeManager,
+        ponder: Arc<AtomicBool>,
+    ) {
+        self.max_nodes = limits.nodes;
+        self.pondering = limits.ponder;
+        self.time_manager = time_manager;
+        self.ponder = ponder;
+    }
+
+    pub fn get_nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    /// Time used from our clock, nothing while still pondering
+    pub fn get_clock_elapsed(&self) -> Duration {
+        if self.pondering {
+            Duration::ZERO
+        } else {
+            self.time_manager.get_clock_elapsed()
+        }
+    }
+
+    /// Search was stopped before proving or refuting the mate
+    pub fn is_aborted(&self) -> bool {
+        self.aborted
+    }
+
+    /// Shortest forced mate in at most `moves` moves of the side to move
+    ///
+    /// Returns moves of both sides ending in checkmate, the defending side resisting as long as
+    /// possible. None when there is no such mate or the search is stopped.
+    pub fn find_mate(&mut self, board: &Board, moves: u32) -> Option<Vec<Move>> {
+        self.aborted = false;
+        self.refutations = vec![None; moves as usize + 1];
+        self.find_shortest(board, moves)
+    }
+
+    fn find_shortest(&mut self, board: &Board, moves: u32) -> Option<Vec<Move>> {
+        (1..=moves).find_map(|moves| {
+            if self.aborted {
+                return None;
+            }
+            self.attack(board, moves)
+        })
+    }
+
+    /// Attacking move mating in at most `moves` moves, followed by the rest of the line
+    fn attack(&mut self, board: &Board, moves: u32) -> Option<Vec<Move>> {
+        self.nodes += 1;
+        if self.max_nodes.is_some_and(|nodes| self.nodes >= nodes)
+            || (self.nodes.is_multiple_of(CHECK_INTERVAL) && self.should_stop())
+        {
+            self.aborted = true;
+        }
+        let hash = board.get_hash();
+        if self.aborted
+            || self
+                .refuted
+                .get(&hash)
+                .is_some_and(|&refuted| refuted >= moves)
+        {
+            return None;
+        }
+
+        let mut candidates = Vec::new();
+        for mv in board.get_legal_moves() {
+            let mut child = board.clone();
+            child.make_move_unchecked(&mv);
+            let check = child.is_in_check();
+            // Only a check can mate with the last move
+            if moves > 1 || check {
+                candidates.push((check, get_mvv_lva(board, &mv), mv, child));
+            }
+        }
+        candidates.sort_by_key(|(check, mvv_lva, _, _)| (!check, -mvv_lva));
+
+        if let Some((_, _, mv, _)) = candidates
+            .iter()
+            .find(|(check, _, _, child)| *check && child.get_legal_moves().is_empty())
+        {
+            return Some(vec![*mv]);
+        }
+        if moves > 1 {
+            for (_, _, mv, child) in &candidates {
+                if let Some(line) = self.defend(child, moves - 1) {
+                    return Some([vec![*mv], line].concat());
+                }
+                if self.aborted {
+                    return None;
+                }
+            }
+        }
+
+        let refuted = self.refuted.entry(hash).or_insert(0);
+        *refuted = (*refuted).max(moves);
+        None
+    }
+
+    /// Longest defence against a mate in at most `moves` moves, None when one of the replies
+    /// escapes the mate
+    fn defend(&mut self, board: &Board, moves: u32) -> Option<Vec<Move>> {
+        let mut replies = board.get_legal_moves();
+        // Stalemate, checkmates are found by the attacker
+        if replies.is_empty() {
+            return None;
+        }
+        let refutation = self.refutations[moves as usize];
+        replies.sort_by_cached_key(|mv| (Some(*mv) != refutation, -get_mvv_lva(board, mv)));
+
+        let mut longest: Option<Vec<Move>> = None;
+        for reply in replies {
+            let mut child = board.clone();
+            child.make_move_unchecked(&reply);
+            let Some(line) = self.find_shortest(&child, moves) else {
+                if !self.aborted {
+                    self.refutations[moves as usize] = Some(reply);
+                }
+                return None;
+            };
+            if longest
+                .as_ref()
+                .is_none_or(|longest| line.len() >= longest.len())
+            {
+                longest = Some([vec![reply], line].concat());
+            }
+        }
+        longest
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if self.pondering && !self.ponder.load(Ordering::Relaxed) {
+            // Ponder hit, the clock starts running now
+            self.pondering = false;
+            self.time_manager.on_ponderhit();
+        }
+        self.stop.load(Ordering::Relaxed)
+            || (!self.pondering && self.time_manager.is_hard_limit_reached())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find_mate(fen: &str, moves: u32) -> Option<Vec<String>> {
+        let board = Board::new_from_fen(fen).unwrap();
+        let line = board.find_mate(moves)?;
+        Some(line.iter().map(Move::get_uci).collect())
+    }
+
+    #[test]
+    fn it_finds_shortest_mate_with_longest_defence() {
+        assert_eq!(
+            find_mate("6k1/5ppp/8/8/8/8/8/R3K3 w Q - 0 1", 3),
+            Some(vec![String::from("a1a8")])
+        );
+
+        // King takes the opposition first, any defence is then mated on the a-file or rank
+        let fen = "k7/8/2K5/8/8/8/8/7R w - - 0 1";
+        assert_eq!(find_mate(fen, 1), None);
+        let line = find_mate(fen, 2).unwrap();
+        assert_eq!(line.len(), 3);
+        let mut board = Board::new_from_fen(fen).unwrap();
+        for mv in &line {
+            board.make_move(&Move::from_uci(mv).unwrap()).unwrap();
+        }
+        assert!(board.is_checkmate());
+    }
+
+    #[test]
+    fn it_refutes_mate_that_does_not_exist() {
+        assert_eq!(find_mate("4k3/8/8/8/8/8/8/4K2R w - - 0 1", 2), None);
+        // Stalemate isn't a mate
+        assert_eq!(find_mate("k7/8/1QK5/8/8/8/8/8 b - - 0 1", 1), None);
+    }
+
+    #[test]
+    fn it_stops_on_demand() {
+        let stop = Arc::new(AtomicBool::new(true));
+        let mut search = MateSearch::new(stop);
+        let board = Board::new_from_fen("4k3/8/8/8/8/8/8/4K2R w - - 0 1").unwrap();
+        assert_eq!(search.find_mate(&board, 10), None);
+        assert!(search.is_aborted());
+        assert!(search.get_nodes() <= CHECK_INTERVAL);
+    }
+
+    #[test]
+    fn it_stops_at_node_and_time_limits() {
+        let board = Board::new_from_fen(
+            "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+        )
+        .unwrap();
+        let limits = SearchLimits {
+            nodes: Some(100),
+            ..SearchLimits::default()
+        };
+        let mut search = MateSearch::default();
+        search.set_limits(
+            &limits,
+            TimeManager::default(),
+            Arc::new(AtomicBool::new(false)),
+        );
+        assert_eq!(search.find_mate(&board, 5), None);
+        assert!(search.is_aborted());
+        assert_eq!(search.get_nodes(), 100);
+
+        let limits = SearchLimits {
+            movetime: Some(Duration::from_millis(100)),
+            ..SearchLimits::default()
+        };
+        let time_manager = TimeManager::new(&limits, board.get_to_move(), Duration::ZERO);
+        let mut search = MateSearch::default();
+        search.set_limits(&limits, time_manager, Arc::new(AtomicBool::new(false)));
+        assert_eq!(search.find_mate(&board, 5), None);
+        assert!(search.is_aborted());
+        assert!(search.get_clock_elapsed() < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,302 @@
+use crate::{
+    board::Board,
+    chess_move::Move,
+    piece::{PieceColor, PieceKind},
+    position::{BoardIndex, Position},
+    search::MAX_PLY,
+    see::{get_captured_kind, get_piece_value, see},
+};
+
+/// History scores saturate towards this value
+pub const MAX_HISTORY: i32 = 16384;
+
+const HASH_MOVE_SCORE: i32 = 1 << 30;
+const GOOD_CAPTURE_SCORE: i32 = 1 << 28;
+const KILLER_SCORE: i32 = 1 << 27;
+const COUNTERMOVE_SCORE: i32 = KILLER_SCORE - 2;
+const BAD_CAPTURE_SCORE: i32 = -(1 << 28);
+const UNDERPROMOTION_SCORE: i32 = -(1 << 29);
+
+/// Moving piece and its destination, identifies a move for countermove and continuation history
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PieceSquare {
+    index: usize,
+}
+
+impl PieceSquare {
+    /// Key of a move, `board` is the position before the move is made
+    pub fn new(board: &Board, mv: &Move) -> Option<PieceSquare> {
+        let piece = (*board.get_piece_by_position(mv.from))?;
+        Some(PieceSquare {
+            index: get_piece_index(piece.get_kind(), *piece.get_color()) * 64 + get_square(mv.to),
+        })
+    }
+}
+
+/// Quiet move statistics gathered during search, used to order moves that aren't captures
+pub struct Heuristics {
+    /// Two quiet moves per ply which caused a beta cutoff, most recent first
+    killers: Vec<[Option<Move>; 2]>,
+    /// Quiet move refuting the previous move, indexed by its piece and destination
+    countermoves: Vec<Option<Move>>,
+    /// Indexed by side to move, from and to square
+    history: Vec<i32>,
+    /// Indexed by an earlier move's piece and destination and the current one's
+    continuation_history: Vec<i32>,
+}
+
+impl Default for Heuristics {
+    fn default() -> Self {
+        Heuristics {
+            killers: vec![[None; 2]; MAX_PLY],
+            countermoves: vec![None; 12 * 64],
+            history: vec![0; 2 * 64 * 64],
+            continuation_history: vec![0; 12 * 64 * 12 * 64],
+        }
+    }
+}
+
+impl Heuristics {
+    pub fn new() -> Heuristics {
+        Heuristics::default()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Heuristics::default();
+    }
+
+    /// Killers only make sense within one search, histories are kept between searches
+    pub fn clear_killers(&mut self) {
+        self.killers.fill([None; 2]);
+    }
+
+    pub fn get_killers(&self, ply: usize) -> [Option<Move>; 2] {
+        self.killers[ply]
+    }
+
+    pub fn add_killer(&mut self, ply: usize, mv: Move) {
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(mv) {
+            killers[1] = killers[0];
+            killers[0] = Some(mv);
+        }
+    }
+
+    pub fn get_countermove(&self, previous: PieceSquare) -> Option<Move> {
+        self.countermoves[previous.index]
+    }
+
+    pub fn set_countermove(&mut self, previous: PieceSquare, mv: Move) {
+        self.countermoves[previous.index] = Some(mv);
+    }
+
+    /// Sum of main history and continuation histories of the earlier moves which are known
+    pub fn get_history(&self, board: &Board, mv: &Move, previous: &[Option<PieceSquare>]) -> i32 {
+        let mut score = self.history[get_history_index(board, mv)];
+        if let Some(current) = PieceSquare::new(board, mv) {
+            for earlier in previous.iter().flatten() {
+                score += self.continuation_history[earlier.index * 12 * 64 + current.index];
+            }
+        }
+        score
+    }
+
+    /// Rewards move which caused a cutoff and penalizes quiet moves tried before it
+    pub fn update_history(
+        &mut self,
+        board: &Board,
+        best_move: &Move,
+        tried: &[Move],
+        depth: u32,
+        previous: &[Option<PieceSquare>],
+    ) {
+        let bonus = (16 * depth * depth).min(1200) as i32;
+        self.add_history(board, best_move, bonus, previous);
+        for mv in tried.iter().filter(|mv| *mv != best_move) {
+            self.add_history(board, mv, -bonus, previous);
+        }
+    }
+
+    /// Gravity keeps scores within MAX_HISTORY, entries close to the limit change slowly
+    fn add_history(
+        &mut self,
+        board: &Board,
+        mv: &Move,
+        bonus: i32,
+        previous: &[Option<PieceSquare>],
+    ) {
+        apply_bonus(&mut self.history[get_history_index(board, mv)], bonus);
+        if let Some(current) = PieceSquare::new(board, mv) {
+            for earlier in previous.iter().flatten() {
+                apply_bonus(
+                    &mut self.continuation_history[earlier.index * 12 * 64 + current.index],
+                    bonus,
+                );
+            }
+        }
+    }
+
+    /// Sorts moves by hash move, captures winning or keeping material by MVV-LVA, killers,
+    /// countermove, quiet moves by history, losing captures and underpromotions
+    ///
+    /// `previous` holds keys of moves leading to the position, the last move first.
+    pub fn order_moves(
+        &self,
+        board: &Board,
+        moves: &mut [Move],
+        hash_move: Option<Move>,
+        ply: usize,
+        previous: &[Option<PieceSquare>],
+    ) {
+        let killers = self.get_killers(ply);
+        let countermove = previous
+            .first()
+            .copied()
+            .flatten()
+            .and_then(|previous| self.get_countermove(previous));
+
+        moves.sort_by_cached_key(|mv| {
+            let score = if Some(*mv) == hash_move {
+                HASH_MOVE_SCORE
+            } else if mv.promotion.is_some_and(|kind| kind != PieceKind::Queen) {
+                UNDERPROMOTION_SCORE + get_mvv_lva(board, mv)
+            } else if is_tactical(board, mv) {
+                if see(board, mv) >= 0 {
+                    GOOD_CAPTURE_SCORE + get_mvv_lva(board, mv)
+                } else {
+                    BAD_CAPTURE_SCORE + get_mvv_lva(board, mv)
+                }
+            } else if killers[0] == Some(*mv) {
+                KILLER_SCORE
+            } else if killers[1] == Some(*mv) {
+                KILLER_SCORE - 1
+            } else if countermove == Some(*mv) {
+                COUNTERMOVE_SCORE
+            } else {
+                self.get_history(board, mv, previous)
+            };
+            -score
+        });
+    }
+}
+
+/// Captures and promotions, moves which aren't ordered by history
+pub fn is_tactical(board: &Board, mv: &Move) -> bool {
+    mv.promotion.is_some() || get_captured_kind(board, mv).is_some()
+}
+
+/// Most valuable victim first, least valuable attacker breaks ties
+pub fn get_mvv_lva(board: &Board, mv: &Move) -> i32 {
+    let victim = get_captured_kind(board, mv).map_or(0, get_piece_value)
+        + mv.promotion.map_or(0, get_piece_value);
+    let attacker = board
+        .get_piece_by_position(mv.from)
+        .map_or(0, |piece| get_piece_value(piece.get_kind()).min(1000));
+    victim * 16 - attacker / 10
+}
+
+fn apply_bonus(entry: &mut i32, bonus: i32) {
+    let bonus = bonus.clamp(-MAX_HISTORY, MAX_HISTORY);
+    *entry += bonus - *entry * bonus.abs() / MAX_HISTORY;
+}
+
+fn get_history_index(board: &Board, mv: &Move) -> usize {
+    let color = match board.get_to_move() {
+        PieceColor::White => 0,
+        PieceColor::Black => 1,
+    };
+    (color * 64 + get_square(mv.from)) * 64 + get_square(mv.to)
+}
+
+fn get_piece_index(kind: PieceKind, color: PieceColor) -> usize {
+    let color = match color {
+        PieceColor::White => 0,
+        PieceColor::Black => 6,
+    };
+    let kind = match kind {
+        PieceKind::Pawn => 0,
+        PieceKind::Knight => 1,
+        PieceKind::Bishop => 2,
+        PieceKind::Rook => 3,
+        PieceKind::Queen => 4,
+        PieceKind::King => 5,
+    };
+    color + kind
+}
+
+fn get_square(pos: Position) -> usize {
+    let BoardIndex(file, rank) = pos.get_indices();
+    rank * 8 + file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uci(mv: &str) -> Move {
+        Move::from_uci(mv).unwrap()
+    }
+
+    #[test]
+    fn it_orders_captures_by_victim_and_exchange() {
+        // Rook takes defended pawn loses material, knight takes queen and pawn takes knight win it
+        let board = Board::new_from_fen("4k3/8/2p5/1p1q4/4n3/2N2P2/8/1R2K3 w - - 0 1").unwrap();
+        let mut moves = vec![uci("b1b5"), uci("e1d1"), uci("f3e4"), uci("c3d5")];
+        Heuristics::new().order_moves(&board, &mut moves, Some(uci("e1d1")), 0, &[]);
+
+        assert_eq!(
+            moves,
+            vec![uci("e1d1"), uci("c3d5"), uci("f3e4"), uci("b1b5")]
+        );
+    }
+
+    #[test]
+    fn it_orders_killers_countermoves_and_history() {
+        let board = Board::default();
+        let previous =
+            Board::new_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1")
+                .unwrap();
+        let previous_move = PieceSquare::new(&previous, &uci("g8f6"));
+        let mut heuristics = Heuristics::new();
+        heuristics.add_killer(3, uci("a2a3"));
+        heuristics.add_killer(3, uci("b2b3"));
+        heuristics.add_killer(3, uci("b2b3"));
+        heuristics.set_countermove(previous_move.unwrap(), uci("c2c3"));
+        heuristics.update_history(&board, &uci("d2d4"), &[uci("e2e4")], 5, &[previous_move]);
+
+        let mut moves = vec![
+            uci("e2e4"),
+            uci("d2d4"),
+            uci("c2c3"),
+            uci("a2a3"),
+            uci("b2b3"),
+        ];
+        heuristics.order_moves(&board, &mut moves, None, 3, &[previous_move]);
+        assert_eq!(
+            moves,
+            vec![
+                uci("b2b3"),
+                uci("a2a3"),
+                uci("c2c3"),
+                uci("d2d4"),
+                uci("e2e4")
+            ]
+        );
+
+        heuristics.clear_killers();
+        assert_eq!(heuristics.get_killers(3), [None, None]);
+    }
+
+    #[test]
+    fn it_keeps_history_within_bounds() {
+        let board = Board::default();
+        let mut heuristics = Heuristics::new();
+        for _ in 0..1000 {
+            heuristics.update_history(&board, &uci("g1f3"), &[uci("a2a3")], 20, &[]);
+        }
+
+        assert!(heuristics.get_history(&board, &uci("g1f3"), &[]) <= MAX_HISTORY);
+        assert!(heuristics.get_history(&board, &uci("a2a3"), &[]) >= -MAX_HISTORY);
+        assert!(heuristics.get_history(&board, &uci("g1f3"), &[]) > MAX_HISTORY / 2);
+    }
+}
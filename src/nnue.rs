@@ -0,0 +1,465 @@
+use std::{fs, path::Path};
+
+use crate::{
+    board::Board,
+    chess_move::Move,
+    piece::{PieceColor, PieceKind},
+    position::{BoardIndex, Position},
+    search::{MATE_SCORE, MAX_PLY},
+};
+
+/// Piece kind, color and square for each side's point of view
+pub const INPUT_SIZE: usize = 768;
+/// Accumulator values are clipped to 0..=QA before the output layer
+const QA: i32 = 255;
+/// Output weights are scaled by this
+const QB: i32 = 64;
+/// Network output of 1.0 corresponds to this many centipawns
+const SCALE: i32 = 400;
+/// Evaluation is clamped below mate scores
+const MAX_SCORE: i32 = MATE_SCORE - MAX_PLY as i32 - 1;
+const MAGIC: &[u8; 4] = b"SZNN";
+
+/// Evaluation network with 768 inputs, one hidden layer seen from both sides and one output
+///
+/// Weights are read from a little-endian file: magic "SZNN", hidden size as u32, i16 feature
+/// weights by input, i16 hidden biases, i16 output weights for the side to move's half followed
+/// by the other half, and i32 output bias.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Network {
+    hidden_size: usize,
+    feature_weights: Vec<i16>,
+    feature_biases: Vec<i16>,
+    output_weights: Vec<i16>,
+    output_bias: i32,
+}
+
+impl Network {
+    /// All weights zero, output is the bias
+    pub fn new(hidden_size: usize, output_bias: i32) -> Network {
+        Network {
+            hidden_size,
+            feature_weights: vec![0; INPUT_SIZE * hidden_size],
+            feature_biases: vec![0; hidden_size],
+            output_weights: vec![0; 2 * hidden_size],
+            output_bias,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Network, String> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Network::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Network, String> {
+        let header = bytes.get(..8).ok_or("Network file is too short")?;
+        if &header[..4] != MAGIC {
+            return Err(String::from("Not a network file"));
+        }
+        let hidden_size = u32::from_le_bytes(header[4..8].try_into().expect("4 bytes")) as usize;
+        let weight_count = (INPUT_SIZE + 3) * hidden_size;
+        if hidden_size == 0 || bytes.len() != 8 + 2 * weight_count + 4 {
+            return Err(String::from(
+                "Network file size doesn't match its hidden layer size",
+            ));
+        }
+
+        let mut values = bytes[8..8 + 2 * weight_count]
+            .chunks_exact(2)
+            .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]));
+        let mut take = |count: usize| values.by_ref().take(count).collect::<Vec<_>>();
+        let feature_weights = take(INPUT_SIZE * hidden_size);
+        let feature_biases = take(hidden_size);
+        let output_weights = take(2 * hidden_size);
+        let output_bias = i32::from_le_bytes(bytes[bytes.len() - 4..].try_into().expect("4 bytes"));
+        Ok(Network {
+            hidden_size,
+            feature_weights,
+            feature_biases,
+            output_weights,
+            output_bias,
+        })
+    }
+
+    /// Encodes network in the format read by `from_bytes`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend((self.hidden_size as u32).to_le_bytes());
+        for weights in [
+            &self.feature_weights,
+            &self.feature_biases,
+            &self.output_weights,
+        ] {
+            bytes.extend(weights.iter().flat_map(|weight| weight.to_le_bytes()));
+        }
+        bytes.extend(self.output_bias.to_le_bytes());
+        bytes
+    }
+
+    pub fn get_hidden_size(&self) -> usize {
+        self.hidden_size
+    }
+
+    /// Weights connecting an input to the hidden layer
+    pub fn get_feature_weights_mut(&mut self, input: usize) -> &mut [i16] {
+        let start = input * self.hidden_size;
+        &mut self.feature_weights[start..start + self.hidden_size]
+    }
+
+    /// Output weights of the side to move's half of the hidden layer, then the other half
+    pub fn get_output_weights_mut(&mut self) -> &mut [i16] {
+        &mut self.output_weights
+    }
+
+    /// Accumulator computed from scratch
+    pub fn new_accumulator(&self, board: &Board) -> Accumulator {
+        let mut accumulator = Accumulator {
+            white: self.feature_biases.clone(),
+            black: self.feature_biases.clone(),
+        };
+        for (square, piece) in get_squares(board).into_iter().enumerate() {
+            if let Some((kind, color)) = piece {
+                self.add_feature(&mut accumulator, kind, color, square);
+            }
+        }
+        accumulator
+    }
+
+    /// Sets `accumulator` to `parent` updated with pieces that differ between the boards on
+    /// squares `mv` might have changed, `board` is `parent_board` after the move
+    pub fn update_accumulator(
+        &self,
+        accumulator: &mut Accumulator,
+        parent: &Accumulator,
+        parent_board: &Board,
+        board: &Board,
+        mv: &Move,
+    ) {
+        accumulator.white.copy_from_slice(&parent.white);
+        accumulator.black.copy_from_slice(&parent.black);
+        for square in get_changed_squares(parent_board, mv) {
+            let position = Position::new_from_indices(BoardIndex(square % 8, square / 8));
+            let before = get_piece(parent_board, position);
+            let after = get_piece(board, position);
+            if before == after {
+                continue;
+            }
+            if let Some((kind, color)) = before {
+                self.remove_feature(accumulator, kind, color, square);
+            }
+            if let Some((kind, color)) = after {
+                self.add_feature(accumulator, kind, color, square);
+            }
+        }
+    }
+
+    /// Score in centipawns from the side to move's point of view
+    pub fn evaluate(&self, accumulator: &Accumulator, to_move: PieceColor) -> i32 {
+        let (us, them) = match to_move {
+            PieceColor::White => (&accumulator.white, &accumulator.black),
+            PieceColor::Black => (&accumulator.black, &accumulator.white),
+        };
+        let (our_weights, their_weights) = self.output_weights.split_at(self.hidden_size);
+        let sum =
+            simd::dot_clipped(us, our_weights, QA) + simd::dot_clipped(them, their_weights, QA);
+        let score = (sum + self.output_bias as i64) * SCALE as i64 / (QA * QB) as i64;
+        score.clamp(-MAX_SCORE as i64, MAX_SCORE as i64) as i32
+    }
+
+    /// Evaluates board without an existing accumulator
+    pub fn evaluate_board(&self, board: &Board) -> i32 {
+        self.evaluate(&self.new_accumulator(board), board.get_to_move())
+    }
+
+    fn add_feature(
+        &self,
+        accumulator: &mut Accumulator,
+        kind: PieceKind,
+        color: PieceColor,
+        square: usize,
+    ) {
+        let (white, black) = get_inputs(kind, color, square);
+        simd::add(&mut accumulator.white, self.get_feature_weights(white));
+        simd::add(&mut accumulator.black, self.get_feature_weights(black));
+    }
+
+    fn remove_feature(
+        &self,
+        accumulator: &mut Accumulator,
+        kind: PieceKind,
+        color: PieceColor,
+        square: usize,
+    ) {
+        let (white, black) = get_inputs(kind, color, square);
+        simd::sub(&mut accumulator.white, self.get_feature_weights(white));
+        simd::sub(&mut accumulator.black, self.get_feature_weights(black));
+    }
+
+    fn get_feature_weights(&self, input: usize) -> &[i16] {
+        let start = input * self.hidden_size;
+        &self.feature_weights[start..start + self.hidden_size]
+    }
+}
+
+/// Hidden layer values before activation, from white's and black's point of view
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Accumulator {
+    white: Vec<i16>,
+    black: Vec<i16>,
+}
+
+/// Input index of a piece from white's and from black's point of view, black sees the board
+/// flipped with colors swapped
+pub fn get_inputs(kind: PieceKind, color: PieceColor, square: usize) -> (usize, usize) {
+    let kind = match kind {
+        PieceKind::Pawn => 0,
+        PieceKind::Knight => 1,
+        PieceKind::Bishop => 2,
+        PieceKind::Rook => 3,
+        PieceKind::Queen => 4,
+        PieceKind::King => 5,
+    };
+    let (white_side, black_side) = match color {
+        PieceColor::White => (0, 1),
+        PieceColor::Black => (1, 0),
+    };
+    (
+        white_side * 384 + kind * 64 + square,
+        black_side * 384 + kind * 64 + (square ^ 56),
+    )
+}
+
+/// Pieces by square, a1 is 0 and h8 is 63
+fn get_squares(board: &Board) -> [Option<(PieceKind, PieceColor)>; 64] {
+    let mut squares = [None; 64];
+    for piece in board.get_flat_pieces() {
+        squares[get_square(*piece.get_position())] = Some((piece.get_kind(), *piece.get_color()));
+    }
+    squares
+}
+
+fn get_piece(board: &Board, position: Position) -> Option<(PieceKind, PieceColor)> {
+    board
+        .get_piece_by_position(position)
+        .map(|piece| (piece.get_kind(), *piece.get_color()))
+}
+
+fn get_square(position: Position) -> usize {
+    let BoardIndex(file, rank) = position.get_indices();
+    rank * 8 + file
+}
+
+/// Move's origin and destination, the whole back rank for castling,
+/// and the square next to a pawn moving diagonally which might capture en passant
+fn get_changed_squares(board: &Board, mv: &Move) -> Vec<usize> {
+    let (from, to) = (get_square(mv.from), get_square(mv.to));
+    let mut squares = vec![from, to];
+    match get_piece(board, mv.from) {
+        Some((PieceKind::King, _)) if board.get_castling_side(mv).is_some() => {
+            let rank = from / 8 * 8;
+            squares = (rank..rank + 8).collect();
+        }
+        Some((PieceKind::Pawn, _)) if mv.from.0 != mv.to.0 => squares.push(from / 8 * 8 + to % 8),
+        _ => {}
+    }
+    squares
+}
+
+/// Vector operations, with AVX2 when built with the "simd" feature and the CPU supports it
+mod simd {
+    pub fn add(values: &mut [i16], weights: &[i16]) {
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: AVX2 support was just checked
+            unsafe { avx2::add(values, weights) };
+            return;
+        }
+        for (value, weight) in values.iter_mut().zip(weights) {
+            *value = value.wrapping_add(*weight);
+        }
+    }
+
+    pub fn sub(values: &mut [i16], weights: &[i16]) {
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: AVX2 support was just checked
+            unsafe { avx2::sub(values, weights) };
+            return;
+        }
+        for (value, weight) in values.iter_mut().zip(weights) {
+            *value = value.wrapping_sub(*weight);
+        }
+    }
+
+    /// Sum of values clipped to 0..=max multiplied by weights
+    pub fn dot_clipped(values: &[i16], weights: &[i16], max: i32) -> i64 {
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: AVX2 support was just checked
+            return unsafe { avx2::dot_clipped(values, weights, max) };
+        }
+        values
+            .iter()
+            .zip(weights)
+            .map(|(value, weight)| ((*value as i32).clamp(0, max) * *weight as i32) as i64)
+            .sum()
+    }
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    mod avx2 {
+        use std::arch::x86_64::*;
+
+        const LANES: usize = 16;
+        /// Steps summed in 32-bit lanes before moving the sums to 64 bits, each step adds
+        /// at most 2 * 255 * 32768 to a lane
+        const STEPS_PER_FLUSH: usize = 64;
+
+        #[target_feature(enable = "avx2")]
+        pub unsafe fn add(values: &mut [i16], weights: &[i16]) {
+            let split = values.len() / LANES * LANES;
+            for i in (0..split).step_by(LANES) {
+                let value = _mm256_loadu_si256(values.as_ptr().add(i) as *const __m256i);
+                let weight = _mm256_loadu_si256(weights.as_ptr().add(i) as *const __m256i);
+                let sum = _mm256_add_epi16(value, weight);
+                _mm256_storeu_si256(values.as_mut_ptr().add(i) as *mut __m256i, sum);
+            }
+            for i in split..values.len() {
+                values[i] = values[i].wrapping_add(weights[i]);
+            }
+        }
+
+        #[target_feature(enable = "avx2")]
+        pub unsafe fn sub(values: &mut [i16], weights: &[i16]) {
+            let split = values.len() / LANES * LANES;
+            for i in (0..split).step_by(LANES) {
+                let value = _mm256_loadu_si256(values.as_ptr().add(i) as *const __m256i);
+                let weight = _mm256_loadu_si256(weights.as_ptr().add(i) as *const __m256i);
+                let difference = _mm256_sub_epi16(value, weight);
+                _mm256_storeu_si256(values.as_mut_ptr().add(i) as *mut __m256i, difference);
+            }
+            for i in split..values.len() {
+                values[i] = values[i].wrapping_sub(weights[i]);
+            }
+        }
+
+        #[target_feature(enable = "avx2")]
+        pub unsafe fn dot_clipped(values: &[i16], weights: &[i16], max: i32) -> i64 {
+            let split = values.len() / LANES * LANES;
+            let (zero, ceiling) = (_mm256_setzero_si256(), _mm256_set1_epi16(max as i16));
+            let mut total = 0i64;
+            for start in (0..split).step_by(LANES * STEPS_PER_FLUSH) {
+                let end = (start + LANES * STEPS_PER_FLUSH).min(split);
+                let mut sums = _mm256_setzero_si256();
+                for i in (start..end).step_by(LANES) {
+                    let value = _mm256_loadu_si256(values.as_ptr().add(i) as *const __m256i);
+                    let weight = _mm256_loadu_si256(weights.as_ptr().add(i) as *const __m256i);
+                    let clipped = _mm256_min_epi16(_mm256_max_epi16(value, zero), ceiling);
+                    sums = _mm256_add_epi32(sums, _mm256_madd_epi16(clipped, weight));
+                }
+                let mut lanes = [0i32; 8];
+                _mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, sums);
+                total += lanes.iter().map(|&lane| lane as i64).sum::<i64>();
+            }
+            let tail: i64 = (split..values.len())
+                .map(|i| ((values[i] as i32).clamp(0, max) * weights[i] as i32) as i64)
+                .sum();
+            total + tail
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Network counting material from the side to move's point of view, plus noise in weights
+    /// which doesn't change the result but catches wrong feature updates
+    fn material_network() -> Network {
+        let mut network = Network::new(20, 0);
+        for kind in [PieceKind::Pawn, PieceKind::Knight, PieceKind::Queen] {
+            for square in 0..64 {
+                let (white, _) = get_inputs(kind, PieceColor::White, square);
+                let (black, _) = get_inputs(kind, PieceColor::Black, square);
+                let value = match kind {
+                    PieceKind::Pawn => 1,
+                    PieceKind::Knight => 3,
+                    _ => 9,
+                };
+                // First neuron counts our material, second the opponent's
+                network.get_feature_weights_mut(white)[0] = value;
+                network.get_feature_weights_mut(black)[1] = value;
+                network.get_feature_weights_mut(white)[2 + square % 18] = square as i16;
+            }
+        }
+        // A pawn counted by both halves is 2 * 2040 * SCALE / (QA * QB) = 100 centipawns
+        let output = network.get_output_weights_mut();
+        output[0] = 2040;
+        output[1] = -2040;
+        output[20] = -2040;
+        output[21] = 2040;
+        network.feature_biases[0] = 100;
+        network.feature_biases[1] = 100;
+        network
+    }
+
+    #[test]
+    fn it_evaluates_from_side_to_move_point_of_view() {
+        let network = material_network();
+        let board = Board::new_from_fen("4k3/8/8/3n4/8/8/PPP5/4K3 w - - 0 1").unwrap();
+        assert_eq!(network.evaluate_board(&board), 0);
+
+        let board = Board::new_from_fen("4k3/8/8/3q4/8/8/PPP5/4K3 b - - 0 1").unwrap();
+        assert_eq!(network.evaluate_board(&board), 600);
+        assert_eq!(network.evaluate_board(&Board::default()), 0);
+    }
+
+    #[test]
+    fn it_does_not_overflow_with_large_weights() {
+        // Sum is far outside i32, the tail after the last full SIMD step is included
+        let values = vec![QA as i16; 4100];
+        let weights = vec![i16::MAX; 4100];
+        assert_eq!(
+            simd::dot_clipped(&values, &weights, QA),
+            4100 * QA as i64 * i16::MAX as i64
+        );
+
+        let mut network = Network::new(1024, 0);
+        network.feature_biases.fill(QA as i16);
+        network.get_output_weights_mut()[..1024].fill(i16::MAX);
+        assert_eq!(network.evaluate_board(&Board::default()), MAX_SCORE);
+        network.get_output_weights_mut()[..1024].fill(i16::MIN);
+        assert_eq!(network.evaluate_board(&Board::default()), -MAX_SCORE);
+    }
+
+    #[test]
+    fn it_updates_accumulator_incrementally() {
+        let network = material_network();
+        // Castling, en passant and promotion change more than two squares
+        let board = Board::new_from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+        let parent = network.new_accumulator(&board);
+        let mut accumulator = parent.clone();
+        for mv in board.get_legal_moves() {
+            let mut child = board.clone();
+            child.make_move_unchecked(&mv);
+            network.update_accumulator(&mut accumulator, &parent, &board, &child, &mv);
+            assert_eq!(
+                accumulator,
+                network.new_accumulator(&child),
+                "{}",
+                mv.get_uci()
+            );
+        }
+    }
+
+    #[test]
+    fn it_loads_saved_network() {
+        let network = material_network();
+        let loaded = Network::from_bytes(&network.to_bytes()).unwrap();
+        assert_eq!(loaded, network);
+
+        assert!(Network::from_bytes(b"SZNN").is_err());
+        assert!(Network::from_bytes(&network.to_bytes()[..100]).is_err());
+        assert!(Network::load("no/such/file.nnue").is_err());
+    }
+}
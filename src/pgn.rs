@@ -0,0 +1,655 @@
+use crate::{
+    board::Board,
+    chess_move::Move,
+    piece::PieceColor,
+    san::{move_to_san, san_to_move},
+};
+
+/// Export format limits lines to 79 characters
+const MAX_LINE_LENGTH: usize = 79;
+
+/// Seven Tag Roster in export order with default values for missing tags
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// Suffix annotations and NAGs they are equivalent to
+const SUFFIX_ANNOTATIONS: [(&str, u8); 6] = [
+    ("!", 1),
+    ("?", 2),
+    ("!!", 3),
+    ("??", 4),
+    ("!?", 5),
+    ("?!", 6),
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PgnMove {
+    pub mv: Move,
+    /// Numeric Annotation Glyphs, e.g. 1 for "!" or 14 for "+="
+    pub nags: Vec<u8>,
+    /// Comment placed before the move, only written for first moves of a line
+    pub comment_before: Option<String>,
+    pub comment: Option<String>,
+    /// Alternative lines, each replacing this move
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+impl PgnMove {
+    pub fn new(mv: Move) -> PgnMove {
+        PgnMove {
+            mv,
+            nags: Vec::new(),
+            comment_before: None,
+            comment: None,
+            variations: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PgnGame {
+    tags: Vec<(String, String)>,
+    starting_board: Board,
+    pub moves: Vec<PgnMove>,
+}
+
+impl Default for PgnGame {
+    fn default() -> Self {
+        PgnGame::new_from_board(Board::default())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    Symbol(String),
+    Result(String),
+    VariationStart,
+    VariationEnd,
+}
+
+impl PgnGame {
+    pub fn new_from_board(starting_board: Board) -> PgnGame {
+        PgnGame {
+            tags: Vec::new(),
+            starting_board,
+            moves: Vec::new(),
+        }
+    }
+
+    pub fn get_starting_board(&self) -> &Board {
+        &self.starting_board
+    }
+
+    pub fn get_tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets tag value, replacing existing one
+    ///
+    /// FEN and SetUp tags are derived from the starting board, so they are ignored here
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        if name == "FEN" || name == "SetUp" {
+            return;
+        }
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old)) => *old = String::from(value),
+            None => self.tags.push((String::from(name), String::from(value))),
+        }
+    }
+
+    /// Game result from the Result tag, "*" if unknown
+    pub fn get_result(&self) -> &str {
+        self.get_tag("Result").unwrap_or("*")
+    }
+
+    /// Adds move to the end of the main line if it's legal
+    pub fn add_move(&mut self, mv: Move) -> Result<(), String> {
+        let board = self.get_final_board();
+        if !board.get_legal_moves().contains(&mv) {
+            return Err(format!("Illegal move {}", mv));
+        }
+        self.moves.push(PgnMove::new(mv));
+        Ok(())
+    }
+
+    /// Board after all main line moves
+    pub fn get_final_board(&self) -> Board {
+        let mut board = self.starting_board.clone();
+        for node in &self.moves {
+            board.make_move_unchecked(&node.mv);
+        }
+        board
+    }
+
+    /// Serializes game in PGN export format
+    pub fn to_pgn(&self) -> String {
+        let mut pgn = String::new();
+
+        for (name, default) in SEVEN_TAG_ROSTER {
+            let value = self.get_tag(name).unwrap_or(default);
+            pgn.push_str(&format_tag(name, value));
+        }
+
+        let mut other_tags: Vec<(&str, String)> = self
+            .tags
+            .iter()
+            .filter(|(name, _)| !SEVEN_TAG_ROSTER.iter().any(|(roster, _)| roster == name))
+            .map(|(name, value)| (name.as_str(), value.clone()))
+            .collect();
+        if self.starting_board != Board::default() {
+            other_tags.push(("SetUp", String::from("1")));
+            other_tags.push(("FEN", self.starting_board.get_fen()));
+        }
+        other_tags.sort();
+        for (name, value) in other_tags {
+            pgn.push_str(&format_tag(name, &value));
+        }
+        pgn.push('\n');
+
+        let mut tokens = Vec::new();
+        write_line(&self.starting_board, &self.moves, &mut tokens);
+        tokens.push(String::from(self.get_result()));
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > MAX_LINE_LENGTH {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        pgn.push_str(&line);
+        pgn.push('\n');
+
+        pgn
+    }
+
+    /// Parses first game from PGN text
+    pub fn parse(pgn: &str) -> Result<PgnGame, String> {
+        PgnGame::parse_all(pgn)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| String::from("No game found"))
+    }
+
+    /// Parses all games from PGN text
+    pub fn parse_all(pgn: &str) -> Result<Vec<PgnGame>, String> {
+        let tokens = tokenize(pgn)?;
+        let mut games = Vec::new();
+        let mut index = 0;
+
+        while index < tokens.len() {
+            let mut tags = Vec::new();
+            while let Some(Token::Tag(name, value)) = tokens.get(index) {
+                tags.push((name.clone(), value.clone()));
+                index += 1;
+            }
+
+            let starting_board = match tags.iter().find(|(name, _)| name == "FEN") {
+                Some((_, fen)) => Board::new_from_fen(fen)?,
+                None => Board::default(),
+            };
+            let mut game = PgnGame::new_from_board(starting_board.clone());
+            for (name, value) in &tags {
+                game.set_tag(name, value);
+            }
+
+            game.moves = parse_line(&tokens, &mut index, starting_board, false)?;
+            if let Some(Token::Result(result)) = tokens.get(index) {
+                game.set_tag("Result", result);
+                index += 1;
+            }
+            games.push(game);
+        }
+        Ok(games)
+    }
+}
+
+fn format_tag(name: &str, value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("[{} \"{}\"]\n", name, escaped)
+}
+
+/// Comments can't contain closing brace and are written in a single line of words
+fn push_comment(comment: &str, tokens: &mut Vec<String>) {
+    let words: Vec<String> = comment
+        .replace('}', "")
+        .split_whitespace()
+        .map(String::from)
+        .collect();
+    let last = words.len().saturating_sub(1);
+
+    if words.is_empty() {
+        tokens.push(String::from("{}"));
+    }
+    for (i, word) in words.into_iter().enumerate() {
+        let mut token = word;
+        if i == 0 {
+            token.insert(0, '{');
+        }
+        if i == last {
+            token.push('}');
+        }
+        tokens.push(token);
+    }
+}
+
+fn write_line(board: &Board, moves: &[PgnMove], tokens: &mut Vec<String>) {
+    let mut board = board.clone();
+    // Black moves get their own number at the start of a line and after comments or variations
+    let mut needs_number = true;
+
+    for node in moves {
+        if let Some(comment) = &node.comment_before {
+            push_comment(comment, tokens);
+            needs_number = true;
+        }
+
+        let number = board.get_fullmove_number();
+        match board.get_to_move() {
+            PieceColor::White => tokens.push(format!("{}.", number)),
+            PieceColor::Black if needs_number => tokens.push(format!("{}...", number)),
+            PieceColor::Black => {}
+        }
+        tokens.push(move_to_san(&board, &node.mv));
+        needs_number = false;
+
+        for nag in &node.nags {
+            tokens.push(format!("${}", nag));
+        }
+        if let Some(comment) = &node.comment {
+            push_comment(comment, tokens);
+            needs_number = true;
+        }
+
+        for variation in &node.variations {
+            let start = tokens.len();
+            write_line(&board, variation, tokens);
+            if tokens.len() == start {
+                continue;
+            }
+            // No space after opening and before closing parenthesis
+            tokens[start].insert(0, '(');
+            if let Some(last) = tokens.last_mut() {
+                last.push(')');
+            }
+            needs_number = true;
+        }
+
+        board.make_move_unchecked(&node.mv);
+    }
+}
+
+fn tokenize(pgn: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = pgn.chars().peekable();
+    let mut line_start = true;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => {
+                line_start = true;
+                continue;
+            }
+            // Escape mechanism, whole line is ignored
+            '%' if line_start => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+                continue;
+            }
+            c if c.is_whitespace() => {}
+            '[' => {
+                let mut name = String::new();
+                while let Some(c) = chars.peek() {
+                    if c.is_whitespace() || *c == '"' {
+                        break;
+                    }
+                    name.push(*c);
+                    chars.next();
+                }
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+                if chars.next() != Some('"') {
+                    return Err(format!("Tag {} has no value", name));
+                }
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => match chars.next() {
+                            Some(c) => value.push(c),
+                            None => return Err(format!("Unterminated tag {}", name)),
+                        },
+                        Some('"') => break,
+                        Some(c) => value.push(c),
+                        None => return Err(format!("Unterminated tag {}", name)),
+                    }
+                }
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                }
+                tokens.push(Token::Tag(name, value));
+            }
+            '{' => {
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => comment.push(c),
+                        None => return Err(String::from("Unterminated comment")),
+                    }
+                }
+                let words: Vec<&str> = comment.split_whitespace().collect();
+                tokens.push(Token::Comment(words.join(" ")));
+            }
+            ';' => {
+                let mut comment = String::new();
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                    comment.push(c);
+                }
+                tokens.push(Token::Comment(String::from(comment.trim())));
+                line_start = true;
+                continue;
+            }
+            '(' => tokens.push(Token::VariationStart),
+            ')' => tokens.push(Token::VariationEnd),
+            '$' => {
+                let mut nag = String::new();
+                while let Some(c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                    nag.push(*c);
+                    chars.next();
+                }
+                let nag = nag.parse().map_err(|_| format!("Invalid NAG ${}", nag))?;
+                tokens.push(Token::Nag(nag));
+            }
+            c => {
+                let mut symbol = String::from(c);
+                while let Some(c) = chars.peek() {
+                    if c.is_whitespace() || "[]{}();$".contains(*c) {
+                        break;
+                    }
+                    symbol.push(*c);
+                    chars.next();
+                }
+                push_symbol(symbol, &mut tokens);
+            }
+        }
+        line_start = false;
+    }
+    Ok(tokens)
+}
+
+/// Splits symbol into move number, move and suffix annotation
+fn push_symbol(symbol: String, tokens: &mut Vec<Token>) {
+    if RESULTS.contains(&symbol.as_str()) {
+        tokens.push(Token::Result(symbol));
+        return;
+    }
+
+    // Move numbers like "12." or "12..." might be glued to the move
+    let without_number = symbol.trim_start_matches(|c: char| c.is_ascii_digit());
+    let symbol = if without_number.starts_with('.') || without_number.is_empty() {
+        without_number.trim_start_matches('.')
+    } else {
+        symbol.as_str()
+    };
+    if symbol.is_empty() {
+        return;
+    }
+
+    let san = symbol.trim_end_matches(['!', '?']);
+    let suffix = &symbol[san.len()..];
+    if !san.is_empty() {
+        tokens.push(Token::Symbol(String::from(san)));
+    }
+    if let Some((_, nag)) = SUFFIX_ANNOTATIONS.iter().find(|(s, _)| *s == suffix) {
+        tokens.push(Token::Nag(*nag));
+    }
+}
+
+fn parse_line(
+    tokens: &[Token],
+    index: &mut usize,
+    board: Board,
+    is_variation: bool,
+) -> Result<Vec<PgnMove>, String> {
+    let mut moves: Vec<PgnMove> = Vec::new();
+    let mut board = board;
+    let mut board_before_last = None;
+    let mut pending_comment: Option<String> = None;
+
+    while let Some(token) = tokens.get(*index) {
+        match token {
+            Token::Symbol(san) => {
+                let mv = san_to_move(&board, san)?;
+                let mut node = PgnMove::new(mv);
+                node.comment_before = pending_comment.take();
+                board_before_last = Some(board.clone());
+                board.make_move_unchecked(&mv);
+                moves.push(node);
+            }
+            Token::Nag(nag) => match moves.last_mut() {
+                Some(node) => node.nags.push(*nag),
+                None => return Err(format!("NAG ${} before any move", nag)),
+            },
+            Token::Comment(comment) => match moves.last_mut() {
+                Some(node) => {
+                    node.comment = Some(match node.comment.take() {
+                        Some(existing) => format!("{} {}", existing, comment),
+                        None => comment.clone(),
+                    })
+                }
+                None => {
+                    pending_comment = Some(match pending_comment.take() {
+                        Some(existing) => format!("{} {}", existing, comment),
+                        None => comment.clone(),
+                    })
+                }
+            },
+            Token::VariationStart => {
+                let before = board_before_last
+                    .clone()
+                    .ok_or_else(|| String::from("Variation before any move"))?;
+                *index += 1;
+                let variation = parse_line(tokens, index, before, true)?;
+                // parse_line stops at the closing parenthesis, so the loop increment skips it
+                if let Some(node) = moves.last_mut() {
+                    node.variations.push(variation);
+                }
+            }
+            Token::VariationEnd => {
+                if is_variation {
+                    return Ok(moves);
+                }
+                return Err(String::from("Unexpected closing parenthesis"));
+            }
+            Token::Result(_) | Token::Tag(..) => break,
+        }
+        *index += 1;
+    }
+
+    if is_variation {
+        return Err(String::from("Unterminated variation"));
+    }
+    Ok(moves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::Position;
+
+    fn create_game(moves: &[&str]) -> PgnGame {
+        let mut game = PgnGame::default();
+        for san in moves {
+            let mv = san_to_move(&game.get_final_board(), san).unwrap();
+            game.add_move(mv).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn it_writes_seven_tag_roster_in_order() {
+        let mut game = create_game(&["e4", "e5"]);
+        game.set_tag("White", "Kasparov, Garry");
+        game.set_tag("Annotator", "Szpess");
+        game.set_tag("Result", "1/2-1/2");
+        game.set_tag("ECO", "C20");
+
+        assert_eq!(
+            game.to_pgn(),
+            "[Event \"?\"]\n\
+             [Site \"?\"]\n\
+             [Date \"????.??.??\"]\n\
+             [Round \"?\"]\n\
+             [White \"Kasparov, Garry\"]\n\
+             [Black \"?\"]\n\
+             [Result \"1/2-1/2\"]\n\
+             [Annotator \"Szpess\"]\n\
+             [ECO \"C20\"]\n\
+             \n\
+             1. e4 e5 1/2-1/2\n"
+        );
+    }
+
+    #[test]
+    fn it_writes_move_numbers_after_comments_and_variations() {
+        let mut game = create_game(&["e4", "e5", "Nf3", "Nc6"]);
+        game.moves[0].nags.push(1);
+        game.moves[0].comment = Some(String::from("Best by test"));
+        game.moves[2].variations.push(vec![
+            PgnMove::new(Move::new(Position('f', '2'), Position('f', '4'))),
+            PgnMove::new(Move::new(Position('e', '5'), Position('f', '4'))),
+        ]);
+
+        let pgn = game.to_pgn();
+        let movetext = pgn.split("\n\n").nth(1).unwrap();
+        assert_eq!(
+            movetext,
+            "1. e4 $1 {Best by test} 1... e5 2. Nf3 (2. f4 exf4) 2... Nc6 *\n"
+        );
+    }
+
+    #[test]
+    fn it_writes_fen_for_non_default_start() {
+        let board = Board::new_from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 40").unwrap();
+        let mut game = PgnGame::new_from_board(board);
+        game.add_move(Move::new(Position('e', '8'), Position('d', '7')))
+            .unwrap();
+        game.add_move(Move::new(Position('e', '2'), Position('e', '4')))
+            .unwrap();
+
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 40\"]\n[SetUp \"1\"]\n"));
+        assert!(pgn.ends_with("\n40... Kd7 41. e4 *\n"));
+    }
+
+    #[test]
+    fn it_wraps_lines() {
+        let mut game = create_game(&[
+            "e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Ba4", "Nf6", "O-O", "Be7", "Re1", "b5", "Bb3",
+            "d6", "c3", "O-O", "h3", "Nb8", "d4", "Nbd7",
+        ]);
+        game.moves[5].comment = Some(String::from(
+            "The Morphy Defence, played in the vast majority of games with Bb5",
+        ));
+
+        let pgn = game.to_pgn();
+        let movetext = pgn.split("\n\n").nth(1).unwrap();
+        assert!(movetext.lines().count() > 1);
+        assert!(movetext.lines().all(|line| line.len() <= MAX_LINE_LENGTH));
+    }
+
+    #[test]
+    fn it_round_trips_through_reader() {
+        let mut game = create_game(&["d4", "d5", "c4", "e6", "Nc3", "Nf6", "Bg5", "Be7"]);
+        game.set_tag("Event", "Test \"quoted\" event");
+        game.set_tag("Result", "1-0");
+        game.moves[1].comment = Some(String::from("Classical"));
+        game.moves[2].nags.push(5);
+        game.moves[3].variations.push(vec![
+            PgnMove::new(Move::new(Position('d', '5'), Position('c', '4'))),
+            PgnMove::new(Move::new(Position('e', '2'), Position('e', '4'))),
+        ]);
+        game.moves[3].variations.push(vec![PgnMove::new(Move::new(
+            Position('c', '7'),
+            Position('c', '6'),
+        ))]);
+        game.moves[3].variations[1][0].comment_before = Some(String::from("Slav"));
+
+        let parsed = PgnGame::parse(&game.to_pgn()).unwrap();
+        assert!(parsed.moves == game.moves);
+        assert_eq!(parsed.get_tag("Event"), game.get_tag("Event"));
+        assert_eq!(parsed.to_pgn(), game.to_pgn());
+    }
+
+    #[test]
+    fn it_round_trips_game_from_fen() {
+        let board = Board::new_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 5 20").unwrap();
+        let mut game = PgnGame::new_from_board(board);
+        game.add_move(Move::new(Position('e', '1'), Position('g', '1')))
+            .unwrap();
+
+        let parsed = PgnGame::parse(&game.to_pgn()).unwrap();
+        assert!(parsed.get_starting_board() == game.get_starting_board());
+        assert!(parsed.moves == game.moves);
+    }
+
+    #[test]
+    fn it_parses_suffix_annotations_and_glued_move_numbers() {
+        let game = PgnGame::parse("1.e4! e5?! 2.Nf3 ; rest of line comment\n*").unwrap();
+
+        assert_eq!(game.moves.len(), 3);
+        assert_eq!(game.moves[0].nags, vec![1]);
+        assert_eq!(game.moves[1].nags, vec![6]);
+        assert_eq!(
+            game.moves[2].comment.as_deref(),
+            Some("rest of line comment")
+        );
+    }
+
+    #[test]
+    fn it_parses_multiple_games() {
+        let games =
+            PgnGame::parse_all("[Event \"A\"]\n\n1. e4 1-0\n\n[Event \"B\"]\n\n1. d4 d5 0-1\n")
+                .unwrap();
+
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].get_result(), "1-0");
+        assert_eq!(games[1].get_tag("Event"), Some("B"));
+        assert_eq!(games[1].moves.len(), 2);
+    }
+
+    #[test]
+    fn it_returns_error_for_illegal_move() {
+        assert!(PgnGame::parse("1. e5 *").is_err());
+        assert!(PgnGame::parse("1. e4 (1. d4 *").is_err());
+    }
+}
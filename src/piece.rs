@@ -1,6 +1,6 @@
-use crate::{board::Board, position::Position};
+use crate::{board::Board, castling_rights::CastlingSide, position::Position};
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Piece {
     King(PieceColor, Position),
     Queen(PieceColor, Position),
@@ -9,13 +9,121 @@ pub enum Piece {
     Knight(PieceColor, Position),
     Pawn(PieceColor, Position),
 }
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum PieceColor {
     White,
     Black,
 }
 
+/// Piece type without color and position, used e.g. for promotions
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PieceKind {
+    King,
+    Queen,
+    Rook,
+    Bishop,
+    Knight,
+    Pawn,
+}
+
+pub const ROOK_DIRECTIONS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+pub const BISHOP_DIRECTIONS: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+pub const KNIGHT_OFFSETS: [(i32, i32); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+pub const KING_OFFSETS: [(i32, i32); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+impl PieceColor {
+    pub fn opposite(&self) -> PieceColor {
+        match self {
+            PieceColor::White => PieceColor::Black,
+            PieceColor::Black => PieceColor::White,
+        }
+    }
+
+    /// Direction in which pawns of this color move, as rank delta
+    pub fn pawn_direction(&self) -> i32 {
+        match self {
+            PieceColor::White => 1,
+            PieceColor::Black => -1,
+        }
+    }
+
+    /// Rank on which pieces of this color start, '1' for White and '8' for Black
+    pub fn back_rank(&self) -> char {
+        match self {
+            PieceColor::White => '1',
+            PieceColor::Black => '8',
+        }
+    }
+}
+
+impl PieceKind {
+    /// Uppercase letter used by FEN and SAN
+    pub fn get_char(&self) -> char {
+        match self {
+            PieceKind::King => 'K',
+            PieceKind::Queen => 'Q',
+            PieceKind::Rook => 'R',
+            PieceKind::Bishop => 'B',
+            PieceKind::Knight => 'N',
+            PieceKind::Pawn => 'P',
+        }
+    }
+
+    /// Accepts both upper and lowercase letters
+    pub fn from_char(c: char) -> Option<PieceKind> {
+        match c.to_ascii_uppercase() {
+            'K' => Some(PieceKind::King),
+            'Q' => Some(PieceKind::Queen),
+            'R' => Some(PieceKind::Rook),
+            'B' => Some(PieceKind::Bishop),
+            'N' => Some(PieceKind::Knight),
+            'P' => Some(PieceKind::Pawn),
+            _ => None,
+        }
+    }
+}
+
 impl Piece {
+    pub fn new(kind: PieceKind, color: PieceColor, pos: Position) -> Piece {
+        match kind {
+            PieceKind::King => Piece::King(color, pos),
+            PieceKind::Queen => Piece::Queen(color, pos),
+            PieceKind::Rook => Piece::Rook(color, pos),
+            PieceKind::Bishop => Piece::Bishop(color, pos),
+            PieceKind::Knight => Piece::Knight(color, pos),
+            PieceKind::Pawn => Piece::Pawn(color, pos),
+        }
+    }
+
+    /// Creates piece from FEN letter, uppercase being White and lowercase Black
+    pub fn from_fen_char(c: char, pos: Position) -> Option<Piece> {
+        let kind = PieceKind::from_char(c)?;
+        let color = if c.is_ascii_uppercase() {
+            PieceColor::White
+        } else {
+            PieceColor::Black
+        };
+        Some(Piece::new(kind, color, pos))
+    }
+
     pub fn get_char(&self, as_icon: bool) -> char {
         match self {
             Piece::King(color, _) => match color {
@@ -53,14 +161,14 @@ impl Piece {
             Piece::Rook(color, _) => match color {
                 PieceColor::White => {
                     if as_icon {
-                        '♜'
+                        '♖'
                     } else {
                         'R'
                     }
                 }
                 PieceColor::Black => {
                     if as_icon {
-                        '♖'
+                        '♜'
                     } else {
                         'r'
                     }
@@ -69,14 +177,14 @@ impl Piece {
             Piece::Bishop(color, _) => match color {
                 PieceColor::White => {
                     if as_icon {
-                        '♝'
+                        '♗'
                     } else {
                         'B'
                     }
                 }
                 PieceColor::Black => {
                     if as_icon {
-                        '♗'
+                        '♝'
                     } else {
                         'b'
                     }
@@ -85,14 +193,14 @@ impl Piece {
             Piece::Knight(color, _) => match color {
                 PieceColor::White => {
                     if as_icon {
-                        '♞'
+                        '♘'
                     } else {
                         'N'
                     }
                 }
                 PieceColor::Black => {
                     if as_icon {
-                        '♘'
+                        '♞'
                     } else {
                         'n'
                     }
@@ -101,14 +209,14 @@ impl Piece {
             Piece::Pawn(color, _) => match color {
                 PieceColor::White => {
                     if as_icon {
-                        '♟'
+                        '♙'
                     } else {
                         'P'
                     }
                 }
                 PieceColor::Black => {
                     if as_icon {
-                        '♙'
+                        '♟'
                     } else {
                         'p'
                     }
@@ -117,6 +225,17 @@ impl Piece {
         }
     }
 
+    pub fn get_kind(&self) -> PieceKind {
+        match self {
+            Piece::King(..) => PieceKind::King,
+            Piece::Queen(..) => PieceKind::Queen,
+            Piece::Rook(..) => PieceKind::Rook,
+            Piece::Bishop(..) => PieceKind::Bishop,
+            Piece::Knight(..) => PieceKind::Knight,
+            Piece::Pawn(..) => PieceKind::Pawn,
+        }
+    }
+
     pub fn get_position(&self) -> &Position {
         match self {
             Piece::King(_, pos)
@@ -149,10 +268,131 @@ impl Piece {
         }
     }
 
-    pub fn get_legal_moves(&self, board: Board) -> Vec<Position> {
+    /// Returns squares this piece can legally move to on a given board
+    ///
+    /// Promotions are not expanded here, a pawn reaching last rank is returned once
+    pub fn get_legal_moves(&self, board: &Board) -> Vec<Position> {
+        let from = *self.get_position();
+        self.get_pseudo_legal_moves(board)
+            .into_iter()
+            .filter(|to| board.is_move_legal(from, *to))
+            .collect()
+    }
+
+    /// Squares the piece could move to without taking own king safety into account
+    pub fn get_pseudo_legal_moves(&self, board: &Board) -> Vec<Position> {
+        let color = *self.get_color();
+        let pos = *self.get_position();
+
         match self {
-            _ => Vec::new(),
+            Piece::King(..) => {
+                let mut moves = Piece::get_steps(board, pos, color, &KING_OFFSETS);
+                moves.extend(Piece::get_castling_moves(board, pos, color));
+                moves
+            }
+            Piece::Queen(..) => {
+                let mut moves = Piece::get_slides(board, pos, color, &ROOK_DIRECTIONS);
+                moves.extend(Piece::get_slides(board, pos, color, &BISHOP_DIRECTIONS));
+                moves
+            }
+            Piece::Rook(..) => Piece::get_slides(board, pos, color, &ROOK_DIRECTIONS),
+            Piece::Bishop(..) => Piece::get_slides(board, pos, color, &BISHOP_DIRECTIONS),
+            Piece::Knight(..) => Piece::get_steps(board, pos, color, &KNIGHT_OFFSETS),
+            Piece::Pawn(..) => Piece::get_pawn_moves(board, pos, color),
+        }
+    }
+
+    fn get_steps(
+        board: &Board,
+        pos: Position,
+        color: PieceColor,
+        offsets: &[(i32, i32)],
+    ) -> Vec<Position> {
+        offsets
+            .iter()
+            .filter_map(|(file, rank)| pos.offset(*file, *rank))
+            .filter(|to| match board.get_piece_by_position(*to) {
+                Some(piece) => *piece.get_color() != color,
+                None => true,
+            })
+            .collect()
+    }
+
+    fn get_slides(
+        board: &Board,
+        pos: Position,
+        color: PieceColor,
+        directions: &[(i32, i32)],
+    ) -> Vec<Position> {
+        let mut moves = Vec::new();
+        for (file, rank) in directions {
+            let mut current = pos;
+            while let Some(to) = current.offset(*file, *rank) {
+                match board.get_piece_by_position(to) {
+                    Some(piece) => {
+                        if *piece.get_color() != color {
+                            moves.push(to);
+                        }
+                        break;
+                    }
+                    None => moves.push(to),
+                }
+                current = to;
+            }
+        }
+        moves
+    }
+
+    fn get_pawn_moves(board: &Board, pos: Position, color: PieceColor) -> Vec<Position> {
+        let mut moves = Vec::new();
+        let direction = color.pawn_direction();
+        let start_rank = match color {
+            PieceColor::White => '2',
+            PieceColor::Black => '7',
+        };
+
+        if let Some(one) = pos.offset(0, direction) {
+            if board.get_piece_by_position(one).is_none() {
+                moves.push(one);
+
+                if pos.1 == start_rank {
+                    if let Some(two) = pos.offset(0, direction * 2) {
+                        if board.get_piece_by_position(two).is_none() {
+                            moves.push(two);
+                        }
+                    }
+                }
+            }
+        }
+
+        for file in [-1, 1] {
+            if let Some(to) = pos.offset(file, direction) {
+                let is_capture = match board.get_piece_by_position(to) {
+                    Some(piece) => *piece.get_color() != color,
+                    None => board.get_en_passant() == Some(to),
+                };
+                if is_capture {
+                    moves.push(to);
+                }
+            }
+        }
+        moves
+    }
+
+    fn get_castling_moves(board: &Board, pos: Position, color: PieceColor) -> Vec<Position> {
+        let mut moves = Vec::new();
+        if pos.1 != color.back_rank() || board.is_square_attacked(pos, color.opposite()) {
+            return moves;
+        }
+
+        for side in [CastlingSide::Kingside, CastlingSide::Queenside] {
+            if board.can_castle(color, side) {
+                if let Some(mv) = board.get_castling_move(color, side) {
+                    moves.push(mv.to);
+                }
+            }
         }
+        moves
     }
 }
 
@@ -167,6 +407,32 @@ mod tests {
         assert_eq!(piece.get_char(false), 'k');
     }
 
+    #[test]
+    fn it_returns_outlined_icons_for_white_and_filled_for_black() {
+        let white = ['♔', '♕', '♖', '♗', '♘', '♙'];
+        let black = ['♚', '♛', '♜', '♝', '♞', '♟'];
+        let kinds = [
+            PieceKind::King,
+            PieceKind::Queen,
+            PieceKind::Rook,
+            PieceKind::Bishop,
+            PieceKind::Knight,
+            PieceKind::Pawn,
+        ];
+
+        for (i, kind) in kinds.iter().enumerate() {
+            let pos = Position('a', '1');
+            assert_eq!(
+                Piece::new(*kind, PieceColor::White, pos).get_char(true),
+                white[i]
+            );
+            assert_eq!(
+                Piece::new(*kind, PieceColor::Black, pos).get_char(true),
+                black[i]
+            );
+        }
+    }
+
     // #[test]
     // fn
 }
@@ -2,7 +2,9 @@
 ///
 /// Panics on other values or on parse fail
 ///
+#[derive(Clone, Copy, Debug)]
 pub struct Position(pub char, pub char);
+#[derive(Clone, Copy, Debug)]
 pub struct BoardIndex(pub usize, pub usize);
 
 impl Position {
@@ -66,6 +68,41 @@ impl Position {
         // Rank is just row + 1 because of 0 indexing
         BoardIndex(file_index, rank_index - 1)
     }
+
+    /// Parses square in algebraic notation, e.g. "e4"
+    ///
+    /// Returns None instead of panicking, so it can be used on user input
+    pub fn parse(square: &str) -> Option<Position> {
+        let mut chars = square.chars();
+        let file = chars.next()?;
+        let rank = chars.next()?;
+
+        if chars.next().is_some() || !matches!(file, 'a'..='h') || !matches!(rank, '1'..='8') {
+            return None;
+        }
+        Some(Position(file, rank))
+    }
+
+    /// Returns position moved by given number of files and ranks or None if it would leave the board
+    pub fn offset(&self, file_delta: i32, rank_delta: i32) -> Option<Position> {
+        let BoardIndex(file, rank) = self.get_indices();
+        let file = file as i32 + file_delta;
+        let rank = rank as i32 + rank_delta;
+
+        if !(0..8).contains(&file) || !(0..8).contains(&rank) {
+            return None;
+        }
+        Some(Position::new_from_indices(BoardIndex(
+            file as usize,
+            rank as usize,
+        )))
+    }
+}
+
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.0, self.1)
+    }
 }
 
 impl PartialEq for Position {
@@ -74,6 +111,15 @@ impl PartialEq for Position {
     }
 }
 
+impl Eq for Position {}
+
+impl std::hash::Hash for Position {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+        self.1.hash(state);
+    }
+}
+
 impl PartialEq for BoardIndex {
     fn eq(&self, other: &Self) -> bool {
         self.0 == other.0 && self.1 == other.1
@@ -136,4 +182,28 @@ mod tests {
     fn it_panics_on_incorrect_position_file_index() {
         Position::new_from_indices(BoardIndex(9, 2));
     }
+
+    #[test]
+    fn it_parses_position() {
+        assert!(Position::parse("e4") == Some(Position('e', '4')));
+        assert!(Position::parse("i4").is_none());
+        assert!(Position::parse("e9").is_none());
+        assert!(Position::parse("e44").is_none());
+        assert!(Position::parse("").is_none());
+    }
+
+    #[test]
+    fn it_offsets_position() {
+        let pos = Position::new('b', '2');
+
+        assert!(pos.offset(1, 2) == Some(Position('c', '4')));
+        assert!(pos.offset(-1, -1) == Some(Position('a', '1')));
+        assert!(pos.offset(-2, 0).is_none());
+        assert!(pos.offset(0, 7).is_none());
+    }
+
+    #[test]
+    fn it_displays_position() {
+        assert_eq!(Position::new('g', '7').to_string(), "g7");
+    }
 }
@@ -0,0 +1,627 @@
+use std::collections::HashMap;
+
+use crate::{
+    board::Board,
+    chess_move::Move,
+    piece::{PieceColor, PieceKind},
+    position::{BoardIndex, Position},
+    svg_renderer::BoardColors,
+};
+
+/// Piece shapes are defined in a 45x45 box, same as SVG artwork
+const PIECE_BOX: f64 = 45.0;
+/// Each pixel is sampled SAMPLES x SAMPLES times for anti-aliasing
+const SAMPLES: u32 = 3;
+const OUTLINE_WIDTH: f64 = 1.5;
+/// Largest width and height of a rendered board, keeps the image buffer under a gigabyte
+/// and fits GIF dimensions
+const MAX_IMAGE_SIZE: u32 = 16384;
+
+const BLACK: [u8; 3] = [0, 0, 0];
+const WHITE: [u8; 3] = [255, 255, 255];
+
+const BASE: &[(f64, f64)] = &[(10.0, 35.0), (35.0, 35.0), (35.0, 39.0), (10.0, 39.0)];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RgbImage {
+    pub width: u32,
+    pub height: u32,
+    /// Row-major RGB triplets
+    pub pixels: Vec<u8>,
+}
+
+impl RgbImage {
+    fn new(width: u32, height: u32) -> Result<RgbImage, String> {
+        let length = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(3))
+            .ok_or_else(|| format!("Image size {}x{} is too big", width, height))?;
+        Ok(RgbImage {
+            width,
+            height,
+            pixels: vec![0; length],
+        })
+    }
+
+    fn get_index(&self, x: u32, y: u32) -> usize {
+        (y as usize * self.width as usize + x as usize) * 3
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> [u8; 3] {
+        let index = self.get_index(x, y);
+        [
+            self.pixels[index],
+            self.pixels[index + 1],
+            self.pixels[index + 2],
+        ]
+    }
+
+    fn blend_pixel(&mut self, x: u32, y: u32, color: [u8; 3], alpha: f64) {
+        let index = self.get_index(x, y);
+        for (pixel, new) in self.pixels[index..index + 3].iter_mut().zip(color) {
+            let old = *pixel as f64;
+            *pixel = (old + (new as f64 - old) * alpha).round() as u8;
+        }
+    }
+
+    pub fn to_png(&self) -> Result<Vec<u8>, String> {
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+        writer
+            .write_image_data(&self.pixels)
+            .map_err(|e| e.to_string())?;
+        writer.finish().map_err(|e| e.to_string())?;
+        Ok(png)
+    }
+}
+
+enum Shape {
+    Polygon(&'static [(f64, f64)]),
+    Circle(f64, f64, f64),
+    /// Line segment with width
+    Segment(f64, f64, f64, f64, f64),
+}
+
+/// Body shapes are filled with piece color and outlined, details are drawn in contrasting color
+enum Layer {
+    Body(Shape),
+    Detail(Shape),
+}
+
+/// Pre-rendered piece, every pixel is color with coverage
+struct Sprite {
+    size: u32,
+    pixels: Vec<([u8; 3], f64)>,
+}
+
+/// Renders board to RGB image, PNG or animated GIF without any native dependencies
+#[derive(Clone, Debug, PartialEq)]
+pub struct RasterRenderer {
+    /// Size of a single square in pixels
+    pub square_size: u32,
+    pub colors: BoardColors,
+    /// Shows board from Black's side
+    pub flipped: bool,
+    /// Highlights origin and destination square of this move
+    pub last_move: Option<Move>,
+    /// Delay between GIF frames in milliseconds, GIF stores it with 10ms precision
+    pub frame_delay_ms: u32,
+}
+
+impl Default for RasterRenderer {
+    fn default() -> Self {
+        RasterRenderer {
+            square_size: 45,
+            colors: BoardColors::default(),
+            flipped: false,
+            last_move: None,
+            frame_delay_ms: 1000,
+        }
+    }
+}
+
+impl RasterRenderer {
+    pub fn render(&self, board: &Board) -> Result<RgbImage, String> {
+        let mut sprites = HashMap::new();
+        self.render_with_sprites(board, self.last_move, &mut sprites)
+    }
+
+    pub fn render_png(&self, board: &Board) -> Result<Vec<u8>, String> {
+        self.render(board)?.to_png()
+    }
+
+    /// Animated GIF starting from `board`, with one frame per move, last move is highlighted
+    pub fn render_gif(&self, board: &Board, moves: &[Move]) -> Result<Vec<u8>, String> {
+        // MAX_IMAGE_SIZE fits the 16-bit GIF dimensions
+        let size = self.get_image_size()?;
+
+        let mut gif = Vec::new();
+        {
+            let mut encoder = gif::Encoder::new(&mut gif, size as u16, size as u16, &[])
+                .map_err(|e| e.to_string())?;
+            encoder
+                .set_repeat(gif::Repeat::Infinite)
+                .map_err(|e| e.to_string())?;
+
+            let mut sprites = HashMap::new();
+            let mut board = board.clone();
+            let mut last_move = self.last_move;
+            for index in 0..=moves.len() {
+                let image = self.render_with_sprites(&board, last_move, &mut sprites)?;
+                let mut frame =
+                    gif::Frame::from_rgb_speed(size as u16, size as u16, &image.pixels, 10);
+                frame.delay = (self.frame_delay_ms / 10).min(u16::MAX as u32) as u16;
+                encoder.write_frame(&frame).map_err(|e| e.to_string())?;
+
+                if let Some(mv) = moves.get(index) {
+                    if !board.get_legal_moves().contains(mv) {
+                        return Err(format!("Illegal move {}", mv));
+                    }
+                    board.make_move_unchecked(mv);
+                    last_move = Some(*mv);
+                }
+            }
+        }
+        Ok(gif)
+    }
+
+    fn render_with_sprites(
+        &self,
+        board: &Board,
+        last_move: Option<Move>,
+        sprites: &mut HashMap<(PieceKind, PieceColor), Sprite>,
+    ) -> Result<RgbImage, String> {
+        let light = parse_hex_color(&self.colors.light)?;
+        let dark = parse_hex_color(&self.colors.dark)?;
+        let highlight = parse_hex_color(&self.colors.highlight)?;
+        let image_size = self.get_image_size()?;
+        let size = self.square_size;
+        let mut image = RgbImage::new(image_size, image_size)?;
+
+        for rank in 0..8 {
+            for file in 0..8 {
+                let pos = Position::new_from_indices(BoardIndex(file, rank));
+                let (x0, y0) = self.get_square_origin(pos);
+                let is_highlighted = last_move.is_some_and(|mv| mv.from == pos || mv.to == pos);
+                let color = if (file + rank).is_multiple_of(2) {
+                    dark
+                } else {
+                    light
+                };
+
+                for y in y0..y0 + size {
+                    for x in x0..x0 + size {
+                        image.blend_pixel(x, y, color, 1.0);
+                        if is_highlighted {
+                            image.blend_pixel(x, y, highlight, 0.6);
+                        }
+                    }
+                }
+
+                if let Some(piece) = board.get_piece_by_position(pos) {
+                    let key = (piece.get_kind(), *piece.get_color());
+                    let sprite = sprites
+                        .entry(key)
+                        .or_insert_with(|| render_sprite(key.0, key.1, size));
+                    for y in 0..sprite.size {
+                        for x in 0..sprite.size {
+                            let (color, alpha) = sprite.pixels[(y * sprite.size + x) as usize];
+                            if alpha > 0.0 {
+                                image.blend_pixel(x0 + x, y0 + y, color, alpha);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(image)
+    }
+
+    /// Width and height of the board in pixels, at most MAX_IMAGE_SIZE
+    fn get_image_size(&self) -> Result<u32, String> {
+        if self.square_size == 0 {
+            return Err(String::from("Square size must be positive"));
+        }
+        self.square_size
+            .checked_mul(8)
+            .filter(|&size| size <= MAX_IMAGE_SIZE)
+            .ok_or_else(|| {
+                format!(
+                    "Square size {} is too big, the image is at most {} pixels wide",
+                    self.square_size, MAX_IMAGE_SIZE
+                )
+            })
+    }
+
+    /// Top left corner of a square in pixels
+    fn get_square_origin(&self, pos: Position) -> (u32, u32) {
+        let BoardIndex(file, rank) = pos.get_indices();
+        let (column, row) = if self.flipped {
+            (7 - file, rank)
+        } else {
+            (file, 7 - rank)
+        };
+        (
+            column as u32 * self.square_size,
+            row as u32 * self.square_size,
+        )
+    }
+}
+
+fn parse_hex_color(color: &str) -> Result<[u8; 3], String> {
+    let hex = color.trim_start_matches('#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(format!("Color '{}' needs to be in #rrggbb format", color));
+    }
+    let channel = |i: usize| {
+        u8::from_str_radix(&hex[i..i + 2], 16)
+            .map_err(|_| format!("Color '{}' needs to be in #rrggbb format", color))
+    };
+    Ok([channel(0)?, channel(2)?, channel(4)?])
+}
+
+fn render_sprite(kind: PieceKind, color: PieceColor, size: u32) -> Sprite {
+    let (fill, detail) = match color {
+        PieceColor::White => (WHITE, BLACK),
+        PieceColor::Black => (BLACK, WHITE),
+    };
+    let layers = get_layers(kind);
+    let scale = PIECE_BOX / size as f64;
+    let mut pixels = Vec::with_capacity((size * size) as usize);
+
+    for y in 0..size {
+        for x in 0..size {
+            let mut sum = [0.0; 3];
+            let mut covered = 0;
+            for sy in 0..SAMPLES {
+                for sx in 0..SAMPLES {
+                    let px = (x as f64 + (sx as f64 + 0.5) / SAMPLES as f64) * scale;
+                    let py = (y as f64 + (sy as f64 + 0.5) / SAMPLES as f64) * scale;
+                    if let Some(sample) = sample_layers(&layers, px, py, fill, detail) {
+                        for channel in 0..3 {
+                            sum[channel] += sample[channel] as f64;
+                        }
+                        covered += 1;
+                    }
+                }
+            }
+
+            if covered == 0 {
+                pixels.push((BLACK, 0.0));
+            } else {
+                let average = sum.map(|channel| (channel / covered as f64).round() as u8);
+                pixels.push((average, covered as f64 / (SAMPLES * SAMPLES) as f64));
+            }
+        }
+    }
+    Sprite { size, pixels }
+}
+
+/// Color of the topmost layer at a point, later layers are drawn over earlier ones
+fn sample_layers(
+    layers: &[Layer],
+    x: f64,
+    y: f64,
+    fill: [u8; 3],
+    detail: [u8; 3],
+) -> Option<[u8; 3]> {
+    let mut color = None;
+    for layer in layers {
+        match layer {
+            Layer::Body(shape) => {
+                if get_distance_to_edge(shape, x, y) <= OUTLINE_WIDTH / 2.0 {
+                    color = Some(BLACK);
+                } else if is_inside(shape, x, y) {
+                    color = Some(fill);
+                }
+            }
+            Layer::Detail(shape) => {
+                if is_inside(shape, x, y) {
+                    color = Some(detail);
+                }
+            }
+        }
+    }
+    color
+}
+
+fn is_inside(shape: &Shape, x: f64, y: f64) -> bool {
+    match shape {
+        Shape::Polygon(points) => {
+            // Even-odd rule
+            let mut inside = false;
+            let mut j = points.len() - 1;
+            for i in 0..points.len() {
+                let (xi, yi) = points[i];
+                let (xj, yj) = points[j];
+                if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+                    inside = !inside;
+                }
+                j = i;
+            }
+            inside
+        }
+        Shape::Circle(cx, cy, r) => (x - cx).powi(2) + (y - cy).powi(2) <= r * r,
+        Shape::Segment(x1, y1, x2, y2, width) => {
+            get_distance_to_segment(x, y, (*x1, *y1), (*x2, *y2)) <= width / 2.0
+        }
+    }
+}
+
+fn get_distance_to_edge(shape: &Shape, x: f64, y: f64) -> f64 {
+    match shape {
+        Shape::Polygon(points) => {
+            let mut distance = f64::MAX;
+            let mut j = points.len() - 1;
+            for i in 0..points.len() {
+                distance = distance.min(get_distance_to_segment(x, y, points[j], points[i]));
+                j = i;
+            }
+            distance
+        }
+        Shape::Circle(cx, cy, r) => (((x - cx).powi(2) + (y - cy).powi(2)).sqrt() - r).abs(),
+        Shape::Segment(x1, y1, x2, y2, width) => {
+            (get_distance_to_segment(x, y, (*x1, *y1), (*x2, *y2)) - width / 2.0).abs()
+        }
+    }
+}
+
+fn get_distance_to_segment(x: f64, y: f64, a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length = dx * dx + dy * dy;
+    let t = if length == 0.0 {
+        0.0
+    } else {
+        (((x - a.0) * dx + (y - a.1) * dy) / length).clamp(0.0, 1.0)
+    };
+    let (px, py) = (a.0 + t * dx, a.1 + t * dy);
+    ((x - px).powi(2) + (y - py).powi(2)).sqrt()
+}
+
+/// Polygon approximations of the geometric SVG piece set
+fn get_layers(kind: PieceKind) -> Vec<Layer> {
+    let mut layers = match kind {
+        PieceKind::Pawn => vec![
+            Layer::Body(Shape::Circle(22.5, 14.0, 5.0)),
+            Layer::Body(Shape::Polygon(&[
+                (17.0, 21.0),
+                (28.0, 21.0),
+                (26.0, 25.0),
+                (29.0, 35.0),
+                (16.0, 35.0),
+                (19.0, 25.0),
+            ])),
+        ],
+        PieceKind::Rook => vec![
+            Layer::Body(Shape::Polygon(&[
+                (12.0, 9.0),
+                (16.0, 9.0),
+                (16.0, 12.0),
+                (20.0, 12.0),
+                (20.0, 9.0),
+                (25.0, 9.0),
+                (25.0, 12.0),
+                (29.0, 12.0),
+                (29.0, 9.0),
+                (33.0, 9.0),
+                (33.0, 15.0),
+                (30.0, 18.0),
+                (30.0, 31.0),
+                (33.0, 34.0),
+                (33.0, 35.0),
+                (12.0, 35.0),
+                (12.0, 34.0),
+                (15.0, 31.0),
+                (15.0, 18.0),
+                (12.0, 15.0),
+            ])),
+            Layer::Detail(Shape::Segment(15.0, 18.0, 30.0, 18.0, 1.0)),
+            Layer::Detail(Shape::Segment(15.0, 31.0, 30.0, 31.0, 1.0)),
+        ],
+        PieceKind::Knight => vec![
+            Layer::Body(Shape::Polygon(&[
+                (14.0, 35.0),
+                (31.0, 35.0),
+                (30.5, 28.0),
+                (29.5, 21.0),
+                (31.0, 14.0),
+                (28.0, 10.0),
+                (24.0, 8.0),
+                (20.0, 8.0),
+                (19.0, 11.0),
+                (15.0, 13.0),
+                (11.0, 18.0),
+                (10.0, 23.0),
+                (12.0, 26.0),
+                (16.0, 24.0),
+                (19.0, 22.0),
+                (17.0, 27.0),
+                (14.5, 31.0),
+            ])),
+            Layer::Detail(Shape::Circle(20.0, 14.0, 1.5)),
+        ],
+        PieceKind::Bishop => vec![
+            Layer::Body(Shape::Circle(22.5, 8.0, 2.5)),
+            Layer::Body(Shape::Polygon(&[
+                (22.5, 10.0),
+                (18.0, 13.5),
+                (15.5, 19.0),
+                (15.8, 24.5),
+                (17.0, 29.0),
+                (28.0, 29.0),
+                (29.2, 24.5),
+                (29.5, 19.0),
+                (27.0, 13.5),
+            ])),
+            Layer::Body(Shape::Polygon(&[
+                (15.0, 31.0),
+                (30.0, 31.0),
+                (31.0, 35.0),
+                (14.0, 35.0),
+            ])),
+            Layer::Detail(Shape::Segment(20.0, 18.0, 25.0, 23.0, 1.5)),
+        ],
+        PieceKind::Queen => vec![
+            Layer::Body(Shape::Polygon(&[
+                (11.0, 31.0),
+                (9.0, 14.0),
+                (13.0, 25.0),
+                (15.5, 11.0),
+                (19.0, 24.0),
+                (22.5, 9.0),
+                (26.0, 24.0),
+                (29.5, 11.0),
+                (32.0, 25.0),
+                (36.0, 14.0),
+                (34.0, 31.0),
+            ])),
+            Layer::Body(Shape::Circle(9.0, 13.0, 2.0)),
+            Layer::Body(Shape::Circle(15.5, 10.0, 2.0)),
+            Layer::Body(Shape::Circle(22.5, 8.0, 2.0)),
+            Layer::Body(Shape::Circle(29.5, 10.0, 2.0)),
+            Layer::Body(Shape::Circle(36.0, 13.0, 2.0)),
+            Layer::Body(Shape::Polygon(&[
+                (11.0, 31.0),
+                (34.0, 31.0),
+                (33.0, 35.0),
+                (12.0, 35.0),
+            ])),
+        ],
+        PieceKind::King => vec![
+            Layer::Body(Shape::Segment(22.5, 5.0, 22.5, 13.0, 2.0)),
+            Layer::Body(Shape::Segment(19.0, 8.5, 26.0, 8.5, 2.0)),
+            Layer::Body(Shape::Polygon(&[
+                (22.5, 14.0),
+                (19.5, 15.0),
+                (18.0, 18.0),
+                (18.5, 21.0),
+                (14.0, 19.0),
+                (10.0, 20.5),
+                (8.5, 24.0),
+                (9.0, 27.0),
+                (12.0, 32.0),
+                (33.0, 32.0),
+                (36.0, 27.0),
+                (36.5, 24.0),
+                (35.0, 20.5),
+                (31.0, 19.0),
+                (26.5, 21.0),
+                (27.0, 18.0),
+                (25.5, 15.0),
+            ])),
+            Layer::Body(Shape::Polygon(&[
+                (12.0, 32.0),
+                (33.0, 32.0),
+                (33.0, 35.0),
+                (12.0, 35.0),
+            ])),
+            Layer::Detail(Shape::Segment(14.0, 27.0, 31.0, 27.0, 1.0)),
+        ],
+    };
+    layers.push(Layer::Body(Shape::Polygon(BASE)));
+    layers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_renderer() -> RasterRenderer {
+        RasterRenderer {
+            square_size: 12,
+            ..RasterRenderer::default()
+        }
+    }
+
+    #[test]
+    fn it_renders_squares_and_pieces() {
+        let board = Board::new_from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let image = create_renderer().render(&board).unwrap();
+
+        assert_eq!(image.width, 96);
+        assert_eq!(image.height, 96);
+        // a1 is dark and in the bottom left corner, h1 is light
+        assert_eq!(image.get_pixel(0, 95), [0xb5, 0x88, 0x63]);
+        assert_eq!(image.get_pixel(95, 95), [0xf0, 0xd9, 0xb5]);
+        // White king's base is drawn on e1
+        let king_pixel = image.get_pixel(4 * 12 + 6, 7 * 12 + 9);
+        assert_ne!(king_pixel, [0xf0, 0xd9, 0xb5]);
+    }
+
+    #[test]
+    fn it_highlights_last_move_and_flips() {
+        let renderer = RasterRenderer {
+            flipped: true,
+            last_move: Some(Move::new(Position('a', '1'), Position('a', '2'))),
+            ..create_renderer()
+        };
+        let image = renderer
+            .render(&Board::new_from_fen("8/8/8/8/8/8/8/8 w - - 0 1").unwrap())
+            .unwrap();
+
+        // a1 is in the top right corner when flipped
+        assert_ne!(image.get_pixel(95, 0), [0xb5, 0x88, 0x63]);
+        assert_eq!(image.get_pixel(0, 95), [0xb5, 0x88, 0x63]);
+    }
+
+    #[test]
+    fn it_encodes_png() {
+        let png = create_renderer().render_png(&Board::default()).unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+    }
+
+    #[test]
+    fn it_encodes_animated_gif() {
+        let moves = [
+            Move::new(Position('e', '2'), Position('e', '4')),
+            Move::new(Position('e', '7'), Position('e', '5')),
+        ];
+        let renderer = RasterRenderer {
+            frame_delay_ms: 500,
+            ..create_renderer()
+        };
+        let gif = renderer.render_gif(&Board::default(), &moves).unwrap();
+        assert_eq!(&gif[0..6], b"GIF89a");
+
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(gif.as_slice()).unwrap();
+        let mut frames = 0;
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!(frame.delay, 50);
+            frames += 1;
+        }
+        assert_eq!(frames, 3);
+    }
+
+    #[test]
+    fn it_returns_error_for_illegal_gif_move_and_invalid_color() {
+        let renderer = create_renderer();
+        let moves = [Move::new(Position('e', '2'), Position('e', '5'))];
+        assert!(renderer.render_gif(&Board::default(), &moves).is_err());
+
+        let renderer = RasterRenderer {
+            colors: BoardColors::new("red", "#000000", "#000000"),
+            ..create_renderer()
+        };
+        assert!(renderer.render(&Board::default()).is_err());
+    }
+
+    #[test]
+    fn it_returns_error_for_invalid_square_size() {
+        for square_size in [0, MAX_IMAGE_SIZE / 8 + 1, 5000, u32::MAX] {
+            let renderer = RasterRenderer {
+                square_size,
+                ..create_renderer()
+            };
+            assert!(renderer.render(&Board::default()).is_err());
+            assert!(renderer.render_png(&Board::default()).is_err());
+            assert!(renderer.render_gif(&Board::default(), &[]).is_err());
+        }
+        assert!(RgbImage::new(u32::MAX, u32::MAX).is_err());
+    }
+}
@@ -0,0 +1,46 @@
+/// Small SplitMix64 generator, enough for picking random moves without pulling in a crate
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniformly distributed number in `0..n`, `n` must not be zero
+    pub fn below(&mut self, n: usize) -> usize {
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+
+    /// Uniformly distributed number in `0.0..1.0`
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_generates_repeatable_numbers_in_range() {
+        let mut rng = Rng::new(7);
+        let numbers: Vec<usize> = (0..1000).map(|_| rng.below(6)).collect();
+        assert!(numbers.iter().all(|&n| n < 6));
+        assert!((0..6).all(|n| numbers.contains(&n)));
+
+        let mut same = Rng::new(7);
+        assert!(numbers.iter().all(|&n| same.below(6) == n));
+        assert!((0..1000).all(|_| (0.0..1.0).contains(&rng.next_f64())));
+    }
+}
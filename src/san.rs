@@ -0,0 +1,376 @@
+use crate::{
+    board::Board, castling_rights::CastlingSide, chess_move::Move, piece::PieceKind,
+    position::Position,
+};
+
+/// Piece letters used in SAN, castling and squares are the same in every locale
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NotationLocale {
+    /// K, Q, R, B, N
+    #[default]
+    English,
+    /// K, D, T, L, S
+    German,
+    /// R, D, T, F, C
+    French,
+    /// R, D, T, A, C
+    Spanish,
+    /// R, D, T, A, C
+    Italian,
+    /// K, H, W, G, S
+    Polish,
+    /// Figurines of white pieces, e.g. "♘f3"
+    Figurine,
+}
+
+impl NotationLocale {
+    pub fn get_piece_char(&self, kind: PieceKind) -> char {
+        let chars = match self {
+            NotationLocale::English => return kind.get_char(),
+            NotationLocale::German => ['K', 'D', 'T', 'L', 'S', 'B'],
+            NotationLocale::French => ['R', 'D', 'T', 'F', 'C', 'P'],
+            NotationLocale::Spanish => ['R', 'D', 'T', 'A', 'C', 'P'],
+            NotationLocale::Italian => ['R', 'D', 'T', 'A', 'C', 'P'],
+            NotationLocale::Polish => ['K', 'H', 'W', 'G', 'S', 'P'],
+            NotationLocale::Figurine => ['♔', '♕', '♖', '♗', '♘', '♙'],
+        };
+        chars[PIECE_KINDS.iter().position(|k| *k == kind).unwrap()]
+    }
+
+    /// Piece for given letter, figurines of both colors are accepted
+    pub fn get_piece_kind(&self, c: char) -> Option<PieceKind> {
+        let c = match c {
+            '♚'..='♟' if *self == NotationLocale::Figurine => {
+                char::from_u32(c as u32 - 6).unwrap_or(c)
+            }
+            _ => c,
+        };
+        PIECE_KINDS
+            .iter()
+            .find(|kind| self.get_piece_char(**kind) == c)
+            .copied()
+    }
+}
+
+const PIECE_KINDS: [PieceKind; 6] = [
+    PieceKind::King,
+    PieceKind::Queen,
+    PieceKind::Rook,
+    PieceKind::Bishop,
+    PieceKind::Knight,
+    PieceKind::Pawn,
+];
+
+/// Returns move in Standard Algebraic Notation, e.g. "Nbd7", "exd5", "O-O" or "e8=Q+"
+///
+/// Move is expected to be legal on given board
+pub fn move_to_san(board: &Board, mv: &Move) -> String {
+    move_to_localized_san(board, mv, NotationLocale::English)
+}
+
+/// Same as `move_to_san`, but with piece letters of given locale, e.g. "Sbd7" in German
+pub fn move_to_localized_san(board: &Board, mv: &Move, locale: NotationLocale) -> String {
+    let piece = match board.get_piece_by_position(mv.from) {
+        Some(piece) => *piece,
+        None => return mv.get_uci(),
+    };
+    let kind = piece.get_kind();
+
+    let mut san = String::new();
+    if let Some(side) = board.get_castling_side(mv) {
+        san.push_str(match side {
+            CastlingSide::Kingside => "O-O",
+            CastlingSide::Queenside => "O-O-O",
+        });
+    } else {
+        let is_capture = board.get_piece_by_position(mv.to).is_some()
+            || (kind == PieceKind::Pawn && mv.from.0 != mv.to.0);
+
+        if kind == PieceKind::Pawn {
+            if is_capture {
+                san.push(mv.from.0);
+            }
+        } else {
+            san.push(locale.get_piece_char(kind));
+            san.push_str(&get_disambiguation(board, mv, kind));
+        }
+        if is_capture {
+            san.push('x');
+        }
+        san.push_str(&mv.to.to_string());
+        if let Some(promotion) = mv.promotion {
+            san.push('=');
+            san.push(locale.get_piece_char(promotion));
+        }
+    }
+
+    let mut after = board.clone();
+    after.make_move_unchecked(mv);
+    if after.is_in_check() {
+        san.push(if after.get_legal_moves().is_empty() {
+            '#'
+        } else {
+            '+'
+        });
+    }
+    san
+}
+
+/// File, rank or whole square needed to tell apart pieces of the same kind moving to the same square
+fn get_disambiguation(board: &Board, mv: &Move, kind: PieceKind) -> String {
+    let others: Vec<Position> = board
+        .get_legal_moves()
+        .into_iter()
+        .filter(|other| other.to == mv.to && other.from != mv.from)
+        .filter(|other| match board.get_piece_by_position(other.from) {
+            Some(piece) => piece.get_kind() == kind,
+            None => false,
+        })
+        .map(|other| other.from)
+        .collect();
+
+    if others.is_empty() {
+        String::new()
+    } else if others.iter().all(|other| other.0 != mv.from.0) {
+        mv.from.0.to_string()
+    } else if others.iter().all(|other| other.1 != mv.from.1) {
+        mv.from.1.to_string()
+    } else {
+        mv.from.to_string()
+    }
+}
+
+/// Parses move in Standard Algebraic Notation for the side to move
+///
+/// Check, mate and annotation suffixes ("+", "#", "!", "?") are ignored
+pub fn san_to_move(board: &Board, san: &str) -> Result<Move, String> {
+    localized_san_to_move(board, san, NotationLocale::English)
+}
+
+/// Parses SAN written with piece letters of given locale
+pub fn localized_san_to_move(
+    board: &Board,
+    san: &str,
+    locale: NotationLocale,
+) -> Result<Move, String> {
+    let trimmed = san.trim_end_matches(['+', '#', '!', '?']);
+    let legal_moves = board.get_legal_moves();
+
+    if matches!(trimmed, "O-O" | "0-0" | "O-O-O" | "0-0-0") {
+        let side = if trimmed.len() == 3 {
+            CastlingSide::Kingside
+        } else {
+            CastlingSide::Queenside
+        };
+        return legal_moves
+            .into_iter()
+            .find(|mv| board.get_castling_side(mv) == Some(side))
+            .ok_or_else(|| format!("Illegal castling '{}'", san));
+    }
+
+    let mut chars: Vec<char> = trimmed.chars().collect();
+
+    let mut promotion = None;
+    if let Some(last) = chars.last() {
+        if let Some(kind) = locale.get_piece_kind(*last) {
+            promotion = Some(kind);
+            chars.pop();
+            if chars.last() == Some(&'=') {
+                chars.pop();
+            }
+        }
+    }
+
+    if chars.len() < 2 {
+        return Err(format!("Invalid SAN '{}'", san));
+    }
+    let destination: String = chars.split_off(chars.len() - 2).into_iter().collect();
+    let to = Position::parse(&destination)
+        .ok_or_else(|| format!("Invalid destination square in '{}'", san))?;
+
+    let mut kind = PieceKind::Pawn;
+    if let Some(first) = chars.first() {
+        if let Some(piece_kind) = locale.get_piece_kind(*first) {
+            kind = piece_kind;
+            chars.remove(0);
+        } else if !first.is_ascii_lowercase() {
+            return Err(format!("Invalid piece in '{}'", san));
+        }
+    }
+    if chars.last() == Some(&'x') {
+        chars.pop();
+    }
+
+    let mut from_file = None;
+    let mut from_rank = None;
+    for c in chars {
+        match c {
+            'a'..='h' => from_file = Some(c),
+            '1'..='8' => from_rank = Some(c),
+            _ => return Err(format!("Invalid SAN '{}'", san)),
+        }
+    }
+
+    let candidates: Vec<Move> = legal_moves
+        .into_iter()
+        .filter(|mv| mv.to == to && mv.promotion == promotion)
+        .filter(|mv| from_file.is_none_or(|file| mv.from.0 == file))
+        .filter(|mv| from_rank.is_none_or(|rank| mv.from.1 == rank))
+        .filter(|mv| match board.get_piece_by_position(mv.from) {
+            Some(piece) => piece.get_kind() == kind,
+            None => false,
+        })
+        .collect();
+
+    match candidates.as_slice() {
+        [mv] => Ok(*mv),
+        [] => Err(format!("Illegal move '{}'", san)),
+        _ => Err(format!("Ambiguous move '{}'", san)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_creates_san_for_basic_moves() {
+        let board = Board::default();
+
+        assert_eq!(
+            move_to_san(&board, &Move::new(Position('e', '2'), Position('e', '4'))),
+            "e4"
+        );
+        assert_eq!(
+            move_to_san(&board, &Move::new(Position('g', '1'), Position('f', '3'))),
+            "Nf3"
+        );
+    }
+
+    #[test]
+    fn it_creates_san_with_disambiguation_and_check() {
+        let board = Board::new_from_fen("4k3/8/8/8/8/8/8/R4RK1 w - - 0 1").unwrap();
+        assert_eq!(
+            move_to_san(&board, &Move::new(Position('a', '1'), Position('d', '1'))),
+            "Rad1"
+        );
+
+        let board = Board::new_from_fen("4k3/8/8/8/8/R7/8/R3K3 w - - 0 1").unwrap();
+        assert_eq!(
+            move_to_san(&board, &Move::new(Position('a', '3'), Position('a', '2'))),
+            "R3a2"
+        );
+
+        let board = Board::new_from_fen("6k1/5ppp/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+        assert_eq!(
+            move_to_san(&board, &Move::new(Position('a', '1'), Position('a', '8'))),
+            "Ra8#"
+        );
+        assert_eq!(
+            move_to_san(&board, &Move::new(Position('e', '1'), Position('c', '1'))),
+            "O-O-O"
+        );
+    }
+
+    #[test]
+    fn it_creates_san_for_pawn_captures_and_promotions() {
+        let board = Board::new_from_fen("1n2k3/P7/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+
+        assert_eq!(
+            move_to_san(&board, &Move::new(Position('e', '5'), Position('d', '6'))),
+            "exd6"
+        );
+        assert_eq!(
+            move_to_san(
+                &board,
+                &Move::new_promotion(Position('a', '7'), Position('b', '8'), PieceKind::Queen)
+            ),
+            "axb8=Q+"
+        );
+    }
+
+    #[test]
+    fn it_parses_san() {
+        let board = Board::new_from_fen("1n2k3/P7/8/3pP3/8/8/8/R3K2R w KQ d6 0 1").unwrap();
+
+        assert!(
+            san_to_move(&board, "exd6").unwrap()
+                == Move::new(Position('e', '5'), Position('d', '6'))
+        );
+        assert!(
+            san_to_move(&board, "axb8=Q+").unwrap()
+                == Move::new_promotion(Position('a', '7'), Position('b', '8'), PieceKind::Queen)
+        );
+        assert!(
+            san_to_move(&board, "O-O!").unwrap()
+                == Move::new(Position('e', '1'), Position('g', '1'))
+        );
+        assert!(
+            san_to_move(&board, "Rad1").unwrap()
+                == Move::new(Position('a', '1'), Position('d', '1'))
+        );
+    }
+
+    #[test]
+    fn it_returns_error_for_invalid_san() {
+        let board = Board::default();
+
+        assert!(san_to_move(&board, "e5").is_err());
+        assert!(san_to_move(&board, "Qd4").is_err());
+        assert!(san_to_move(&board, "O-O").is_err());
+        assert!(san_to_move(&board, "xyz").is_err());
+    }
+
+    #[test]
+    fn it_creates_localized_san() {
+        let board = Board::new_from_fen("1n2k3/P7/8/8/8/8/8/4K1N1 w - - 0 1").unwrap();
+        let knight_move = Move::new(Position('g', '1'), Position('f', '3'));
+        let promotion =
+            Move::new_promotion(Position('a', '7'), Position('b', '8'), PieceKind::Queen);
+
+        assert_eq!(
+            move_to_localized_san(&board, &knight_move, NotationLocale::German),
+            "Sf3"
+        );
+        assert_eq!(
+            move_to_localized_san(&board, &knight_move, NotationLocale::French),
+            "Cf3"
+        );
+        assert_eq!(
+            move_to_localized_san(&board, &knight_move, NotationLocale::Figurine),
+            "♘f3"
+        );
+        assert_eq!(
+            move_to_localized_san(&board, &promotion, NotationLocale::German),
+            "axb8=D+"
+        );
+    }
+
+    #[test]
+    fn it_parses_localized_san() {
+        let board = Board::new_from_fen("1n2k3/P7/8/8/8/8/8/R3K1N1 w - - 0 1").unwrap();
+        let knight_move = Move::new(Position('g', '1'), Position('f', '3'));
+
+        assert!(
+            localized_san_to_move(&board, "Sf3", NotationLocale::German).unwrap() == knight_move
+        );
+        assert!(
+            localized_san_to_move(&board, "♞f3", NotationLocale::Figurine).unwrap() == knight_move
+        );
+        assert!(
+            localized_san_to_move(&board, "Rd2", NotationLocale::French).unwrap()
+                == Move::new(Position('e', '1'), Position('d', '2'))
+        );
+        assert!(
+            localized_san_to_move(&board, "axb8=D", NotationLocale::German).unwrap()
+                == Move::new_promotion(Position('a', '7'), Position('b', '8'), PieceKind::Queen)
+        );
+        assert!(localized_san_to_move(&board, "Nf3", NotationLocale::German).is_err());
+    }
+
+    #[test]
+    fn it_returns_error_for_ambiguous_san() {
+        let board = Board::new_from_fen("4k3/8/8/8/8/8/8/R4RK1 w - - 0 1").unwrap();
+        assert!(san_to_move(&board, "Rd1").is_err());
+    }
+}
@@ -0,0 +1,1738 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    board::Board,
+    chess_move::Move,
+    eval::{self, EvalParams},
+    mate_search::{MateSearch, MateSearchStatus},
+    move_ordering::{get_mvv_lva, is_tactical, Heuristics, PieceSquare},
+    nnue::{Accumulator, Network},
+    piece::{PieceColor, PieceKind},
+    rng::Rng,
+    see::{get_captured_kind, get_piece_value, see},
+    skill::Skill,
+    time_manager::{TimeManager, DEFAULT_MOVE_OVERHEAD},
+    transposition_table::{Bound, TranspositionTable},
+};
+
+pub const MATE_SCORE: i32 = 30000;
+pub const INFINITY: i32 = 32000;
+pub const MAX_PLY: usize = 128;
+
+const ASPIRATION_DEPTH: u32 = 4;
+const ASPIRATION_WINDOW: i32 = 40;
+/// Stop flag and limits are checked once every this many nodes
+const CHECK_INTERVAL: u64 = 1024;
+/// Captures that can't raise the score to alpha even with this margin are skipped in quiescence
+const DELTA_MARGIN: i32 = 200;
+/// Scores beyond this are mates
+const MATE_BOUND: i32 = MATE_SCORE - MAX_PLY as i32;
+/// Futility margins are this much per ply of remaining depth
+const FUTILITY_MARGIN: i32 = 100;
+const FUTILITY_DEPTH: u32 = 6;
+const RAZORING_MARGIN: i32 = 250;
+const RAZORING_DEPTH: u32 = 3;
+const NULL_MOVE_DEPTH: u32 = 3;
+const LMR_DEPTH: u32 = 3;
+const SINGULAR_EXTENSION_DEPTH: u32 = 8;
+
+/// Score of a position from the side to move's perspective
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Score {
+    Centipawns(i32),
+    /// Moves until mate, negative when side to move gets mated
+    Mate(i32),
+}
+
+impl Score {
+    /// Converts search score, where mates are MATE_SCORE minus distance in plies
+    pub fn from_internal(score: i32) -> Score {
+        if score >= MATE_SCORE - MAX_PLY as i32 {
+            Score::Mate((MATE_SCORE - score + 1) / 2)
+        } else if score <= -MATE_SCORE + MAX_PLY as i32 {
+            Score::Mate(-(MATE_SCORE + score) / 2)
+        } else {
+            Score::Centipawns(score)
+        }
+    }
+
+    /// Converts back to search score, mates at the shortest distance matching the move count
+    pub fn to_internal(&self) -> i32 {
+        match *self {
+            Score::Centipawns(cp) => cp,
+            Score::Mate(moves) if moves > 0 => MATE_SCORE - (2 * moves - 1),
+            Score::Mate(moves) => -MATE_SCORE - 2 * moves,
+        }
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Score::Centipawns(cp) => write!(f, "{:+.2}", *cp as f64 / 100.0),
+            Score::Mate(moves) => write!(f, "#{}", moves),
+        }
+    }
+}
+
+/// Conditions that end the search, search runs until stopped if none are set
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<Duration>,
+    /// Remaining clock times, the time manager splits them between moves
+    pub white_time: Option<Duration>,
+    pub black_time: Option<Duration>,
+    pub white_increment: Option<Duration>,
+    pub black_increment: Option<Duration>,
+    /// Moves until next time control, sudden death when not set
+    pub moves_to_go: Option<u32>,
+    /// Proves or refutes a mate in at most this many moves with `MateSearch`, a normal search
+    /// picks a move with the time and nodes left when there is no mate
+    pub mate: Option<u32>,
+    /// Only these root moves are searched, all of them when empty
+    pub search_moves: Vec<Move>,
+    /// Result is wanted only after an explicit stop, search doesn't end on its own
+    pub infinite: bool,
+    /// Searching on the opponent's time, time limits apply only once the ponder flag is cleared
+    pub ponder: bool,
+}
+
+impl SearchLimits {
+    /// Limits for the rest of the move after `nodes` and `elapsed` time of the clock of `color`
+    /// were used
+    fn get_remaining(&self, color: PieceColor, nodes: u64, elapsed: Duration) -> SearchLimits {
+        let (white_elapsed, black_elapsed) = match color {
+            PieceColor::White => (elapsed, Duration::ZERO),
+            PieceColor::Black => (Duration::ZERO, elapsed),
+        };
+        SearchLimits {
+            nodes: self.nodes.map(|limit| limit.saturating_sub(nodes)),
+            movetime: self.movetime.map(|time| time.saturating_sub(elapsed)),
+            white_time: self
+                .white_time
+                .map(|time| time.saturating_sub(white_elapsed)),
+            black_time: self
+                .black_time
+                .map(|time| time.saturating_sub(black_elapsed)),
+            ..self.clone()
+        }
+    }
+}
+
+/// Switches for search features, mostly useful for measuring their effect
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchOptions {
+    /// Extends captures, promotions and checks past the search depth
+    pub quiescence: bool,
+    /// Tries two quiet moves per ply that caused a cutoff in sibling nodes early
+    pub killers: bool,
+    /// Tries quiet move which refuted the same previous move early
+    pub countermoves: bool,
+    /// Orders quiet moves by main and continuation history
+    pub history: bool,
+    /// Skips nodes where passing the turn still fails high
+    pub null_move: bool,
+    /// Searches late quiet moves at reduced depth first
+    pub late_move_reductions: bool,
+    /// Skips quiet moves near the horizon when static evaluation is far below alpha
+    pub futility: bool,
+    /// Skips nodes near the horizon when static evaluation is far above beta
+    pub reverse_futility: bool,
+    /// Skips late quiet moves near the horizon
+    pub late_move_pruning: bool,
+    /// Drops into quiescence search near the horizon when static evaluation is far below alpha
+    pub razoring: bool,
+    /// Searches positions in check one ply deeper
+    pub check_extensions: bool,
+    /// Searches hash move one ply deeper when all other moves are much worse
+    pub singular_extensions: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            quiescence: true,
+            killers: true,
+            countermoves: true,
+            history: true,
+            null_move: true,
+            late_move_reductions: true,
+            futility: true,
+            reverse_futility: true,
+            late_move_pruning: true,
+            razoring: true,
+            check_extensions: true,
+            singular_extensions: true,
+        }
+    }
+}
+
+impl SearchOptions {
+    /// Option names, field names with words separated by dashes
+    pub const NAMES: [&'static str; 12] = [
+        "quiescence",
+        "killers",
+        "countermoves",
+        "history",
+        "null-move",
+        "late-move-reductions",
+        "futility",
+        "reverse-futility",
+        "late-move-pruning",
+        "razoring",
+        "check-extensions",
+        "singular-extensions",
+    ];
+
+    pub fn get(&self, name: &str) -> Option<bool> {
+        let mut options = self.clone();
+        options.get_mut(name).map(|option| *option)
+    }
+
+    /// Switches option by its name, see `NAMES`
+    pub fn set(&mut self, name: &str, enabled: bool) -> Result<(), String> {
+        let option = self
+            .get_mut(name)
+            .ok_or_else(|| format!("Unknown search option '{}'", name))?;
+        *option = enabled;
+        Ok(())
+    }
+
+    fn get_mut(&mut self, name: &str) -> Option<&mut bool> {
+        Some(match name {
+            "quiescence" => &mut self.quiescence,
+            "killers" => &mut self.killers,
+            "countermoves" => &mut self.countermoves,
+            "history" => &mut self.history,
+            "null-move" => &mut self.null_move,
+            "late-move-reductions" => &mut self.late_move_reductions,
+            "futility" => &mut self.futility,
+            "reverse-futility" => &mut self.reverse_futility,
+            "late-move-pruning" => &mut self.late_move_pruning,
+            "razoring" => &mut self.razoring,
+            "check-extensions" => &mut self.check_extensions,
+            "singular-extensions" => &mut self.singular_extensions,
+            _ => return None,
+        })
+    }
+}
+
+/// Result of a single iteration of iterative deepening
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchInfo {
+    pub depth: u32,
+    /// Highest ply reached, including quiescence search
+    pub seldepth: u32,
+    /// Rank of this line when searching multiple lines, starting from 1
+    pub multipv: usize,
+    pub score: Score,
+    pub nodes: u64,
+    pub time: Duration,
+    /// Principal variation, its first move is the best move
+    pub pv: Vec<Move>,
+    /// Permill of the transposition table used
+    pub hashfull: u32,
+    /// How the mate search ended when `SearchLimits::mate` is set, None for other searches
+    pub mate_search: Option<MateSearchStatus>,
+}
+
+impl SearchInfo {
+    pub fn get_best_move(&self) -> Option<Move> {
+        self.pv.first().copied()
+    }
+
+    /// Expected reply to the best move, the move to ponder on
+    pub fn get_ponder_move(&self) -> Option<Move> {
+        self.pv.get(1).copied()
+    }
+}
+
+/// Negamax alpha-beta search with iterative deepening and aspiration windows
+pub struct Search {
+    stop: Arc<AtomicBool>,
+    /// Cleared when the opponent plays the expected move, see `SearchLimits::ponder`
+    ponder: Arc<AtomicBool>,
+    pondering: bool,
+    tt: Arc<TranspositionTable>,
+    /// Hashes of positions played before the root, oldest first
+    history: Vec<u64>,
+    /// History followed by hashes of positions on the current search path
+    positions: Vec<u64>,
+    options: SearchOptions,
+    heuristics: Box<Heuristics>,
+    /// Key of the move made at each ply of the current search path, none for null moves
+    played: [Option<PieceSquare>; MAX_PLY],
+    /// Static evaluation at each ply of the current search path
+    static_evals: [i32; MAX_PLY],
+    /// Weights of the handcrafted evaluation
+    eval_params: Arc<EvalParams>,
+    /// Evaluates positions instead of the handcrafted evaluation when set
+    network: Option<Arc<Network>>,
+    /// Network accumulator at each ply of the current search path
+    accumulators: Vec<Accumulator>,
+    /// Move skipped at a ply while testing if the hash move is singular
+    excluded: [Option<Move>; MAX_PLY],
+    /// Number of best lines searched
+    multi_pv: usize,
+    /// Root moves skipped, best moves of earlier lines of the iteration
+    root_excluded: Vec<Move>,
+    skill: Skill,
+    /// Picks weaker moves when skill is limited
+    rng: Rng,
+    limits: SearchLimits,
+    time_manager: TimeManager,
+    move_overhead: Duration,
+    nodes: u64,
+    seldepth: u32,
+    /// Depth of the current iteration
+    root_depth: u32,
+    aborted: bool,
+    /// Number of threads searching the same position, sharing the transposition table
+    threads: usize,
+    /// Searches run by the other threads, each keeping its own move ordering statistics
+    helpers: Vec<Search>,
+    /// Zero for the main thread, helpers are numbered from one
+    thread_index: usize,
+    /// Node count published for the main thread
+    node_counter: Arc<AtomicU64>,
+    /// Node counters of the running helpers
+    helper_nodes: Vec<Arc<AtomicU64>>,
+}
+
+impl Default for Search {
+    fn default() -> Self {
+        Search {
+            stop: Arc::new(AtomicBool::new(false)),
+            ponder: Arc::new(AtomicBool::new(false)),
+            pondering: false,
+            tt: Arc::new(TranspositionTable::default()),
+            history: Vec::new(),
+            positions: Vec::new(),
+            options: SearchOptions::default(),
+            heuristics: Box::default(),
+            played: [None; MAX_PLY],
+            static_evals: [0; MAX_PLY],
+            eval_params: Arc::new(EvalParams::default()),
+            network: None,
+            accumulators: Vec::new(),
+            excluded: [None; MAX_PLY],
+            multi_pv: 1,
+            root_excluded: Vec::new(),
+            skill: Skill::default(),
+            rng: Rng::new(
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |time| time.as_nanos() as u64),
+            ),
+            limits: SearchLimits::default(),
+            time_manager: TimeManager::default(),
+            move_overhead: DEFAULT_MOVE_OVERHEAD,
+            nodes: 0,
+            seldepth: 0,
+            root_depth: 0,
+            aborted: false,
+            threads: 1,
+            helpers: Vec::new(),
+            thread_index: 0,
+            node_counter: Arc::new(AtomicU64::new(0)),
+            helper_nodes: Vec::new(),
+        }
+    }
+}
+
+impl Search {
+    pub fn new() -> Search {
+        Search::default()
+    }
+
+    /// Flag which stops running search when set, can be shared with other threads
+    pub fn get_stop_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+
+    /// Flag to set before starting a pondering search and to clear on ponder hit
+    pub fn get_ponder_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.ponder)
+    }
+
+    pub fn get_options(&self) -> &SearchOptions {
+        &self.options
+    }
+
+    pub fn set_options(&mut self, options: SearchOptions) {
+        self.options = options;
+    }
+
+    /// Allocates new transposition table of given size
+    pub fn set_hash_size(&mut self, size_mb: usize) {
+        self.tt = Arc::new(TranspositionTable::new(size_mb));
+    }
+
+    /// Clears transposition table, e.g. before a new game
+    pub fn clear_hash(&self) {
+        self.tt.clear();
+    }
+
+    /// Forgets move ordering statistics gathered by previous searches
+    pub fn clear_heuristics(&mut self) {
+        self.heuristics.clear();
+        self.helpers.iter_mut().for_each(Search::clear_heuristics);
+    }
+
+    pub fn get_transposition_table(&self) -> Arc<TranspositionTable> {
+        Arc::clone(&self.tt)
+    }
+
+    /// Time kept in reserve for communication delays when playing on a clock
+    pub fn set_move_overhead(&mut self, move_overhead: Duration) {
+        self.move_overhead = move_overhead;
+    }
+
+    /// Number of best lines searched by `run`, only the first line is searched by default
+    pub fn set_multi_pv(&mut self, multi_pv: usize) {
+        self.multi_pv = multi_pv.max(1);
+    }
+
+    /// Weights of the handcrafted evaluation, e.g. adjusted to a playing style
+    pub fn set_eval_params(&mut self, params: EvalParams) {
+        self.eval_params = Arc::new(params);
+    }
+
+    /// Playing strength of `run`, full strength by default
+    pub fn set_skill(&mut self, skill: Skill) {
+        self.skill = skill;
+    }
+
+    pub fn get_skill(&self) -> Skill {
+        self.skill
+    }
+
+    /// Evaluates with the network instead of the handcrafted evaluation, or stops using it
+    pub fn set_network(&mut self, network: Option<Arc<Network>>) {
+        self.network = network;
+        self.accumulators.clear();
+    }
+
+    pub fn get_network(&self) -> Option<&Arc<Network>> {
+        self.network.as_ref()
+    }
+
+    /// Number of threads used by `run`, helper threads are started with each search
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+        self.helpers.truncate(self.threads - 1);
+    }
+
+    /// Sets hashes of positions played before the searched one, oldest first,
+    /// so the search can detect repetitions
+    pub fn set_history(&mut self, history: Vec<u64>) {
+        self.history = history;
+    }
+
+    /// Searches until one of the limits is hit or search is stopped, calling `on_iteration`
+    /// after each completed depth, once for every line when searching multiple lines
+    ///
+    /// Returns the best line of the last completed iteration, first iteration is always completed
+    /// so there is a move to play. PV is empty when there are no legal moves.
+    pub fn run(
+        &mut self,
+        board: &Board,
+        limits: &SearchLimits,
+        on_iteration: impl FnMut(&SearchInfo),
+    ) -> SearchInfo {
+        self.run_multi_pv(board, limits, on_iteration)
+            .swap_remove(0)
+    }
+
+    /// Searches `count` best moves and returns them sorted from the best one
+    pub fn get_best_moves(
+        &mut self,
+        board: &Board,
+        limits: &SearchLimits,
+        count: usize,
+    ) -> Vec<(Move, Score, Vec<Move>)> {
+        let multi_pv = std::mem::replace(&mut self.multi_pv, count);
+        let lines = self.run_multi_pv(board, limits, |_| {});
+        self.multi_pv = multi_pv;
+        lines
+            .into_iter()
+            .filter_map(|line| Some((line.get_best_move()?, line.score, line.pv)))
+            .collect()
+    }
+
+    /// Like `run`, but returns all lines of the last completed iteration, best first
+    ///
+    /// Every line after the first searches the root without the best moves of the lines before it.
+    /// With more threads, helpers search the same position until the main thread finishes and
+    /// the best move is chosen by a vote weighted by depth and score of each thread's result.
+    pub fn run_multi_pv(
+        &mut self,
+        board: &Board,
+        limits: &SearchLimits,
+        on_iteration: impl FnMut(&SearchInfo),
+    ) -> Vec<SearchInfo> {
+        if let Some(moves) = limits.mate.filter(|_| self.multi_pv == 1) {
+            return self.run_mate_search(board, limits, moves, on_iteration);
+        }
+        if self.skill.is_limited() {
+            return self.run_with_skill(board, limits, on_iteration);
+        }
+        self.run_threads(board, limits, on_iteration)
+    }
+
+    /// Searches more lines within the limits of the skill level, the line it picks to play
+    /// is returned first
+    fn run_with_skill(
+        &mut self,
+        board: &Board,
+        limits: &SearchLimits,
+        on_iteration: impl FnMut(&SearchInfo),
+    ) -> Vec<SearchInfo> {
+        let multi_pv = self.multi_pv;
+        self.multi_pv = self.skill.get_multi_pv(multi_pv);
+        let mut lines = self.run_threads(board, &self.skill.limit(limits), on_iteration);
+        self.multi_pv = multi_pv;
+
+        let picked = self.skill.pick_line(&lines, &mut self.rng);
+        let line = lines.remove(picked);
+        lines.insert(0, line);
+        lines.truncate(multi_pv);
+        lines
+    }
+
+    /// Searches forced mates within the limits, the result is the mating line when there is
+    /// a mate and the result of a normal search otherwise
+    fn run_mate_search(
+        &mut self,
+        board: &Board,
+        limits: &SearchLimits,
+        moves: u32,
+        mut on_iteration: impl FnMut(&SearchInfo),
+    ) -> Vec<SearchInfo> {
+        let start = Instant::now();
+        let mut mate_search = MateSearch::new(Arc::clone(&self.stop));
+        let time_manager = TimeManager::new(limits, board.get_to_move(), self.move_overhead);
+        mate_search.set_limits(limits, time_manager, Arc::clone(&self.ponder));
+        if let Some(pv) = mate_search.find_mate(board, moves) {
+            self.stop.store(false, Ordering::Relaxed);
+            let info = SearchInfo {
+                depth: pv.len() as u32,
+                seldepth: pv.len() as u32,
+                multipv: 1,
+                score: Score::Mate(pv.len().div_ceil(2) as i32),
+                nodes: mate_search.get_nodes(),
+                time: start.elapsed(),
+                pv,
+                hashfull: self.tt.get_hashfull(),
+                mate_search: Some(MateSearchStatus::Found),
+            };
+            on_iteration(&info);
+            return vec![info];
+        }
+
+        // No mate, a normal search as deep as the mate search picks the move. After a stop
+        // it still completes depth 1 so there is a move to play.
+        let remaining = limits.get_remaining(
+            board.get_to_move(),
+            mate_search.get_nodes(),
+            mate_search.get_clock_elapsed(),
+        );
+        let limits = SearchLimits {
+            depth: Some(limits.depth.unwrap_or(2 * moves)),
+            mate: None,
+            ..remaining
+        };
+        let mut lines = self.run_threads(board, &limits, on_iteration);
+        lines[0].mate_search = Some(if mate_search.is_aborted() {
+            MateSearchStatus::Aborted
+        } else {
+            MateSearchStatus::Refuted
+        });
+        lines
+    }
+
+    /// Runs main thread and helpers, see `run_multi_pv`
+    fn run_threads(
+        &mut self,
+        board: &Board,
+        limits: &SearchLimits,
+        mut on_iteration: impl FnMut(&SearchInfo),
+    ) -> Vec<SearchInfo> {
+        while self.helpers.len() + 1 < self.threads {
+            let thread_index = self.helpers.len() + 1;
+            self.helpers.push(Search {
+                thread_index,
+                ..Search::default()
+            });
+        }
+        // Once per search, all threads store entries of the same age
+        self.tt.new_search();
+        if self.helpers.is_empty() {
+            return self.iterate(board, limits, &mut on_iteration);
+        }
+
+        let mut helpers = std::mem::take(&mut self.helpers);
+        for helper in helpers.iter_mut() {
+            helper.tt = Arc::clone(&self.tt);
+            helper.options = self.options.clone();
+            helper.eval_params = Arc::clone(&self.eval_params);
+            if helper.network.as_ref().map(Arc::as_ptr) != self.network.as_ref().map(Arc::as_ptr) {
+                helper.set_network(self.network.clone());
+            }
+            helper.history = self.history.clone();
+            helper.node_counter.store(0, Ordering::Relaxed);
+            // Helper which finished before the main thread was still told to stop
+            helper.stop.store(false, Ordering::Relaxed);
+        }
+        self.helper_nodes = helpers
+            .iter()
+            .map(|helper| Arc::clone(&helper.node_counter))
+            .collect();
+        let helper_stops: Vec<_> = helpers.iter().map(Search::get_stop_handle).collect();
+        // Helpers run until the main thread tells them to stop
+        let helper_limits = SearchLimits {
+            depth: limits.depth,
+            search_moves: limits.search_moves.clone(),
+            ..SearchLimits::default()
+        };
+
+        let (mut lines, results) = thread::scope(|scope| {
+            let threads: Vec<_> = helpers
+                .iter_mut()
+                .map(|helper| {
+                    let helper_limits = &helper_limits;
+                    scope.spawn(move || {
+                        helper
+                            .iterate(board, helper_limits, &mut |_| {})
+                            .swap_remove(0)
+                    })
+                })
+                .collect();
+            let lines = self.iterate(board, limits, &mut on_iteration);
+            for stop in &helper_stops {
+                stop.store(true, Ordering::Relaxed);
+            }
+            let results: Vec<_> = threads
+                .into_iter()
+                .map(|thread| thread.join().expect("helper thread doesn't panic"))
+                .collect();
+            (lines, results)
+        });
+        self.helpers = helpers;
+
+        let nodes = self.get_total_nodes();
+        self.helper_nodes.clear();
+        lines.iter_mut().for_each(|line| line.nodes = nodes);
+        if lines.len() == 1 {
+            let mut candidates = vec![lines[0].clone()];
+            candidates.extend(results.into_iter().filter(|result| !result.pv.is_empty()));
+            let best = get_voted_result(&candidates);
+            if best > 0 {
+                lines[0] = SearchInfo {
+                    nodes,
+                    time: lines[0].time,
+                    multipv: 1,
+                    ..candidates.swap_remove(best)
+                };
+                on_iteration(&lines[0]);
+            }
+        }
+        lines
+    }
+
+    /// Iterative deepening of a single thread
+    fn iterate(
+        &mut self,
+        board: &Board,
+        limits: &SearchLimits,
+        on_iteration: &mut impl FnMut(&SearchInfo),
+    ) -> Vec<SearchInfo> {
+        self.limits = limits.clone();
+        self.time_manager = TimeManager::new(limits, board.get_to_move(), self.move_overhead);
+        self.pondering = limits.ponder;
+        self.nodes = 0;
+        self.aborted = false;
+        self.positions = self.history.clone();
+        self.heuristics.clear_killers();
+        if let Some(network) = &self.network {
+            let root = network.new_accumulator(board);
+            self.accumulators = vec![root; MAX_PLY + 1];
+        }
+
+        let mut root_moves = board.get_legal_moves();
+        if root_moves.iter().any(|mv| limits.search_moves.contains(mv)) {
+            root_moves.retain(|mv| limits.search_moves.contains(mv));
+        }
+        let line_count = self.multi_pv.clamp(1, root_moves.len().max(1));
+        let mut lines = vec![SearchInfo {
+            depth: 0,
+            seldepth: 0,
+            multipv: 1,
+            score: Score::Centipawns(0),
+            nodes: 0,
+            time: Duration::ZERO,
+            pv: Vec::new(),
+            hashfull: 0,
+            mate_search: None,
+        }];
+        let mut scores = vec![0; line_count];
+        let max_depth = limits.depth.unwrap_or(MAX_PLY as u32).min(MAX_PLY as u32);
+        // With only one move there is nothing to think about on the clock
+        let is_forced = root_moves.len() == 1 && self.time_manager.is_active();
+
+        for depth in 1..=max_depth {
+            // Odd helpers skip every other depth so threads don't all search the same tree
+            if self.thread_index % 2 == 1 && depth > 1 && depth < max_depth && depth % 2 == 0 {
+                continue;
+            }
+            self.root_depth = depth;
+            self.root_excluded.clear();
+            let mut iteration = Vec::new();
+            for previous_score in scores.iter().copied() {
+                self.seldepth = 0;
+                let mut pv = Vec::new();
+                let score = self.search_with_aspiration(board, depth, previous_score, &mut pv);
+                if self.aborted {
+                    break;
+                }
+                self.complete_pv(board, &mut pv, depth);
+                self.root_excluded.extend(pv.first());
+                iteration.push((score, self.seldepth.max(depth), pv));
+            }
+            if self.aborted {
+                break;
+            }
+            // Later lines might score better after their own re-searches
+            iteration.sort_by_key(|(score, _, _)| -score);
+
+            let previous_best = scores[0];
+            scores = iteration.iter().map(|(score, _, _)| *score).collect();
+            if depth > 1 {
+                let best_move_changed = iteration[0].2.first() != lines[0].pv.first();
+                self.time_manager
+                    .on_iteration(best_move_changed, scores[0] - previous_best);
+            }
+
+            let (time, hashfull) = (self.time_manager.get_elapsed(), self.tt.get_hashfull());
+            lines = iteration
+                .into_iter()
+                .enumerate()
+                .map(|(index, (score, seldepth, pv))| SearchInfo {
+                    depth,
+                    seldepth,
+                    multipv: index + 1,
+                    score: Score::from_internal(score),
+                    nodes: self.get_total_nodes(),
+                    time,
+                    pv,
+                    hashfull,
+                    mate_search: None,
+                })
+                .collect();
+            lines.iter().for_each(&mut *on_iteration);
+
+            if lines[0].pv.is_empty()
+                || self.should_stop()
+                || (!self.pondering && (is_forced || self.time_manager.is_soft_limit_reached()))
+            {
+                break;
+            }
+        }
+        self.stop.store(false, Ordering::Relaxed);
+        self.node_counter.store(self.nodes, Ordering::Relaxed);
+        lines
+    }
+
+    /// Nodes searched by this thread and the helpers
+    fn get_total_nodes(&self) -> u64 {
+        self.nodes
+            + self
+                .helper_nodes
+                .iter()
+                .map(|nodes| nodes.load(Ordering::Relaxed))
+                .sum::<u64>()
+    }
+
+    /// Searches narrow window around previous score, widening it on fail low or high
+    fn search_with_aspiration(
+        &mut self,
+        board: &Board,
+        depth: u32,
+        previous_score: i32,
+        pv: &mut Vec<Move>,
+    ) -> i32 {
+        let mut delta = ASPIRATION_WINDOW;
+        let (mut alpha, mut beta) = if depth >= ASPIRATION_DEPTH {
+            (previous_score - delta, previous_score + delta)
+        } else {
+            (-INFINITY, INFINITY)
+        };
+
+        loop {
+            let score = self.negamax(board, depth, 0, alpha, beta, pv);
+            if self.aborted {
+                return score;
+            }
+
+            if score <= alpha {
+                alpha = (score - delta).max(-INFINITY);
+            } else if score >= beta {
+                beta = (score + delta).min(INFINITY);
+            } else {
+                return score;
+            }
+            delta *= 2;
+        }
+    }
+
+    fn negamax(
+        &mut self,
+        board: &Board,
+        depth: u32,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+        pv: &mut Vec<Move>,
+    ) -> i32 {
+        pv.clear();
+        self.nodes += 1;
+        let can_abort = self.root_depth > 1;
+        if can_abort
+            && (self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes)
+                || (self.nodes.is_multiple_of(CHECK_INTERVAL) && self.should_stop()))
+        {
+            self.aborted = true;
+        }
+        if self.aborted {
+            return 0;
+        }
+
+        let hash = board.get_hash();
+        if ply > 0 && (board.get_halfmove_clock() >= 100 || self.is_repetition(board, hash)) {
+            return 0;
+        }
+        let in_check = board.is_in_check();
+        // Extensions are limited so perpetual checks can't blow up the search
+        let depth =
+            if in_check && self.options.check_extensions && ply < 2 * self.root_depth as usize {
+                depth + 1
+            } else {
+                depth
+            };
+        if depth == 0 && self.options.quiescence {
+            return self.quiescence(board, ply, 0, alpha, beta);
+        }
+
+        let is_pv = beta - alpha > 1;
+        let excluded = self.excluded[ply];
+        // Search without the excluded move is a different search, its results aren't stored
+        let tt_entry = if excluded.is_none() {
+            self.tt.probe(hash, ply)
+        } else {
+            None
+        };
+        if let Some(entry) = tt_entry {
+            let is_cutoff = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => entry.score >= beta,
+                Bound::Upper => entry.score <= alpha,
+            };
+            if ply > 0 && entry.depth >= depth && is_cutoff {
+                return entry.score;
+            }
+        }
+
+        let mut moves = board.get_legal_moves();
+        if moves.is_empty() {
+            return if in_check {
+                -MATE_SCORE + ply as i32
+            } else {
+                0
+            };
+        }
+        if ply == 0 {
+            let search_moves = &self.limits.search_moves;
+            if moves.iter().any(|mv| search_moves.contains(mv)) {
+                moves.retain(|mv| search_moves.contains(mv));
+            }
+            moves.retain(|mv| !self.root_excluded.contains(mv));
+        }
+        if let Some(excluded) = excluded {
+            moves.retain(|mv| *mv != excluded);
+            if moves.is_empty() {
+                return alpha;
+            }
+        }
+        if depth == 0 || ply >= MAX_PLY - 1 {
+            return self.evaluate(board, ply);
+        }
+
+        let static_eval = if in_check {
+            -INFINITY
+        } else {
+            self.evaluate(board, ply)
+        };
+        self.static_evals[ply] = static_eval;
+        let improving = !in_check && ply >= 2 && static_eval > self.static_evals[ply - 2];
+
+        if !is_pv && !in_check && excluded.is_none() {
+            if let Some(score) = self.prune_node(board, depth, ply, alpha, beta, static_eval) {
+                return score;
+            }
+        }
+
+        let hash_move = tt_entry.and_then(|entry| entry.best_move);
+        let previous = self.get_previous_moves(ply);
+        self.heuristics
+            .order_moves(board, &mut moves, hash_move, ply, &previous);
+
+        // Hash move is extended when all other moves fail low against a lowered bound
+        let mut singular_move = None;
+        if let Some((entry, mv)) = tt_entry.zip(hash_move) {
+            if self.options.singular_extensions
+                && ply > 0
+                && depth >= SINGULAR_EXTENSION_DEPTH
+                && entry.bound != Bound::Upper
+                && entry.depth + 3 >= depth
+                && entry.score.abs() < MATE_BOUND
+            {
+                let singular_beta = entry.score - 2 * depth as i32;
+                self.excluded[ply] = Some(mv);
+                let score = self.negamax(
+                    board,
+                    (depth - 1) / 2,
+                    ply,
+                    singular_beta - 1,
+                    singular_beta,
+                    &mut Vec::new(),
+                );
+                self.excluded[ply] = None;
+                if self.aborted {
+                    return 0;
+                }
+                if score < singular_beta {
+                    singular_move = Some(mv);
+                }
+            }
+        }
+
+        let futility_margin = FUTILITY_MARGIN * depth as i32;
+        let can_prune_quiets = !is_pv && !in_check && depth <= FUTILITY_DEPTH;
+        let late_move_count = (3 + depth * depth) as usize / if improving { 1 } else { 2 };
+
+        let original_alpha = alpha;
+        let mut best_move = None;
+        let mut child_pv = Vec::new();
+        let mut best_score = -INFINITY;
+        let mut quiets_tried = Vec::new();
+        self.positions.push(hash);
+        for (move_number, mv) in moves.into_iter().enumerate() {
+            let child = self.make_move(board, &mv, ply);
+            let is_quiet = !is_tactical(board, &mv);
+            let gives_check = child.is_in_check();
+
+            if is_quiet && !gives_check && can_prune_quiets && best_score > -MATE_BOUND {
+                if self.options.futility && static_eval + futility_margin <= alpha {
+                    continue;
+                }
+                if self.options.late_move_pruning && quiets_tried.len() >= late_move_count {
+                    continue;
+                }
+            }
+
+            self.played[ply] = PieceSquare::new(board, &mv);
+            let new_depth = if singular_move == Some(mv) {
+                depth
+            } else {
+                depth - 1
+            };
+
+            let score = if move_number == 0 {
+                -self.negamax(&child, new_depth, ply + 1, -beta, -alpha, &mut child_pv)
+            } else {
+                // Principal variation search, later moves are expected to fail low and
+                // are searched with a null window first, quiet ones at reduced depth
+                let reduction = if self.options.late_move_reductions
+                    && is_quiet
+                    && !in_check
+                    && !gives_check
+                    && depth >= LMR_DEPTH
+                    && move_number >= if is_pv { 3 } else { 2 }
+                {
+                    let mut reduction = get_reduction(depth, move_number);
+                    if is_pv {
+                        reduction -= 1;
+                    }
+                    if !improving {
+                        reduction += 1;
+                    }
+                    if self.heuristics.get_killers(ply).contains(&Some(mv)) {
+                        reduction -= 1;
+                    }
+                    reduction -= self.heuristics.get_history(board, &mv, &previous) / 8192;
+                    reduction.clamp(0, new_depth as i32 - 1) as u32
+                } else {
+                    0
+                };
+
+                let mut score = -self.negamax(
+                    &child,
+                    new_depth - reduction,
+                    ply + 1,
+                    -alpha - 1,
+                    -alpha,
+                    &mut child_pv,
+                );
+                if score > alpha && reduction > 0 {
+                    score = -self.negamax(
+                        &child,
+                        new_depth,
+                        ply + 1,
+                        -alpha - 1,
+                        -alpha,
+                        &mut child_pv,
+                    );
+                }
+                if score > alpha && is_pv {
+                    score = -self.negamax(&child, new_depth, ply + 1, -beta, -alpha, &mut child_pv);
+                }
+                score
+            };
+            if self.aborted {
+                self.positions.pop();
+                return 0;
+            }
+            if is_quiet {
+                quiets_tried.push(mv);
+            }
+
+            if score > best_score {
+                best_score = score;
+                if score > alpha {
+                    alpha = score;
+                    best_move = Some(mv);
+                    pv.clear();
+                    pv.push(mv);
+                    pv.extend_from_slice(&child_pv);
+                }
+                if score >= beta {
+                    if is_quiet {
+                        self.update_heuristics(board, mv, &quiets_tried, depth, ply, &previous);
+                    }
+                    break;
+                }
+            }
+        }
+        self.positions.pop();
+
+        let bound = if best_score >= beta {
+            Bound::Lower
+        } else if best_score > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        if excluded.is_none() && (ply > 0 || self.root_excluded.is_empty()) {
+            self.tt
+                .store(hash, best_move, best_score, depth, bound, ply);
+        }
+        best_score
+    }
+
+    /// Reverse futility pruning, razoring and null move pruning of a non-PV node,
+    /// returns score when the whole node can be skipped
+    fn prune_node(
+        &mut self,
+        board: &Board,
+        depth: u32,
+        ply: usize,
+        alpha: i32,
+        beta: i32,
+        static_eval: i32,
+    ) -> Option<i32> {
+        // Static evaluation is so far above beta that a quiet move will likely keep it there
+        if self.options.reverse_futility
+            && depth <= FUTILITY_DEPTH
+            && static_eval - FUTILITY_MARGIN * depth as i32 >= beta
+        {
+            return Some(static_eval);
+        }
+
+        // Static evaluation is so far below alpha that only captures might help
+        if self.options.razoring
+            && depth <= RAZORING_DEPTH
+            && static_eval + RAZORING_MARGIN * depth as i32 <= alpha
+        {
+            // Without quiescence search static evaluation is the score, like at depth 0
+            let score = if self.options.quiescence {
+                self.quiescence(board, ply, 0, alpha, alpha + 1)
+            } else {
+                static_eval
+            };
+            if score <= alpha {
+                return Some(score);
+            }
+        }
+
+        // Passing the turn still fails high, so a real move will too. Not done after another
+        // null move, or without pieces as pawn endgames are often zugzwang
+        let after_null_move = ply > 0 && self.played[ply - 1].is_none();
+        if self.options.null_move
+            && depth >= NULL_MOVE_DEPTH
+            && static_eval >= beta
+            && !after_null_move
+            && has_non_pawn_material(board)
+        {
+            let reduction = 3 + depth / 4 + ((static_eval - beta) / 200).min(3) as u32;
+            let mut child = board.clone();
+            child.make_null_move();
+            if self.network.is_some() {
+                let (parents, children) = self.accumulators.split_at_mut(ply + 1);
+                children[0].clone_from(&parents[ply]);
+            }
+            self.played[ply] = None;
+            self.positions.push(board.get_hash());
+            let score = -self.negamax(
+                &child,
+                depth.saturating_sub(reduction + 1),
+                ply + 1,
+                -beta,
+                -beta + 1,
+                &mut Vec::new(),
+            );
+            self.positions.pop();
+            if self.aborted {
+                return Some(0);
+            }
+            if score >= beta {
+                // Mate found after passing isn't a proven mate
+                return Some(score.min(MATE_BOUND - 1));
+            }
+        }
+        None
+    }
+
+    /// Searches captures and promotions until the position is quiet, and quiet checks at
+    /// its first ply, side to move can always stand pat with static evaluation unless in check
+    fn quiescence(
+        &mut self,
+        board: &Board,
+        ply: usize,
+        quiescence_ply: usize,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply as u32);
+        if self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes)
+            || (self.nodes.is_multiple_of(CHECK_INTERVAL) && self.should_stop())
+        {
+            self.aborted = self.root_depth > 1;
+        }
+        if self.aborted {
+            return 0;
+        }
+
+        let in_check = board.is_in_check();
+        let mut moves = board.get_legal_moves();
+        if moves.is_empty() {
+            return if in_check {
+                -MATE_SCORE + ply as i32
+            } else {
+                0
+            };
+        }
+
+        let stand_pat = self.evaluate(board, ply);
+        if ply >= MAX_PLY - 1 {
+            return stand_pat;
+        }
+        if !in_check {
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+
+            moves.retain(|mv| {
+                get_captured_kind(board, mv).is_some()
+                    || mv.promotion.is_some()
+                    || (quiescence_ply == 0 && gives_check(board, mv))
+            });
+        }
+        moves.sort_by_cached_key(|mv| -get_mvv_lva(board, mv));
+
+        let mut best_score = if in_check { -INFINITY } else { stand_pat };
+        for mv in moves {
+            if !in_check && mv.promotion.is_none() {
+                if let Some(captured) = get_captured_kind(board, &mv) {
+                    if stand_pat + get_piece_value(captured) + DELTA_MARGIN < alpha {
+                        continue;
+                    }
+                    if see(board, &mv) < 0 {
+                        continue;
+                    }
+                }
+            }
+
+            let child = self.make_move(board, &mv, ply);
+            let score = -self.quiescence(&child, ply + 1, quiescence_ply + 1, -beta, -alpha);
+            if self.aborted {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                alpha = alpha.max(score);
+                if score >= beta {
+                    break;
+                }
+            }
+        }
+        best_score
+    }
+
+    /// Keys of the last two moves leading to the node at `ply`, the last one first
+    fn get_previous_moves(&self, ply: usize) -> [Option<PieceSquare>; 2] {
+        [
+            ply.checked_sub(1).and_then(|i| self.played[i]),
+            ply.checked_sub(2).and_then(|i| self.played[i]),
+        ]
+    }
+
+    fn update_heuristics(
+        &mut self,
+        board: &Board,
+        mv: Move,
+        quiets_tried: &[Move],
+        depth: u32,
+        ply: usize,
+        previous: &[Option<PieceSquare>; 2],
+    ) {
+        if self.options.killers {
+            self.heuristics.add_killer(ply, mv);
+        }
+        if self.options.countermoves {
+            if let Some(last) = previous[0] {
+                self.heuristics.set_countermove(last, mv);
+            }
+        }
+        if self.options.history {
+            self.heuristics
+                .update_history(board, &mv, quiets_tried, depth, previous);
+        }
+    }
+
+    /// Checks if position occurred before since the last capture or pawn move, a single
+    /// repetition is enough to score it as a draw
+    fn is_repetition(&self, board: &Board, hash: u64) -> bool {
+        self.positions
+            .iter()
+            .rev()
+            .take(board.get_halfmove_clock() as usize)
+            .skip(1)
+            .step_by(2)
+            .any(|position| *position == hash)
+    }
+
+    /// Extends PV cut short by transposition table hits with best moves stored in the table
+    fn complete_pv(&self, board: &Board, pv: &mut Vec<Move>, depth: u32) {
+        let mut board = board.clone();
+        for mv in pv.iter() {
+            board.make_move_unchecked(mv);
+        }
+        while pv.len() < depth as usize {
+            let Some(mv) = self
+                .tt
+                .probe(board.get_hash(), 0)
+                .and_then(|entry| entry.best_move)
+            else {
+                break;
+            };
+            if !board.get_legal_moves().contains(&mv) {
+                break;
+            }
+            board.make_move_unchecked(&mv);
+            pv.push(mv);
+        }
+    }
+
+    /// Static evaluation with the network when there is one, `ply` selects its accumulator
+    fn evaluate(&self, board: &Board, ply: usize) -> i32 {
+        match &self.network {
+            Some(network) => network
+                .evaluate(&self.accumulators[ply], board.get_to_move())
+                .clamp(-MATE_BOUND + 1, MATE_BOUND - 1),
+            None => eval::evaluate_with_params(board, &self.eval_params),
+        }
+    }
+
+    /// Plays a move at `ply`, updating the network accumulator of the next ply
+    fn make_move(&mut self, board: &Board, mv: &Move, ply: usize) -> Board {
+        let mut child = board.clone();
+        child.make_move_unchecked(mv);
+        if let Some(network) = &self.network {
+            let (parents, children) = self.accumulators.split_at_mut(ply + 1);
+            network.update_accumulator(&mut children[0], &parents[ply], board, &child, mv);
+        }
+        child
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if self.pondering && !self.ponder.load(Ordering::Relaxed) {
+            // Ponder hit, the clock starts running now
+            self.pondering = false;
+            self.time_manager.on_ponderhit();
+            // Pondering might have already taken as long as a normal search would
+            if self.time_manager.is_soft_limit_reached() {
+                return true;
+            }
+        }
+        self.node_counter.store(self.nodes, Ordering::Relaxed);
+        self.stop.load(Ordering::Relaxed)
+            || self
+                .limits
+                .nodes
+                .is_some_and(|nodes| self.get_total_nodes() >= nodes)
+            || (!self.pondering && self.time_manager.is_hard_limit_reached())
+    }
+}
+
+/// Index of the result whose best move got most votes, the deepest such result is preferred
+///
+/// Each result votes for its best move with weight growing with depth and score.
+fn get_voted_result(results: &[SearchInfo]) -> usize {
+    let min_score = results
+        .iter()
+        .map(|result| result.score.to_internal())
+        .min()
+        .unwrap_or(0);
+    let get_votes = |mv: Option<Move>| -> i64 {
+        results
+            .iter()
+            .filter(|result| result.get_best_move() == mv)
+            .map(|result| {
+                (result.score.to_internal() - min_score + 14) as i64 * result.depth as i64
+            })
+            .sum()
+    };
+    (0..results.len())
+        .max_by_key(|&index| {
+            let result = &results[index];
+            (
+                get_votes(result.get_best_move()),
+                result.depth,
+                result.score.to_internal(),
+                std::cmp::Reverse(index),
+            )
+        })
+        .unwrap_or(0)
+}
+
+/// Late move reduction in plies, grows with both depth and number of moves searched
+fn get_reduction(depth: u32, move_number: usize) -> i32 {
+    (0.75 + (depth as f64).ln() * (move_number as f64).ln() / 2.25) as i32
+}
+
+fn has_non_pawn_material(board: &Board) -> bool {
+    let color = board.get_to_move();
+    board.get_flat_pieces().iter().any(|piece| {
+        *piece.get_color() == color
+            && !matches!(piece.get_kind(), PieceKind::Pawn | PieceKind::King)
+    })
+}
+
+fn gives_check(board: &Board, mv: &Move) -> bool {
+    let mut child = board.clone();
+    child.make_move_unchecked(mv);
+    child.is_in_check()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{piece::PieceColor, position::Position};
+
+    fn search_to_depth(fen: &str, depth: u32) -> SearchInfo {
+        let board = Board::new_from_fen(fen).unwrap();
+        let limits = SearchLimits {
+            depth: Some(depth),
+            ..SearchLimits::default()
+        };
+        Search::new().run(&board, &limits, |_| {})
+    }
+
+    #[test]
+    fn it_converts_mate_scores() {
+        assert_eq!(Score::from_internal(35), Score::Centipawns(35));
+        assert_eq!(Score::from_internal(MATE_SCORE - 1), Score::Mate(1));
+        assert_eq!(Score::from_internal(MATE_SCORE - 3), Score::Mate(2));
+        assert_eq!(Score::from_internal(-MATE_SCORE + 2), Score::Mate(-1));
+        assert_eq!(Score::Centipawns(-150).to_string(), "-1.50");
+        assert_eq!(Score::Mate(-3).to_string(), "#-3");
+    }
+
+    #[test]
+    fn it_finds_mate_in_one() {
+        let info = search_to_depth("6k1/5ppp/8/8/8/8/8/R3K3 w Q - 0 1", 3);
+
+        assert_eq!(info.score, Score::Mate(1));
+        assert_eq!(
+            info.get_best_move(),
+            Some(Move::new(Position('a', '1'), Position('a', '8')))
+        );
+    }
+
+    #[test]
+    fn it_captures_hanging_piece() {
+        let info = search_to_depth("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", 2);
+
+        assert_eq!(
+            info.get_best_move(),
+            Some(Move::new(Position('d', '2'), Position('d', '5')))
+        );
+        assert!(matches!(info.score, Score::Centipawns(cp) if cp > 400));
+    }
+
+    #[test]
+    fn it_reports_every_iteration() {
+        let board = Board::default();
+        let limits = SearchLimits {
+            depth: Some(3),
+            ..SearchLimits::default()
+        };
+        let mut iterations = Vec::new();
+        let info = Search::new().run(&board, &limits, |info| iterations.push(info.clone()));
+
+        assert_eq!(
+            iterations.iter().map(|info| info.depth).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert!(iterations.windows(2).all(|w| w[0].nodes < w[1].nodes));
+        assert_eq!(info.pv.len(), 3);
+
+        let mut board = board.clone();
+        for mv in &info.pv {
+            assert!(board.make_move(mv).is_ok());
+        }
+    }
+
+    #[test]
+    fn it_stops_on_demand_and_on_node_limit() {
+        let board = Board::default();
+        let mut search = Search::new();
+        search.get_stop_handle().store(true, Ordering::Relaxed);
+        let info = search.run(&board, &SearchLimits::default(), |_| {});
+        assert_eq!(info.depth, 1);
+        assert!(info.get_best_move().is_some());
+
+        let limits = SearchLimits {
+            nodes: Some(2000),
+            ..SearchLimits::default()
+        };
+        let info = search.run(&board, &limits, |_| {});
+        assert!(info.nodes <= 2000);
+        assert!(info.get_best_move().is_some());
+    }
+
+    #[test]
+    fn it_plays_forced_move_and_respects_clock() {
+        let limits = SearchLimits {
+            white_time: Some(Duration::from_secs(60)),
+            ..SearchLimits::default()
+        };
+        // King has a single legal move
+        let board = Board::new_from_fen("k7/8/8/8/8/8/1q6/K7 w - - 0 1").unwrap();
+        let info = Search::new().run(&board, &limits, |_| {});
+        assert_eq!(info.depth, 1);
+        assert_eq!(info.get_best_move(), Move::from_uci("a1b2"));
+
+        let limits = SearchLimits {
+            white_time: Some(Duration::from_millis(500)),
+            ..SearchLimits::default()
+        };
+        let info = Search::new().run(&Board::default(), &limits, |_| {});
+        assert!(info.time < Duration::from_millis(500));
+        assert!(info.get_best_move().is_some());
+    }
+
+    #[test]
+    fn it_searches_multiple_lines() {
+        // Queen takes the undefended knight
+        let board = Board::new_from_fen("4k3/8/8/8/8/8/1n1Q4/4K3 w - - 0 1").unwrap();
+        let limits = SearchLimits {
+            depth: Some(3),
+            ..SearchLimits::default()
+        };
+        let mut search = Search::new();
+        let lines = search.get_best_moves(&board, &limits, 3);
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].0, Move::from_uci("d2b2").unwrap());
+        assert!(lines.windows(2).all(|w| match (w[0].1, w[1].1) {
+            (Score::Centipawns(first), Score::Centipawns(second)) => first >= second,
+            _ => true,
+        }));
+        assert!(lines.iter().all(|(mv, _, pv)| pv.first() == Some(mv)));
+
+        search.set_multi_pv(50);
+        let mut reported = Vec::new();
+        let info = search.run(&board, &limits, |info| reported.push(info.multipv));
+        // Lines are capped by the number of legal moves
+        let move_count = board.get_legal_moves().len();
+        assert_eq!(reported.len(), 3 * move_count);
+        assert_eq!(info.multipv, 1);
+    }
+
+    #[test]
+    fn it_searches_with_network() {
+        // Counts queens only, so the search has to win the queen to see any difference
+        let mut network = Network::new(2, 0);
+        for square in 0..64 {
+            let (ours, theirs) =
+                crate::nnue::get_inputs(PieceKind::Queen, PieceColor::White, square);
+            network.get_feature_weights_mut(ours)[0] = 10;
+            network.get_feature_weights_mut(theirs)[1] = 10;
+        }
+        network
+            .get_output_weights_mut()
+            .copy_from_slice(&[2000, -2000, -2000, 2000]);
+        let board = Board::new_from_fen("4k3/8/8/3q4/8/4N3/8/4K3 w - - 0 1").unwrap();
+        assert!(network.evaluate_board(&board) < -300);
+
+        let mut search = Search::new();
+        search.set_network(Some(Arc::new(network)));
+        let limits = SearchLimits {
+            depth: Some(3),
+            ..SearchLimits::default()
+        };
+        let info = search.run(&board, &limits, |_| {});
+        assert_eq!(info.get_best_move(), Move::from_uci("e3d5"));
+        assert_eq!(info.score, Score::Centipawns(0));
+    }
+
+    #[test]
+    fn it_limits_strength_by_skill_level() {
+        let board = Board::default();
+        let mut search = Search::new();
+        search.rng = Rng::new(1);
+        search.set_skill(Skill::new(0));
+        let mut lines = 0;
+        let info = search.run(&board, &SearchLimits::default(), |_| lines += 1);
+        assert_eq!(info.depth, 1);
+        assert_eq!(lines, 4);
+
+        let moves: Vec<_> = (0..10)
+            .map(|_| search.run(&board, &SearchLimits::default(), |_| {}))
+            .map(|info| info.get_best_move())
+            .collect();
+        assert!(moves.iter().any(|mv| *mv != moves[0]));
+    }
+
+    #[test]
+    fn it_searches_with_multiple_threads() {
+        let board = Board::new_from_fen("4k3/8/8/8/8/8/1n1Q4/4K3 w - - 0 1").unwrap();
+        let limits = SearchLimits {
+            depth: Some(4),
+            ..SearchLimits::default()
+        };
+        let mut search = Search::new();
+        search.set_threads(4);
+        let mut reported = 0;
+        let info = search.run(&board, &limits, |info| reported = info.nodes);
+
+        assert_eq!(info.get_best_move(), Move::from_uci("d2b2"));
+        assert_eq!(search.helpers.len(), 3);
+        // Final result counts nodes of all threads, also those searched after the last report
+        assert!(info.nodes >= reported);
+        assert!(search.helpers.iter().all(|helper| helper.nodes > 0));
+        assert!(info.nodes > search.nodes);
+
+        search.set_threads(1);
+        assert!(search.helpers.is_empty());
+    }
+
+    #[test]
+    fn it_restarts_helpers_after_depth_limited_search() {
+        let board = Board::default();
+        let mut search = Search::new();
+        search.set_threads(2);
+        let limits = SearchLimits {
+            depth: Some(6),
+            ..SearchLimits::default()
+        };
+        search.run(&board, &limits, |_| {});
+        // Main thread stops the helper also when the helper finished first
+        search.helpers[0].stop.store(true, Ordering::Relaxed);
+
+        search.run(&board, &limits, |_| {});
+        // Helper searched deeper than depth 1 instead of stopping right after it
+        assert!(search.helpers[0].root_depth > 1);
+    }
+
+    #[test]
+    fn it_ages_transposition_table_once_per_search() {
+        let board = Board::default();
+        let mut search = Search::new();
+        search.set_threads(3);
+        let limits = SearchLimits {
+            depth: Some(3),
+            ..SearchLimits::default()
+        };
+        search.run(&board, &limits, |_| {});
+        search.run(&board, &limits, |_| {});
+        assert_eq!(search.get_transposition_table().get_age(), 2);
+    }
+
+    #[test]
+    fn it_votes_for_move_of_deep_and_confident_threads() {
+        let result = |mv: &str, depth: u32, score: i32| SearchInfo {
+            depth,
+            seldepth: depth,
+            multipv: 1,
+            score: Score::Centipawns(score),
+            nodes: 0,
+            time: Duration::ZERO,
+            pv: vec![Move::from_uci(mv).unwrap()],
+            hashfull: 0,
+            mate_search: None,
+        };
+        let results = [
+            result("e2e4", 6, 20),
+            result("d2d4", 6, 30),
+            result("d2d4", 7, 25),
+            result("g1f3", 5, 40),
+        ];
+        assert_eq!(get_voted_result(&results), 2);
+        assert_eq!(get_voted_result(&results[..1]), 0);
+
+        assert_eq!(Score::Mate(2).to_internal(), MATE_SCORE - 3);
+        assert_eq!(Score::Mate(-1).to_internal(), -MATE_SCORE + 2);
+        assert_eq!(
+            Score::from_internal(Score::Mate(-4).to_internal()),
+            Score::Mate(-4)
+        );
+    }
+
+    #[test]
+    fn it_scores_repetition_as_draw() {
+        let mut board = Board::new_from_fen("4k3/7q/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let mut history = Vec::new();
+        for mv in ["e1d1", "h7h6", "d1e1", "h6h7"] {
+            history.push(board.get_hash());
+            board.make_move(&Move::from_uci(mv).unwrap()).unwrap();
+        }
+
+        let mut search = Search::new();
+        search.set_history(history);
+        let limits = SearchLimits {
+            depth: Some(2),
+            ..SearchLimits::default()
+        };
+        let info = search.run(&board, &limits, |_| {});
+
+        assert_eq!(info.get_best_move(), Move::from_uci("e1d1"));
+        assert_eq!(info.score, Score::Centipawns(0));
+    }
+
+    #[test]
+    fn it_fills_transposition_table() {
+        let mut search = Search::new();
+        search.set_hash_size(1);
+        let limits = SearchLimits {
+            depth: Some(3),
+            ..SearchLimits::default()
+        };
+        let info = search.run(&Board::default(), &limits, |_| {});
+        assert!(info.hashfull > 0);
+
+        let entry = search
+            .get_transposition_table()
+            .probe(Board::default().get_hash(), 0)
+            .unwrap();
+        assert_eq!(entry.best_move, info.get_best_move());
+        assert_eq!(entry.depth, 3);
+
+        search.clear_hash();
+        assert!(search
+            .get_transposition_table()
+            .probe(Board::default().get_hash(), 0)
+            .is_none());
+    }
+
+    #[test]
+    fn it_searches_fewer_nodes_with_pruning() {
+        let board = Board::new_from_fen(
+            "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+        )
+        .unwrap();
+        let limits = SearchLimits {
+            depth: Some(4),
+            ..SearchLimits::default()
+        };
+        let pruned = Search::new().run(&board, &limits, |_| {});
+
+        let mut search = Search::new();
+        let mut options = SearchOptions::default();
+        for name in [
+            "null-move",
+            "late-move-reductions",
+            "futility",
+            "reverse-futility",
+            "late-move-pruning",
+            "razoring",
+        ] {
+            options.set(name, false).unwrap();
+        }
+        search.set_options(options);
+        let full = search.run(&board, &limits, |_| {});
+
+        assert!(pruned.nodes < full.nodes);
+        assert!(SearchOptions::default().set("unknown", false).is_err());
+    }
+
+    #[test]
+    fn it_does_not_search_captures_past_depth_without_quiescence() {
+        let board =
+            Board::new_from_fen("r3k3/pppq1ppp/2n5/1B1pp3/3PP3/2N5/PPP2PPP/R2QK2R w KQq - 0 1")
+                .unwrap();
+        let mut search = Search::new();
+        let mut options = SearchOptions::default();
+        options.set("quiescence", false).unwrap();
+        options.set("check-extensions", false).unwrap();
+        search.set_options(options);
+        let limits = SearchLimits {
+            depth: Some(4),
+            ..SearchLimits::default()
+        };
+        let info = search.run(&board, &limits, |_| {});
+
+        assert!(info.seldepth <= 4);
+    }
+
+    #[test]
+    fn it_does_not_null_move_prune_pawn_endgames() {
+        assert!(!has_non_pawn_material(
+            &Board::new_from_fen("8/8/8/3k4/3P4/3K4/8/8 w - - 0 1").unwrap()
+        ));
+        assert!(has_non_pawn_material(
+            &Board::new_from_fen("8/8/8/3k4/3P4/3K4/8/7N w - - 0 1").unwrap()
+        ));
+    }
+
+    #[test]
+    fn it_returns_empty_pv_without_legal_moves() {
+        let info = search_to_depth("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", 3);
+        assert!(info.pv.is_empty());
+        assert_eq!(info.score, Score::Centipawns(0));
+    }
+}
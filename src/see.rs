@@ -0,0 +1,186 @@
+use crate::{
+    board::Board,
+    chess_move::Move,
+    piece::{
+        PieceColor, PieceKind, BISHOP_DIRECTIONS, KING_OFFSETS, KNIGHT_OFFSETS, ROOK_DIRECTIONS,
+    },
+    position::BoardIndex,
+};
+
+type Grid = [[Option<(PieceKind, PieceColor)>; 8]; 8];
+
+/// Piece values used for exchanges and move ordering
+pub fn get_piece_value(kind: PieceKind) -> i32 {
+    match kind {
+        PieceKind::Pawn => 100,
+        PieceKind::Knight => 320,
+        PieceKind::Bishop => 330,
+        PieceKind::Rook => 500,
+        PieceKind::Queen => 900,
+        PieceKind::King => 20000,
+    }
+}
+
+/// Piece captured by a move, en passant captures a pawn
+pub fn get_captured_kind(board: &Board, mv: &Move) -> Option<PieceKind> {
+    let piece = (*board.get_piece_by_position(mv.from))?;
+    match board.get_piece_by_position(mv.to) {
+        Some(captured) if captured.get_color() != piece.get_color() => Some(captured.get_kind()),
+        Some(_) => None,
+        None if piece.get_kind() == PieceKind::Pawn && mv.from.0 != mv.to.0 => {
+            Some(PieceKind::Pawn)
+        }
+        None => None,
+    }
+}
+
+/// Static exchange evaluation, material won or lost when both sides keep capturing
+/// on the destination square with their least valuable piece
+///
+/// Pins and checks are ignored, x-ray attackers behind other pieces are included.
+pub fn see(board: &Board, mv: &Move) -> i32 {
+    let Some(piece) = board.get_piece_by_position(mv.from) else {
+        return 0;
+    };
+    let mut grid: Grid = [[None; 8]; 8];
+    for piece in board.get_flat_pieces() {
+        let BoardIndex(file, rank) = piece.get_position().get_indices();
+        grid[file][rank] = Some((piece.get_kind(), *piece.get_color()));
+    }
+
+    let BoardIndex(from_file, from_rank) = mv.from.get_indices();
+    let BoardIndex(to_file, to_rank) = mv.to.get_indices();
+    let target = (to_file as i32, to_rank as i32);
+
+    let mut gains = vec![get_captured_kind(board, mv).map_or(0, get_piece_value)];
+    if get_captured_kind(board, mv).is_some() && grid[to_file][to_rank].is_none() {
+        // En passant, captured pawn is next to the moving one
+        grid[to_file][from_rank] = None;
+    }
+    let mut on_square = get_piece_value(piece.get_kind());
+    if let Some(promotion) = mv.promotion {
+        gains[0] += get_piece_value(promotion) - get_piece_value(PieceKind::Pawn);
+        on_square = get_piece_value(promotion);
+    }
+    grid[from_file][from_rank] = None;
+
+    let mut side = piece.get_color().opposite();
+    while let Some((file, rank, kind)) = get_least_valuable_attacker(&grid, target, side) {
+        // King can't capture a defended piece
+        if kind == PieceKind::King
+            && get_least_valuable_attacker(&grid, target, side.opposite()).is_some()
+        {
+            break;
+        }
+        gains.push(on_square - gains[gains.len() - 1]);
+        grid[file as usize][rank as usize] = None;
+        on_square = get_piece_value(kind);
+        side = side.opposite();
+    }
+
+    for i in (1..gains.len()).rev() {
+        gains[i - 1] = -(-gains[i - 1]).max(gains[i]);
+    }
+    gains[0]
+}
+
+fn get_least_valuable_attacker(
+    grid: &Grid,
+    (file, rank): (i32, i32),
+    color: PieceColor,
+) -> Option<(i32, i32, PieceKind)> {
+    let piece_at = |f: i32, r: i32| -> Option<(PieceKind, PieceColor)> {
+        if (0..8).contains(&f) && (0..8).contains(&r) {
+            grid[f as usize][r as usize]
+        } else {
+            None
+        }
+    };
+    let mut best: Option<(i32, i32, PieceKind)> = None;
+    let mut consider = |f: i32, r: i32, kind: PieceKind| {
+        if best.is_none_or(|(_, _, best_kind)| get_piece_value(kind) < get_piece_value(best_kind)) {
+            best = Some((f, r, kind));
+        }
+    };
+
+    // Pawns attacking the square stand one rank behind it from their point of view
+    for file_delta in [-1, 1] {
+        let (f, r) = (file + file_delta, rank - color.pawn_direction());
+        if piece_at(f, r) == Some((PieceKind::Pawn, color)) {
+            consider(f, r, PieceKind::Pawn);
+        }
+    }
+    for (df, dr) in KNIGHT_OFFSETS {
+        if piece_at(file + df, rank + dr) == Some((PieceKind::Knight, color)) {
+            consider(file + df, rank + dr, PieceKind::Knight);
+        }
+    }
+    for (df, dr) in KING_OFFSETS {
+        if piece_at(file + df, rank + dr) == Some((PieceKind::King, color)) {
+            consider(file + df, rank + dr, PieceKind::King);
+        }
+    }
+    for (directions, slider) in [
+        (ROOK_DIRECTIONS, PieceKind::Rook),
+        (BISHOP_DIRECTIONS, PieceKind::Bishop),
+    ] {
+        for (df, dr) in directions {
+            let (mut f, mut r) = (file + df, rank + dr);
+            while (0..8).contains(&f) && (0..8).contains(&r) {
+                if let Some((kind, piece_color)) = piece_at(f, r) {
+                    if piece_color == color && (kind == slider || kind == PieceKind::Queen) {
+                        consider(f, r, kind);
+                    }
+                    break;
+                }
+                f += df;
+                r += dr;
+            }
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn see_uci(fen: &str, uci: &str) -> i32 {
+        let board = Board::new_from_fen(fen).unwrap();
+        see(&board, &Move::from_uci(uci).unwrap())
+    }
+
+    #[test]
+    fn it_evaluates_simple_captures() {
+        // Undefended pawn
+        assert_eq!(
+            see_uci("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1", "e1e5"),
+            100
+        );
+        // Pawn defended by pawn, rook gets lost
+        assert_eq!(see_uci("4k3/8/3p4/4p3/8/8/8/4RK2 w - - 0 1", "e1e5"), -400);
+        assert_eq!(see_uci("4k3/8/8/8/8/8/8/4K3 w - - 0 1", "e1e2"), 0);
+    }
+
+    #[test]
+    fn it_includes_x_ray_attackers() {
+        // Queen behind the bishop and queen behind the rook join the exchange
+        assert_eq!(
+            see_uci(
+                "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+                "d3e5"
+            ),
+            -220
+        );
+        // Doubled rooks win a pawn defended by one rook
+        assert_eq!(see_uci("3r3k/3p4/8/8/8/8/3R4/3RK3 w - - 0 1", "d2d7"), 100);
+        // But not when king defends it too
+        assert_eq!(see_uci("3rk3/3p4/8/8/8/8/3R4/3RK3 w - - 0 1", "d2d7"), -400);
+    }
+
+    #[test]
+    fn it_does_not_let_king_capture_defended_piece() {
+        assert_eq!(see_uci("4k3/8/8/8/8/8/3r4/3QK3 b - - 0 1", "d2d1"), 400);
+        assert_eq!(see_uci("3rk3/8/8/8/8/8/3r4/3QK3 b - - 0 1", "d2d1"), 900);
+    }
+}
@@ -0,0 +1,183 @@
+use crate::{
+    rng::Rng,
+    search::{SearchInfo, SearchLimits},
+};
+
+/// Full strength, the search isn't limited
+pub const MAX_SKILL_LEVEL: u32 = 20;
+/// Rating of skill levels 0 to 19, measured in self-play with default limits so only
+/// the skill caps apply, from balanced random openings played with both colors. Level 0
+/// is anchored at 800, even levels add the measured gap to the level below, odd levels
+/// are midpoints and level 19 extends half the 16 to 18 gap. Self-play exaggerates
+/// gaps, so these are relative ratings rather than ones calibrated against people:
+///
+/// | Levels   | Games | +  | =  | -  | Elo  |
+/// |----------|-------|----|----|----|------|
+/// | 0 - 2    | 120   | 32 | 3  | 85 | -165 |
+/// | 2 - 4    | 120   | 38 | 3  | 79 | -124 |
+/// | 4 - 6    | 120   | 24 | 4  | 92 | -223 |
+/// | 6 - 8    | 100   | 17 | 8  | 75 | -230 |
+/// | 8 - 10   | 100   | 20 | 9  | 71 | -196 |
+/// | 10 - 12  | 60    | 9  | 5  | 46 | -250 |
+/// | 12 - 14  | 40    | 3  | 5  | 32 | -319 |
+/// | 14 - 16  | 24    | 2  | 1  | 21 | -374 |
+/// | 16 - 18  | 12    | 1  | 3  | 8  | -232 |
+const LEVEL_ELO: [u32; MAX_SKILL_LEVEL as usize] = [
+    800, 882, 965, 1027, 1088, 1200, 1312, 1427, 1542, 1640, 1737, 1862, 1987, 2147, 2306, 2493,
+    2680, 2796, 2912, 3028,
+];
+/// Elo range of `Skill::from_elo`
+pub const MIN_ELO: u32 = LEVEL_ELO[0];
+pub const MAX_ELO: u32 = LEVEL_ELO[MAX_SKILL_LEVEL as usize - 1];
+/// Lines searched to choose a weaker move from
+const SKILL_MULTI_PV: usize = 4;
+/// Largest score gap between the best and the worst line that the random bonus scales with
+const MAX_NOISE: i32 = 100;
+
+/// Playing strength, levels below MAX_SKILL_LEVEL search shallower and sometimes play
+/// worse moves on purpose
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Skill {
+    level: u32,
+}
+
+impl Default for Skill {
+    fn default() -> Self {
+        Skill {
+            level: MAX_SKILL_LEVEL,
+        }
+    }
+}
+
+impl Skill {
+    pub fn new(level: u32) -> Skill {
+        Skill {
+            level: level.min(MAX_SKILL_LEVEL),
+        }
+    }
+
+    /// Level whose measured rating is nearest, even MAX_ELO plays below full strength
+    pub fn from_elo(elo: u32) -> Skill {
+        let level = (0..MAX_SKILL_LEVEL)
+            .min_by_key(|&level| LEVEL_ELO[level as usize].abs_diff(elo))
+            .unwrap_or(0);
+        Skill::new(level)
+    }
+
+    pub fn get_level(&self) -> u32 {
+        self.level
+    }
+
+    pub fn is_limited(&self) -> bool {
+        self.level < MAX_SKILL_LEVEL
+    }
+
+    /// Lines to search, at least SKILL_MULTI_PV when limited
+    pub fn get_multi_pv(&self, multi_pv: usize) -> usize {
+        if self.is_limited() {
+            multi_pv.max(SKILL_MULTI_PV)
+        } else {
+            multi_pv
+        }
+    }
+
+    /// Limits with depth and nodes capped by the level, from depth 1 and 256 nodes at level 0
+    pub fn limit(&self, limits: &SearchLimits) -> SearchLimits {
+        if !self.is_limited() {
+            return limits.clone();
+        }
+        let depth = 1 + self.level / 2;
+        let nodes = 256 << (self.level / 2);
+        SearchLimits {
+            depth: Some(limits.depth.map_or(depth, |limit| limit.min(depth))),
+            nodes: Some(limits.nodes.map_or(nodes, |limit| limit.min(nodes))),
+            ..limits.clone()
+        }
+    }
+
+    /// Index of the line to play, lines are sorted from the best one
+    ///
+    /// Each line counts as the best score minus a share of its gap to it, from 6% of the gap
+    /// at level 0 to 36% at level 19, plus a random bonus of up to MAX_NOISE, smaller when
+    /// the lines are closer. The highest total is played, so lower levels play worse moves
+    /// more often.
+    pub fn pick_line(&self, lines: &[SearchInfo], rng: &mut Rng) -> usize {
+        let Some(best) = lines.first() else {
+            return 0;
+        };
+        if !self.is_limited() {
+            return 0;
+        }
+        let top = best.score.to_internal();
+        let worst = lines.last().map_or(top, |line| line.score.to_internal());
+        let noise = (top - worst).min(MAX_NOISE) as i64;
+        let weakness = 120 - 2 * self.level as i64;
+
+        (0..lines.len())
+            .max_by_key(|&i| {
+                let score = lines[i].score.to_internal() as i64;
+                let gap = top as i64 - score;
+                score + (weakness * gap + noise * rng.below(weakness as usize) as i64) / 128
+            })
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::search::Score;
+
+    fn line(cp: i32) -> SearchInfo {
+        SearchInfo {
+            depth: 1,
+            seldepth: 1,
+            multipv: 1,
+            score: Score::Centipawns(cp),
+            nodes: 0,
+            time: Duration::ZERO,
+            pv: Vec::new(),
+            hashfull: 0,
+            mate_search: None,
+        }
+    }
+
+    #[test]
+    fn it_limits_search_by_level() {
+        assert!(!Skill::default().is_limited());
+        assert_eq!(Skill::new(30), Skill::default());
+        assert_eq!(Skill::from_elo(MIN_ELO).get_level(), 0);
+        assert_eq!(Skill::from_elo(MAX_ELO).get_level(), MAX_SKILL_LEVEL - 1);
+        assert_eq!(Skill::from_elo(0).get_level(), 0);
+        assert_eq!(Skill::from_elo(1500).get_level(), 8);
+
+        let limits = SearchLimits {
+            depth: Some(3),
+            ..SearchLimits::default()
+        };
+        let limited = Skill::new(10).limit(&limits);
+        assert_eq!(limited.depth, Some(3));
+        assert_eq!(limited.nodes, Some(256 << 5));
+        assert_eq!(Skill::new(0).limit(&limits).depth, Some(1));
+        assert_eq!(Skill::default().limit(&limits), limits);
+        assert_eq!(Skill::new(0).get_multi_pv(1), SKILL_MULTI_PV);
+    }
+
+    #[test]
+    fn it_plays_worse_moves_more_often_at_lower_levels() {
+        let lines = [line(50), line(30), line(0), line(-400)];
+        let count_best = |level| {
+            let mut rng = Rng::new(1);
+            (0..1000)
+                .filter(|_| Skill::new(level).pick_line(&lines, &mut rng) == 0)
+                .count()
+        };
+        let weak = count_best(0);
+        let strong = count_best(19);
+        assert!(weak < strong);
+        assert!(strong < 1000);
+        assert_eq!(count_best(MAX_SKILL_LEVEL), 1000);
+    }
+}
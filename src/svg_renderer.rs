@@ -0,0 +1,449 @@
+use crate::{
+    board::Board,
+    piece::{Piece, PieceColor, PieceKind},
+    position::{BoardIndex, Position},
+};
+
+/// Piece artwork is drawn in a 45x45 box and scaled to square size
+const PIECE_BOX: f64 = 45.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PieceSet {
+    /// Simple vector shapes, doesn't depend on any fonts
+    Geometric,
+    /// Unicode chess figurines rendered as text
+    Figurine,
+    /// Discs with FEN letters
+    Letters,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoardColors {
+    pub light: String,
+    pub dark: String,
+    /// Used for squares given in `highlights` without own color
+    pub highlight: String,
+}
+
+impl BoardColors {
+    pub fn brown() -> BoardColors {
+        BoardColors::new("#f0d9b5", "#b58863", "#cdd26a")
+    }
+
+    pub fn green() -> BoardColors {
+        BoardColors::new("#eeeed2", "#769656", "#baca44")
+    }
+
+    pub fn blue() -> BoardColors {
+        BoardColors::new("#dee3e6", "#8ca2ad", "#9bc700")
+    }
+
+    pub fn gray() -> BoardColors {
+        BoardColors::new("#e0e0e0", "#9e9e9e", "#c5c56a")
+    }
+
+    pub fn new(light: &str, dark: &str, highlight: &str) -> BoardColors {
+        BoardColors {
+            light: String::from(light),
+            dark: String::from(dark),
+            highlight: String::from(highlight),
+        }
+    }
+}
+
+impl Default for BoardColors {
+    fn default() -> Self {
+        BoardColors::brown()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Arrow {
+    pub from: Position,
+    pub to: Position,
+    pub color: String,
+}
+
+impl Arrow {
+    pub fn new(from: Position, to: Position, color: &str) -> Arrow {
+        Arrow {
+            from,
+            to,
+            color: String::from(color),
+        }
+    }
+}
+
+/// Renders board as self-contained SVG
+///
+/// Output depends only on the board and options, so it can be compared against stored snapshots
+#[derive(Clone, Debug, PartialEq)]
+pub struct SvgRenderer {
+    pub piece_set: PieceSet,
+    pub colors: BoardColors,
+    /// Size of a single square in pixels
+    pub square_size: u32,
+    /// File letters and rank numbers drawn in the corners of edge squares
+    pub coordinates: bool,
+    /// Shows board from Black's side
+    pub flipped: bool,
+    /// Squares with optional color, `colors.highlight` is used when it's None
+    pub highlights: Vec<(Position, Option<String>)>,
+    pub arrows: Vec<Arrow>,
+}
+
+impl Default for SvgRenderer {
+    fn default() -> Self {
+        SvgRenderer {
+            piece_set: PieceSet::Geometric,
+            colors: BoardColors::default(),
+            square_size: 45,
+            coordinates: true,
+            flipped: false,
+            highlights: Vec::new(),
+            arrows: Vec::new(),
+        }
+    }
+}
+
+impl SvgRenderer {
+    pub fn render(&self, board: &Board) -> String {
+        let size = self.square_size as f64;
+        let board_size = size * 8.0;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {0} {0}\">\n",
+            num(board_size)
+        );
+
+        for rank in 0..8 {
+            for file in 0..8 {
+                let (x, y) =
+                    self.get_square_origin(Position::new_from_indices(BoardIndex(file, rank)));
+                svg.push_str(&format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                    num(x),
+                    num(y),
+                    num(size),
+                    num(size),
+                    escape(self.get_square_color(file, rank))
+                ));
+            }
+        }
+
+        for (pos, color) in &self.highlights {
+            let (x, y) = self.get_square_origin(*pos);
+            svg.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" fill-opacity=\"0.6\"/>\n",
+                num(x),
+                num(y),
+                num(size),
+                num(size),
+                escape(color.as_deref().unwrap_or(&self.colors.highlight))
+            ));
+        }
+
+        if self.coordinates {
+            svg.push_str(&self.render_coordinates());
+        }
+
+        for piece in board.get_flat_pieces() {
+            let (x, y) = self.get_square_origin(*piece.get_position());
+            svg.push_str(&format!(
+                "<g transform=\"translate({},{}) scale({})\">{}</g>\n",
+                num(x),
+                num(y),
+                num(size / PIECE_BOX),
+                get_piece_artwork(piece.get_kind(), *piece.get_color(), self.piece_set)
+            ));
+        }
+
+        for arrow in &self.arrows {
+            svg.push_str(&self.render_arrow(arrow));
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Top left corner of a square in pixels
+    fn get_square_origin(&self, pos: Position) -> (f64, f64) {
+        let BoardIndex(file, rank) = pos.get_indices();
+        let (column, row) = if self.flipped {
+            (7 - file, rank)
+        } else {
+            (file, 7 - rank)
+        };
+        let size = self.square_size as f64;
+        (column as f64 * size, row as f64 * size)
+    }
+
+    fn get_square_color(&self, file: usize, rank: usize) -> &str {
+        if (file + rank).is_multiple_of(2) {
+            &self.colors.dark
+        } else {
+            &self.colors.light
+        }
+    }
+
+    fn render_coordinates(&self) -> String {
+        let size = self.square_size as f64;
+        let font_size = size * 0.25;
+        let mut svg = String::new();
+        let bottom_rank = if self.flipped { 7 } else { 0 };
+        let left_file = if self.flipped { 7 } else { 0 };
+
+        for index in 0..8 {
+            let pos = Position::new_from_indices(BoardIndex(index, bottom_rank));
+            let (x, y) = self.get_square_origin(pos);
+            svg.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"{}\" text-anchor=\"end\" fill=\"{}\">{}</text>\n",
+                num(x + size - 2.0),
+                num(y + size - 3.0),
+                num(font_size),
+                escape(self.get_square_color(index + 1, bottom_rank)),
+                pos.0
+            ));
+
+            let pos = Position::new_from_indices(BoardIndex(left_file, index));
+            let (x, y) = self.get_square_origin(pos);
+            svg.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"{}\" fill=\"{}\">{}</text>\n",
+                num(x + 2.0),
+                num(y + font_size),
+                num(font_size),
+                escape(self.get_square_color(left_file + 1, index)),
+                pos.1
+            ));
+        }
+        svg
+    }
+
+    /// Arrow is a line with a triangular head, both computed here so no marker definitions are needed
+    fn render_arrow(&self, arrow: &Arrow) -> String {
+        let size = self.square_size as f64;
+        let center = |pos: Position| {
+            let (x, y) = self.get_square_origin(pos);
+            (x + size / 2.0, y + size / 2.0)
+        };
+        let (x1, y1) = center(arrow.from);
+        let (x2, y2) = center(arrow.to);
+        let length = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+        if length == 0.0 {
+            return String::new();
+        }
+
+        let (dx, dy) = ((x2 - x1) / length, (y2 - y1) / length);
+        let head_length = size * 0.4;
+        let head_width = size * 0.25;
+        let (base_x, base_y) = (x2 - dx * head_length, y2 - dy * head_length);
+
+        format!(
+            "<g fill=\"{0}\" stroke=\"{0}\" opacity=\"0.8\"><line x1=\"{1}\" y1=\"{2}\" x2=\"{3}\" y2=\"{4}\" stroke-width=\"{5}\" stroke-linecap=\"round\"/><polygon points=\"{6},{7} {8},{9} {10},{11}\" stroke=\"none\"/></g>\n",
+            escape(&arrow.color),
+            num(x1),
+            num(y1),
+            num(base_x),
+            num(base_y),
+            num(size * 0.15),
+            num(x2),
+            num(y2),
+            num(base_x - dy * head_width),
+            num(base_y + dx * head_width),
+            num(base_x + dy * head_width),
+            num(base_y - dx * head_width),
+        )
+    }
+}
+
+/// Standalone SVG of a single piece, e.g. for UI buttons
+pub fn get_piece_svg(kind: PieceKind, color: PieceColor, piece_set: PieceSet, size: u32) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" viewBox=\"0 0 45 45\">{1}</svg>\n",
+        size,
+        get_piece_artwork(kind, color, piece_set)
+    )
+}
+
+/// Piece drawing in the 45x45 box
+fn get_piece_artwork(kind: PieceKind, color: PieceColor, piece_set: PieceSet) -> String {
+    let (fill, stroke) = match color {
+        PieceColor::White => ("#ffffff", "#000000"),
+        PieceColor::Black => ("#000000", "#ffffff"),
+    };
+
+    match piece_set {
+        PieceSet::Geometric => format!(
+            "<g fill=\"{}\" stroke=\"#000000\" stroke-width=\"1.5\" stroke-linejoin=\"round\">{}</g>",
+            fill,
+            get_geometric_shape(kind, stroke)
+        ),
+        PieceSet::Figurine => {
+            // Filled figurines are used for both colors, color comes from fill and outline
+            let glyph = Piece::new(kind, PieceColor::Black, Position('a', '1')).get_char(true);
+            format!(
+                "<text x=\"22.5\" y=\"37\" font-family=\"DejaVu Sans, Segoe UI Symbol, sans-serif\" font-size=\"38\" text-anchor=\"middle\" fill=\"{}\" stroke=\"#000000\" stroke-width=\"1\">{}</text>",
+                fill, glyph
+            )
+        }
+        PieceSet::Letters => format!(
+            "<circle cx=\"22.5\" cy=\"22.5\" r=\"17\" fill=\"{}\" stroke=\"#000000\" stroke-width=\"1.5\"/><text x=\"22.5\" y=\"30\" font-family=\"sans-serif\" font-size=\"22\" font-weight=\"bold\" text-anchor=\"middle\" fill=\"{}\">{}</text>",
+            fill,
+            stroke,
+            kind.get_char()
+        ),
+    }
+}
+
+/// Shapes are filled with piece color, `detail` is used for lines drawn on top of the body
+fn get_geometric_shape(kind: PieceKind, detail: &str) -> String {
+    let base = "<rect x=\"10\" y=\"35\" width=\"25\" height=\"4\" rx=\"1\"/>";
+    match kind {
+        PieceKind::Pawn => format!(
+            "<circle cx=\"22.5\" cy=\"14\" r=\"5\"/><path d=\"M 17 21 L 28 21 L 26 25 L 29 35 L 16 35 L 19 25 Z\"/>{}",
+            base
+        ),
+        PieceKind::Rook => format!(
+            "<path d=\"M 12 9 L 16 9 L 16 12 L 20 12 L 20 9 L 25 9 L 25 12 L 29 12 L 29 9 L 33 9 L 33 15 L 30 18 L 30 31 L 33 34 L 33 35 L 12 35 L 12 34 L 15 31 L 15 18 L 12 15 Z\"/><path d=\"M 15 18 L 30 18 M 15 31 L 30 31\" fill=\"none\" stroke=\"{}\" stroke-width=\"1\"/>{}",
+            detail, base
+        ),
+        PieceKind::Knight => format!(
+            "<path d=\"M 14 35 L 31 35 C 31 27 29 21 31 14 C 28 10 24 8 20 8 L 19 11 C 15 13 11 18 10 23 L 12 26 L 16 24 L 19 22 C 17 27 14 30 14 35 Z\"/><circle cx=\"20\" cy=\"14\" r=\"1.5\" fill=\"{}\" stroke=\"none\"/>{}",
+            detail, base
+        ),
+        PieceKind::Bishop => format!(
+            "<circle cx=\"22.5\" cy=\"8\" r=\"2.5\"/><path d=\"M 22.5 10 C 16 15 14 22 17 29 L 28 29 C 31 22 29 15 22.5 10 Z\"/><path d=\"M 15 31 L 30 31 L 31 35 L 14 35 Z\"/><path d=\"M 20 18 L 25 23\" fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\"/>{}",
+            detail, base
+        ),
+        PieceKind::Queen => format!(
+            "<path d=\"M 11 31 L 9 14 L 13 25 L 15.5 11 L 19 24 L 22.5 9 L 26 24 L 29.5 11 L 32 25 L 36 14 L 34 31 Z\"/><circle cx=\"9\" cy=\"13\" r=\"2\"/><circle cx=\"15.5\" cy=\"10\" r=\"2\"/><circle cx=\"22.5\" cy=\"8\" r=\"2\"/><circle cx=\"29.5\" cy=\"10\" r=\"2\"/><circle cx=\"36\" cy=\"13\" r=\"2\"/><path d=\"M 11 31 L 34 31 L 33 35 L 12 35 Z\"/>{}",
+            base
+        ),
+        PieceKind::King => format!(
+            "<path d=\"M 22.5 5 L 22.5 13 M 19 8.5 L 26 8.5\" fill=\"none\" stroke-width=\"2\"/><path d=\"M 22.5 14 C 19 14 17 17 18.5 21 C 12 17 7 21 9 27 L 12 32 L 33 32 L 36 27 C 38 21 33 17 26.5 21 C 28 17 26 14 22.5 14 Z\"/><path d=\"M 12 32 L 33 32 L 33 35 L 12 35 Z\"/><path d=\"M 14 27 L 31 27\" fill=\"none\" stroke=\"{}\" stroke-width=\"1\"/>{}",
+            detail, base
+        ),
+    }
+}
+
+/// Formats number without trailing zeros, so output stays short and stable
+fn num(value: f64) -> String {
+    let formatted = format!("{:.2}", value);
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    if trimmed == "-0" {
+        return String::from("0");
+    }
+    String::from(trimmed)
+}
+
+/// Escapes a user given value for use inside a quoted attribute
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_renders_deterministic_svg() {
+        let board = Board::default();
+        let renderer = SvgRenderer::default();
+        let svg = renderer.render(&board);
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"360\""));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg, renderer.render(&board));
+        assert_eq!(svg.matches("<g transform=").count(), 32);
+    }
+
+    #[test]
+    fn it_renders_squares_for_orientation() {
+        let board = Board::new_from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let renderer = SvgRenderer {
+            coordinates: false,
+            ..SvgRenderer::default()
+        };
+        let svg = renderer.render(&board);
+        // a1 is dark and in the bottom left corner
+        assert!(
+            svg.contains("<rect x=\"0\" y=\"315\" width=\"45\" height=\"45\" fill=\"#b58863\"/>")
+        );
+        assert!(svg.contains("<g transform=\"translate(180,315) scale(1)\">"));
+
+        let flipped = SvgRenderer {
+            flipped: true,
+            ..renderer
+        };
+        let svg = flipped.render(&board);
+        assert!(
+            svg.contains("<rect x=\"315\" y=\"0\" width=\"45\" height=\"45\" fill=\"#b58863\"/>")
+        );
+        assert!(svg.contains("<g transform=\"translate(135,0) scale(1)\">"));
+    }
+
+    #[test]
+    fn it_renders_highlights_arrows_and_coordinates() {
+        let renderer = SvgRenderer {
+            square_size: 60,
+            colors: BoardColors::green(),
+            highlights: vec![
+                (Position('e', '2'), None),
+                (Position('e', '4'), Some(String::from("#ff0000"))),
+            ],
+            arrows: vec![Arrow::new(
+                Position('g', '1'),
+                Position('f', '3'),
+                "#15781b",
+            )],
+            ..SvgRenderer::default()
+        };
+        let svg = renderer.render(&Board::default());
+
+        assert!(svg.contains("fill=\"#baca44\" fill-opacity=\"0.6\""));
+        assert!(
+            svg.contains("<rect x=\"240\" y=\"240\" width=\"60\" height=\"60\" fill=\"#ff0000\"")
+        );
+        assert!(svg.contains("<g fill=\"#15781b\" stroke=\"#15781b\""));
+        assert!(svg.contains(">a</text>"));
+        assert!(svg.contains(">8</text>"));
+    }
+
+    #[test]
+    fn it_escapes_colors() {
+        let renderer = SvgRenderer {
+            colors: BoardColors {
+                light: String::from("red\" onload=\"alert(1)"),
+                ..BoardColors::default()
+            },
+            arrows: vec![Arrow::new(Position('e', '2'), Position('e', '4'), "<b>&")],
+            ..SvgRenderer::default()
+        };
+        let svg = renderer.render(&Board::default());
+
+        assert!(svg.contains("fill=\"red&quot; onload=&quot;alert(1)\""));
+        assert!(svg.contains("<g fill=\"&lt;b&gt;&amp;\""));
+        assert!(!svg.contains("onload=\""));
+    }
+
+    #[test]
+    fn it_renders_piece_sets() {
+        let letters = get_piece_svg(PieceKind::Knight, PieceColor::Black, PieceSet::Letters, 45);
+        assert!(letters.contains(">N</text>"));
+
+        let figurine = get_piece_svg(PieceKind::Queen, PieceColor::White, PieceSet::Figurine, 45);
+        assert!(figurine.contains(">♛</text>"));
+        assert!(figurine.contains("fill=\"#ffffff\""));
+    }
+
+    #[test]
+    fn it_formats_numbers() {
+        assert_eq!(num(45.0), "45");
+        assert_eq!(num(22.5), "22.5");
+        assert_eq!(num(1.0 / 3.0), "0.33");
+        assert_eq!(num(-0.001), "0");
+    }
+}
@@ -0,0 +1,247 @@
+use std::time::{Duration, Instant};
+
+use crate::{piece::PieceColor, search::SearchLimits};
+
+/// Time reserved for communication delays, subtracted from every allocation
+pub const DEFAULT_MOVE_OVERHEAD: Duration = Duration::from_millis(30);
+/// Moves expected until the next time control when the GUI doesn't tell
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+/// Hard deadline is at most this many times the soft one
+const HARD_LIMIT_FACTOR: u32 = 4;
+/// Score drop in centipawns between iterations which makes the search think longer
+const SCORE_DROP: i32 = 30;
+
+/// Decides how long a search may take from the clock state
+///
+/// No new iteration is started after the soft deadline, which grows when the best move
+/// keeps changing or the score drops. Search is aborted at the hard deadline.
+#[derive(Clone, Debug)]
+pub struct TimeManager {
+    start: Instant,
+    /// Limits are measured from here, differs from search start after pondering
+    clock_start: Instant,
+    soft_limit: Option<Duration>,
+    hard_limit: Option<Duration>,
+    /// Best move changes, halved every iteration so recent changes weigh more
+    instability: f64,
+    /// Number of consecutive iterations with the same best move
+    stable_iterations: u32,
+    score_dropped: bool,
+}
+
+impl Default for TimeManager {
+    fn default() -> Self {
+        TimeManager::new(
+            &SearchLimits::default(),
+            PieceColor::White,
+            DEFAULT_MOVE_OVERHEAD,
+        )
+    }
+}
+
+impl TimeManager {
+    /// Starts timing a search for `color`, move time takes precedence over clock times
+    pub fn new(limits: &SearchLimits, color: PieceColor, move_overhead: Duration) -> TimeManager {
+        let (time, increment) = match color {
+            PieceColor::White => (limits.white_time, limits.white_increment),
+            PieceColor::Black => (limits.black_time, limits.black_increment),
+        };
+        let (soft_limit, hard_limit) = if let Some(movetime) = limits.movetime {
+            let limit = movetime.saturating_sub(move_overhead);
+            (Some(limit), Some(limit))
+        } else if let Some(time) = time {
+            let available = time.saturating_sub(move_overhead);
+            let increment = increment.unwrap_or(Duration::ZERO);
+            let moves_to_go = limits
+                .moves_to_go
+                .unwrap_or(DEFAULT_MOVES_TO_GO)
+                .clamp(1, 50);
+            // Never plan to use more than most of the clock, even on the last move before
+            // the time control, so there is time left for the next moves
+            let hard_limit =
+                (available / moves_to_go * HARD_LIMIT_FACTOR + increment).min(available * 4 / 5);
+            let soft_limit = (available / moves_to_go + increment * 3 / 4).min(hard_limit);
+            (Some(soft_limit), Some(hard_limit))
+        } else {
+            (None, None)
+        };
+
+        let start = Instant::now();
+        TimeManager {
+            start,
+            clock_start: start,
+            soft_limit,
+            hard_limit,
+            instability: 0.0,
+            stable_iterations: 0,
+            score_dropped: false,
+        }
+    }
+
+    /// Pondering turned into a normal search, our clock starts running now
+    pub fn on_ponderhit(&mut self) {
+        self.clock_start = Instant::now();
+    }
+
+    /// Time limits apply, search isn't limited only by depth, nodes or a stop command
+    pub fn is_active(&self) -> bool {
+        self.hard_limit.is_some()
+    }
+
+    /// Time since search start
+    pub fn get_elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Time used from our clock
+    pub fn get_clock_elapsed(&self) -> Duration {
+        self.clock_start.elapsed()
+    }
+
+    pub fn get_soft_limit(&self) -> Option<Duration> {
+        self.soft_limit
+    }
+
+    pub fn get_hard_limit(&self) -> Option<Duration> {
+        self.hard_limit
+    }
+
+    /// Records outcome of a completed iteration
+    pub fn on_iteration(&mut self, best_move_changed: bool, score_change: i32) {
+        self.instability /= 2.0;
+        if best_move_changed {
+            self.instability += 1.0;
+            self.stable_iterations = 0;
+        } else {
+            self.stable_iterations += 1;
+        }
+        self.score_dropped = score_change <= -SCORE_DROP;
+    }
+
+    /// Soft deadline scaled by best move stability and score trend
+    pub fn get_scaled_soft_limit(&self) -> Option<Duration> {
+        let mut scale = 1.0 + self.instability;
+        if self.score_dropped {
+            scale *= 1.5;
+        }
+        if self.stable_iterations >= 4 {
+            scale *= 0.7;
+        }
+        let soft_limit = self.soft_limit?.mul_f64(scale);
+        Some(
+            self.hard_limit
+                .map_or(soft_limit, |hard| soft_limit.min(hard)),
+        )
+    }
+
+    /// No new iteration should be started
+    ///
+    /// Time spent pondering counts here, the search has already thought about the position.
+    pub fn is_soft_limit_reached(&self) -> bool {
+        self.get_scaled_soft_limit()
+            .is_some_and(|limit| self.get_elapsed() >= limit)
+    }
+
+    /// Running iteration has to be aborted
+    pub fn is_hard_limit_reached(&self) -> bool {
+        self.hard_limit
+            .is_some_and(|limit| self.get_clock_elapsed() >= limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(ms: u64) -> Option<Duration> {
+        Some(Duration::from_millis(ms))
+    }
+
+    #[test]
+    fn it_uses_move_time_minus_overhead() {
+        let limits = SearchLimits {
+            movetime: millis(1000),
+            white_time: millis(60_000),
+            ..SearchLimits::default()
+        };
+        let manager = TimeManager::new(&limits, PieceColor::White, Duration::from_millis(30));
+
+        assert_eq!(manager.get_soft_limit(), millis(970));
+        assert_eq!(manager.get_hard_limit(), millis(970));
+        assert!(!TimeManager::default().is_active());
+    }
+
+    #[test]
+    fn it_splits_clock_time_between_moves() {
+        let limits = SearchLimits {
+            white_time: millis(60_000),
+            black_time: millis(3_000),
+            white_increment: millis(1_000),
+            ..SearchLimits::default()
+        };
+        let white = TimeManager::new(&limits, PieceColor::White, Duration::ZERO);
+        assert_eq!(white.get_soft_limit(), millis(2_750));
+        assert_eq!(white.get_hard_limit(), millis(9_000));
+
+        let black = TimeManager::new(&limits, PieceColor::Black, Duration::ZERO);
+        assert_eq!(black.get_soft_limit(), millis(100));
+        assert_eq!(black.get_hard_limit(), millis(400));
+    }
+
+    #[test]
+    fn it_never_plans_to_use_whole_clock() {
+        let limits = SearchLimits {
+            black_time: millis(1_000),
+            black_increment: millis(2_000),
+            moves_to_go: Some(1),
+            ..SearchLimits::default()
+        };
+        let manager = TimeManager::new(&limits, PieceColor::Black, Duration::from_millis(50));
+        assert_eq!(manager.get_hard_limit(), millis(760));
+        assert_eq!(manager.get_soft_limit(), millis(760));
+
+        let limits = SearchLimits {
+            white_time: millis(20),
+            ..SearchLimits::default()
+        };
+        let manager = TimeManager::new(&limits, PieceColor::White, Duration::from_millis(50));
+        assert_eq!(manager.get_hard_limit(), millis(0));
+    }
+
+    #[test]
+    fn it_extends_on_instability_and_score_drop() {
+        let limits = SearchLimits {
+            white_time: millis(30_000),
+            ..SearchLimits::default()
+        };
+        let mut manager = TimeManager::new(&limits, PieceColor::White, Duration::ZERO);
+        assert_eq!(manager.get_scaled_soft_limit(), millis(1_000));
+
+        manager.on_iteration(true, 0);
+        assert_eq!(manager.get_scaled_soft_limit(), millis(2_000));
+        manager.on_iteration(false, -50);
+        assert_eq!(manager.get_scaled_soft_limit(), millis(2_250));
+
+        for _ in 0..10 {
+            manager.on_iteration(false, 0);
+        }
+        assert!(manager.get_scaled_soft_limit() < millis(1_000));
+    }
+
+    #[test]
+    fn it_counts_ponder_time_only_toward_soft_limit() {
+        let limits = SearchLimits {
+            white_time: millis(300),
+            ponder: true,
+            ..SearchLimits::default()
+        };
+        let mut manager = TimeManager::new(&limits, PieceColor::White, Duration::ZERO);
+        assert_eq!(manager.get_soft_limit(), millis(10));
+        assert_eq!(manager.get_hard_limit(), millis(40));
+
+        std::thread::sleep(Duration::from_millis(50));
+        manager.on_ponderhit();
+        assert!(manager.is_soft_limit_reached());
+        assert!(!manager.is_hard_limit_reached());
+    }
+}
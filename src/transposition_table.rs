@@ -0,0 +1,357 @@
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+use crate::{
+    chess_move::Move,
+    piece::PieceKind,
+    position::{BoardIndex, Position},
+    search::{MATE_SCORE, MAX_PLY},
+};
+
+pub const DEFAULT_SIZE_MB: usize = 16;
+const ENTRIES_PER_BUCKET: usize = 4;
+/// Age is stored in 6 bits and wraps around
+const AGE_MASK: u8 = 0x3f;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    /// Score is at least this, search failed high
+    Lower,
+    /// Score is at most this, search failed low
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TtEntry {
+    pub best_move: Option<Move>,
+    /// Mate scores are relative to the probing node
+    pub score: i32,
+    pub depth: u32,
+    pub bound: Bound,
+}
+
+/// Entry is two atomics, with key stored xor-ed with data, so entries torn by
+/// concurrent writes don't match any key and are ignored
+#[derive(Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+#[derive(Default)]
+struct Bucket {
+    slots: [Slot; ENTRIES_PER_BUCKET],
+}
+
+/// Lock-free transposition table shared by search threads
+pub struct TranspositionTable {
+    buckets: Vec<Bucket>,
+    age: AtomicU8,
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        TranspositionTable::new(DEFAULT_SIZE_MB)
+    }
+}
+
+impl TranspositionTable {
+    pub fn new(size_mb: usize) -> TranspositionTable {
+        let mut table = TranspositionTable {
+            buckets: Vec::new(),
+            age: AtomicU8::new(0),
+        };
+        table.resize(size_mb);
+        table
+    }
+
+    /// Reallocates the table, which also clears it
+    pub fn resize(&mut self, size_mb: usize) {
+        let count = (size_mb.max(1) * 1024 * 1024 / std::mem::size_of::<Bucket>()).max(1);
+        self.buckets = Vec::new();
+        self.buckets.resize_with(count, Bucket::default);
+    }
+
+    pub fn get_size_mb(&self) -> usize {
+        self.buckets.len() * std::mem::size_of::<Bucket>() / (1024 * 1024)
+    }
+
+    pub fn clear(&self) {
+        for slot in self.buckets.iter().flat_map(|bucket| &bucket.slots) {
+            slot.key.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+        self.age.store(0, Ordering::Relaxed);
+    }
+
+    /// Marks start of a new search, entries from older searches get replaced first
+    pub fn new_search(&self) {
+        let age = (self.age.load(Ordering::Relaxed) + 1) & AGE_MASK;
+        self.age.store(age, Ordering::Relaxed);
+    }
+
+    /// Number of searches since the table was cleared, wrapping around at AGE_MASK
+    pub fn get_age(&self) -> u8 {
+        self.age.load(Ordering::Relaxed)
+    }
+
+    pub fn probe(&self, hash: u64, ply: usize) -> Option<TtEntry> {
+        self.get_bucket(hash).slots.iter().find_map(|slot| {
+            let data = slot.data.load(Ordering::Relaxed);
+            if data == 0 || slot.key.load(Ordering::Relaxed) ^ data != hash {
+                return None;
+            }
+            let mut entry = unpack(data);
+            entry.score = score_from_tt(entry.score, ply);
+            Some(entry)
+        })
+    }
+
+    /// Stores search result, replacing the same position or the least valuable entry in the bucket
+    pub fn store(
+        &self,
+        hash: u64,
+        best_move: Option<Move>,
+        score: i32,
+        depth: u32,
+        bound: Bound,
+        ply: usize,
+    ) {
+        let age = self.age.load(Ordering::Relaxed);
+        let bucket = self.get_bucket(hash);
+
+        let mut replace = &bucket.slots[0];
+        let mut lowest_value = i32::MAX;
+        for slot in &bucket.slots {
+            let data = slot.data.load(Ordering::Relaxed);
+            if data == 0 || slot.key.load(Ordering::Relaxed) ^ data == hash {
+                replace = slot;
+                break;
+            }
+            let (slot_depth, slot_age) = (get_depth(data), get_age(data));
+            let age_difference = (age.wrapping_sub(slot_age) & AGE_MASK) as i32;
+            let value = slot_depth as i32 - 8 * age_difference;
+            if value < lowest_value {
+                lowest_value = value;
+                replace = slot;
+            }
+        }
+
+        let old_data = replace.data.load(Ordering::Relaxed);
+        let is_same_position =
+            old_data != 0 && replace.key.load(Ordering::Relaxed) ^ old_data == hash;
+        // Keep the old move when the new search didn't find any
+        let best_move = match (best_move, is_same_position) {
+            (None, true) => unpack(old_data).best_move,
+            (best_move, _) => best_move,
+        };
+        if is_same_position
+            && bound != Bound::Exact
+            && get_age(old_data) == age
+            && depth + 2 < get_depth(old_data)
+        {
+            return;
+        }
+
+        let data = pack(best_move, score_to_tt(score, ply), depth, bound, age);
+        replace.key.store(hash ^ data, Ordering::Relaxed);
+        replace.data.store(data, Ordering::Relaxed);
+    }
+
+    /// Permill of entries filled in the current search, sampled from the start of the table
+    pub fn get_hashfull(&self) -> u32 {
+        let age = self.age.load(Ordering::Relaxed);
+        let sample: Vec<u64> = self
+            .buckets
+            .iter()
+            .flat_map(|bucket| &bucket.slots)
+            .take(1000)
+            .map(|slot| slot.data.load(Ordering::Relaxed))
+            .collect();
+        let used = sample
+            .iter()
+            .filter(|data| **data != 0 && get_age(**data) == age)
+            .count();
+        (used * 1000 / sample.len()) as u32
+    }
+
+    fn get_bucket(&self, hash: u64) -> &Bucket {
+        let index = ((hash as u128 * self.buckets.len() as u128) >> 64) as usize;
+        &self.buckets[index]
+    }
+}
+
+/// Mate scores are stored relative to the node, not the root
+fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_SCORE - MAX_PLY as i32 {
+        score + ply as i32
+    } else if score <= -MATE_SCORE + MAX_PLY as i32 {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_SCORE - MAX_PLY as i32 {
+        score - ply as i32
+    } else if score <= -MATE_SCORE + MAX_PLY as i32 {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+// Data layout, lowest bits first:
+// 16 bits move, 16 bits score, 8 bits depth, 2 bits bound, 6 bits age, 1 bit set on every entry
+const VALID_BIT: u64 = 1 << 48;
+
+fn pack(best_move: Option<Move>, score: i32, depth: u32, bound: Bound, age: u8) -> u64 {
+    let bound = match bound {
+        Bound::Exact => 0,
+        Bound::Lower => 1,
+        Bound::Upper => 2,
+    };
+    pack_move(best_move) as u64
+        | ((score as i16 as u16 as u64) << 16)
+        | ((depth.min(255) as u64) << 32)
+        | (bound << 40)
+        | (((age & AGE_MASK) as u64) << 42)
+        | VALID_BIT
+}
+
+fn unpack(data: u64) -> TtEntry {
+    TtEntry {
+        best_move: unpack_move(data as u16),
+        score: (data >> 16) as u16 as i16 as i32,
+        depth: get_depth(data),
+        bound: match (data >> 40) & 0b11 {
+            0 => Bound::Exact,
+            1 => Bound::Lower,
+            _ => Bound::Upper,
+        },
+    }
+}
+
+fn get_depth(data: u64) -> u32 {
+    ((data >> 32) & 0xff) as u32
+}
+
+fn get_age(data: u64) -> u8 {
+    ((data >> 42) as u8) & AGE_MASK
+}
+
+/// 6 bits from square, 6 bits to square, 3 bits promotion and 1 bit marking a move is present
+fn pack_move(mv: Option<Move>) -> u16 {
+    let Some(mv) = mv else {
+        return 0;
+    };
+    let square = |pos: Position| {
+        let BoardIndex(file, rank) = pos.get_indices();
+        (rank * 8 + file) as u16
+    };
+    let promotion = match mv.promotion {
+        None => 0,
+        Some(PieceKind::Knight) => 1,
+        Some(PieceKind::Bishop) => 2,
+        Some(PieceKind::Rook) => 3,
+        Some(_) => 4,
+    };
+    square(mv.from) | (square(mv.to) << 6) | (promotion << 12) | (1 << 15)
+}
+
+fn unpack_move(packed: u16) -> Option<Move> {
+    if packed & (1 << 15) == 0 {
+        return None;
+    }
+    let position = |square: u16| {
+        Position::new_from_indices(BoardIndex((square % 8) as usize, (square / 8) as usize))
+    };
+    let promotion = match (packed >> 12) & 0b111 {
+        1 => Some(PieceKind::Knight),
+        2 => Some(PieceKind::Bishop),
+        3 => Some(PieceKind::Rook),
+        4 => Some(PieceKind::Queen),
+        _ => None,
+    };
+    Some(Move {
+        from: position(packed & 0x3f),
+        to: position((packed >> 6) & 0x3f),
+        promotion,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_stores_and_probes_entries() {
+        let table = TranspositionTable::new(1);
+        let mv = Move::new_promotion(Position('a', '7'), Position('b', '8'), PieceKind::Knight);
+        table.store(42, Some(mv), -150, 7, Bound::Lower, 3);
+
+        assert_eq!(
+            table.probe(42, 3),
+            Some(TtEntry {
+                best_move: Some(mv),
+                score: -150,
+                depth: 7,
+                bound: Bound::Lower,
+            })
+        );
+        assert_eq!(table.probe(43, 3), None);
+
+        table.clear();
+        assert_eq!(table.probe(42, 3), None);
+    }
+
+    #[test]
+    fn it_adjusts_mate_scores_by_ply() {
+        let table = TranspositionTable::new(1);
+        // Mate in 5 plies from the root, found at ply 3, is mate in 2 plies from the node
+        table.store(1, None, MATE_SCORE - 5, 4, Bound::Exact, 3);
+        assert_eq!(table.probe(1, 1).unwrap().score, MATE_SCORE - 3);
+
+        table.store(2, None, -MATE_SCORE + 6, 4, Bound::Exact, 2);
+        assert_eq!(table.probe(2, 4).unwrap().score, -MATE_SCORE + 8);
+    }
+
+    #[test]
+    fn it_keeps_best_move_when_new_entry_has_none() {
+        let table = TranspositionTable::new(1);
+        let mv = Move::new(Position('e', '2'), Position('e', '4'));
+        table.store(7, Some(mv), 10, 2, Bound::Exact, 0);
+        table.store(7, None, -30, 3, Bound::Upper, 0);
+
+        let entry = table.probe(7, 0).unwrap();
+        assert_eq!(entry.best_move, Some(mv));
+        assert_eq!(entry.bound, Bound::Upper);
+    }
+
+    #[test]
+    fn it_resizes_and_reports_hashfull() {
+        let mut table = TranspositionTable::new(1);
+        assert_eq!(table.get_size_mb(), 1);
+        assert_eq!(table.get_hashfull(), 0);
+
+        for hash in 0..100_000u64 {
+            table.store(
+                hash.wrapping_mul(0x9e37_79b9_7f4a_7c15),
+                None,
+                0,
+                1,
+                Bound::Exact,
+                0,
+            );
+        }
+        assert!(table.get_hashfull() > 500);
+
+        table.new_search();
+        assert_eq!(table.get_hashfull(), 0);
+
+        table.resize(2);
+        assert_eq!(table.get_size_mb(), 2);
+        assert_eq!(table.get_hashfull(), 0);
+    }
+}
@@ -0,0 +1,289 @@
+use crate::{
+    board::Board,
+    datagen::{parse_result, DataEntry, ENTRY_SIZE},
+    epd::{Epd, EpdOperation},
+    eval::{self, w, EvalParams, Weight, MAX_PHASE},
+};
+
+/// Range searched for the sigmoid scaling constant
+const MAX_K: f64 = 10.0;
+const ADAM_BETA1: f64 = 0.9;
+const ADAM_BETA2: f64 = 0.999;
+const ADAM_EPSILON: f64 = 1e-8;
+
+/// Gradient descent settings
+#[derive(Clone, Debug, PartialEq)]
+pub struct TuneOptions {
+    pub iterations: usize,
+    /// Adam step size, roughly how many centipawns a weight moves per iteration
+    pub learning_rate: f64,
+}
+
+impl Default for TuneOptions {
+    fn default() -> Self {
+        TuneOptions {
+            iterations: 1000,
+            learning_rate: 1.0,
+        }
+    }
+}
+
+/// Tuned parameters with the scaling constant they were tuned for and their error
+#[derive(Clone, Debug, PartialEq)]
+pub struct TuneResult {
+    pub params: EvalParams,
+    pub k: f64,
+    pub error: f64,
+}
+
+/// Position labelled with a game result, reduced to what the evaluation needs
+///
+/// Evaluation is linear in its weights before tapering, so it's kept as the number of times
+/// each weight is counted from White's point of view.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TuningEntry {
+    /// Weight indices in `EvalParams::get_weights` order with nonzero counts
+    coefficients: Vec<(usize, i32)>,
+    phase: i32,
+    /// 1.0 when White won, 0.5 for a draw, 0.0 when Black won
+    result: f64,
+}
+
+impl TuningEntry {
+    /// Finds coefficients by evaluating with one weight set at a time
+    pub fn new(board: &Board, result: f64) -> TuningEntry {
+        let mut params = EvalParams::default();
+        let mut weights = vec![Weight::default(); params.get_weights().len()];
+        let mut coefficients = Vec::new();
+        for i in 0..weights.len() {
+            weights[i] = w(1, 0);
+            params.set_weights(&weights);
+            let coefficient = eval::trace_with_params(board, &params).get_white_total().mg;
+            if coefficient != 0 {
+                coefficients.push((i, coefficient));
+            }
+            weights[i] = Weight::default();
+        }
+        TuningEntry {
+            coefficients,
+            phase: eval::get_phase(board),
+            result,
+        }
+    }
+
+    /// Share of the middlegame value in the tapered score
+    fn get_mg_factor(&self) -> f64 {
+        self.phase as f64 / MAX_PHASE as f64
+    }
+
+    /// Tapered evaluation from White's point of view with given middlegame and endgame weights
+    fn evaluate(&self, weights: &[(f64, f64)]) -> f64 {
+        let (mg, eg) = self
+            .coefficients
+            .iter()
+            .fold((0.0, 0.0), |(mg, eg), &(i, coefficient)| {
+                (
+                    mg + weights[i].0 * coefficient as f64,
+                    eg + weights[i].1 * coefficient as f64,
+                )
+            });
+        let mg_factor = self.get_mg_factor();
+        mg * mg_factor + eg * (1.0 - mg_factor)
+    }
+}
+
+/// Parses labelled positions, either "FEN | score | result" lines written by datagen or EPD
+/// records with the result in a c9 operation
+pub fn parse_dataset(text: &str) -> Result<Vec<(Board, f64)>, String> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            if line.contains('|') {
+                let entry = DataEntry::parse_text(line)?;
+                return Ok((entry.board, entry.result));
+            }
+            let epd = Epd::parse(line)?;
+            let result = epd
+                .operations
+                .iter()
+                .find_map(|operation| match operation {
+                    EpdOperation::Comment(9, result) => Some(parse_result(result)),
+                    _ => None,
+                })
+                .ok_or_else(|| format!("EPD has no c9 result: '{}'", line))??;
+            Ok((epd.get_board().clone(), result))
+        })
+        .collect()
+}
+
+/// Parses positions in the binary datagen format
+pub fn parse_binary_dataset(bytes: &[u8]) -> Result<Vec<(Board, f64)>, String> {
+    if !bytes.len().is_multiple_of(ENTRY_SIZE) {
+        return Err(format!(
+            "Binary data isn't made of {}-byte entries",
+            ENTRY_SIZE
+        ));
+    }
+    bytes
+        .chunks(ENTRY_SIZE)
+        .map(|bytes| DataEntry::from_bytes(bytes).map(|entry| (entry.board, entry.result)))
+        .collect()
+}
+
+/// Expected score of White for an evaluation
+fn sigmoid(score: f64, k: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * score / 400.0))
+}
+
+/// Mean squared difference between expected scores and game results
+fn get_error(entries: &[TuningEntry], weights: &[(f64, f64)], k: f64) -> f64 {
+    let total: f64 = entries
+        .iter()
+        .map(|entry| (entry.result - sigmoid(entry.evaluate(weights), k)).powi(2))
+        .sum();
+    total / entries.len().max(1) as f64
+}
+
+/// Scaling constant which makes the current evaluation predict results best
+fn fit_k(entries: &[TuningEntry], weights: &[(f64, f64)]) -> f64 {
+    // Golden section search, the error has a single minimum in K
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let (mut low, mut high) = (0.0, MAX_K);
+    while high - low > 1e-4 {
+        let left = high - ratio * (high - low);
+        let right = low + ratio * (high - low);
+        if get_error(entries, weights, left) < get_error(entries, weights, right) {
+            high = right;
+        } else {
+            low = left;
+        }
+    }
+    (low + high) / 2.0
+}
+
+fn to_f64(params: &EvalParams) -> Vec<(f64, f64)> {
+    params
+        .get_weights()
+        .iter()
+        .map(|weight| (weight.mg as f64, weight.eg as f64))
+        .collect()
+}
+
+/// Minimizes the error of `params` over the entries with Adam, after fitting K to them,
+/// calling `on_iteration` with the error every 100 iterations
+pub fn tune(
+    entries: &[TuningEntry],
+    params: &EvalParams,
+    options: &TuneOptions,
+    mut on_iteration: impl FnMut(usize, f64),
+) -> TuneResult {
+    let mut weights = to_f64(params);
+    let k = fit_k(entries, &weights);
+    let mut moments = vec![((0.0, 0.0), (0.0, 0.0)); weights.len()];
+    // Derivative of the sigmoid is this times σ(1 - σ)
+    let scale = k * 10f64.ln() / 400.0;
+
+    for iteration in 1..=options.iterations {
+        let mut gradient = vec![(0.0, 0.0); weights.len()];
+        for entry in entries {
+            let expected = sigmoid(entry.evaluate(&weights), k);
+            let slope = 2.0 * (expected - entry.result) * expected * (1.0 - expected) * scale
+                / entries.len() as f64;
+            let mg_factor = entry.get_mg_factor();
+            for &(i, coefficient) in &entry.coefficients {
+                gradient[i].0 += slope * coefficient as f64 * mg_factor;
+                gradient[i].1 += slope * coefficient as f64 * (1.0 - mg_factor);
+            }
+        }
+
+        let correction1 = 1.0 - ADAM_BETA1.powi(iteration as i32);
+        let correction2 = 1.0 - ADAM_BETA2.powi(iteration as i32);
+        let step = |weight: &mut f64, gradient: f64, moments: &mut (f64, f64)| {
+            moments.0 = ADAM_BETA1 * moments.0 + (1.0 - ADAM_BETA1) * gradient;
+            moments.1 = ADAM_BETA2 * moments.1 + (1.0 - ADAM_BETA2) * gradient * gradient;
+            *weight -= options.learning_rate * (moments.0 / correction1)
+                / ((moments.1 / correction2).sqrt() + ADAM_EPSILON);
+        };
+        for ((weight, gradient), moments) in weights.iter_mut().zip(&gradient).zip(&mut moments) {
+            step(&mut weight.0, gradient.0, &mut moments.0);
+            step(&mut weight.1, gradient.1, &mut moments.1);
+        }
+
+        if iteration % 100 == 0 {
+            on_iteration(iteration, get_error(entries, &weights, k));
+        }
+    }
+
+    let mut tuned = params.clone();
+    let rounded: Vec<Weight> = weights
+        .iter()
+        .map(|(mg, eg)| w(mg.round() as i32, eg.round() as i32))
+        .collect();
+    tuned.set_weights(&rounded);
+    TuneResult {
+        error: get_error(entries, &to_f64(&tuned), k),
+        params: tuned,
+        k,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_counts_weights_like_evaluation() {
+        let params = EvalParams::default();
+        let weights = to_f64(&params);
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 0 1",
+        ] {
+            let board = Board::new_from_fen(fen).unwrap();
+            let entry = TuningEntry::new(&board, 0.5);
+            let white_score = eval::trace(&board).get_white_score() as f64;
+            assert!((entry.evaluate(&weights) - white_score).abs() < 1.0);
+        }
+    }
+
+    #[test]
+    fn it_parses_labelled_positions() {
+        let positions = parse_dataset(
+            "4k3/8/8/8/8/8/8/3QK3 w - - 0 1 | 900 | 1.0\n\
+             4k3/8/8/8/8/8/8/3qK3 w - - c9 \"0-1\";\n",
+        )
+        .unwrap();
+        assert_eq!(positions.len(), 2);
+        assert_eq!(positions[1].1, 0.0);
+        assert!(parse_dataset("4k3/8/8/8/8/8/8/3qK3 w - - id \"x\";").is_err());
+        assert!(parse_binary_dataset(&[0; ENTRY_SIZE + 1]).is_err());
+    }
+
+    #[test]
+    fn it_reduces_prediction_error() {
+        // White wins with an extra pawn, so pawns should be worth more
+        let entries: Vec<TuningEntry> = [
+            ("4k3/8/8/8/8/8/3P4/4K3 w - - 0 1", 1.0),
+            ("4k3/8/8/8/8/8/2P5/4K3 b - - 0 1", 1.0),
+            ("4k3/3p4/8/8/8/8/8/4K3 w - - 0 1", 0.0),
+            ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", 0.5),
+        ]
+        .iter()
+        .map(|(fen, result)| TuningEntry::new(&Board::new_from_fen(fen).unwrap(), *result))
+        .collect();
+        let params = EvalParams::default();
+        let options = TuneOptions {
+            iterations: 200,
+            ..TuneOptions::default()
+        };
+        let mut reported = Vec::new();
+        let result = tune(&entries, &params, &options, |iteration, _| {
+            reported.push(iteration)
+        });
+
+        assert_eq!(reported, vec![100, 200]);
+        assert!(result.k > 0.0 && result.k < MAX_K);
+        assert!(result.error < get_error(&entries, &to_f64(&params), result.k));
+        assert!(result.params.piece_values[0].eg > params.piece_values[0].eg);
+    }
+}
@@ -0,0 +1,622 @@
+use std::{
+    io::{BufRead, Write},
+    iter::Peekable,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{
+    board::Board,
+    chess_move::Move,
+    engine::Engine,
+    eval::{EvalParams, Style},
+    mate_search::MateSearchStatus,
+    nnue::Network,
+    search::{Score, SearchInfo, SearchLimits, SearchOptions},
+    skill::{Skill, MAX_ELO, MAX_SKILL_LEVEL, MIN_ELO},
+    time_manager::DEFAULT_MOVE_OVERHEAD,
+    transposition_table::DEFAULT_SIZE_MB,
+};
+
+pub const ENGINE_NAME: &str = concat!("szpess ", env!("CARGO_PKG_VERSION"));
+
+/// Protocol output, shared with the search thread which reports progress
+pub type Output = Arc<Mutex<dyn Write + Send>>;
+
+/// Runs Universal Chess Interface protocol until "quit" or end of input
+pub fn run(input: impl BufRead, output: Output) -> Result<(), String> {
+    let mut uci = Uci::new(output);
+    for line in input.lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.trim() == "quit" {
+            break;
+        }
+        uci.handle_line(&line);
+    }
+    uci.engine.stop();
+    Ok(())
+}
+
+pub fn send(output: &Output, line: &str) {
+    let mut output = output.lock().expect("output isn't poisoned");
+    // Nothing to do when the GUI is gone
+    let _ = writeln!(output, "{}", line);
+    let _ = output.flush();
+}
+
+/// Info line reporting a completed iteration
+pub fn format_info(info: &SearchInfo) -> String {
+    let score = match info.score {
+        Score::Centipawns(cp) => format!("cp {}", cp),
+        Score::Mate(moves) => format!("mate {}", moves),
+    };
+    let millis = info.time.as_millis() as u64;
+    let mut line = format!(
+        "info depth {} seldepth {} multipv {} score {} nodes {} nps {} hashfull {} time {}",
+        info.depth,
+        info.seldepth,
+        info.multipv,
+        score,
+        info.nodes,
+        info.nodes * 1000 / millis.max(1),
+        info.hashfull,
+        millis
+    );
+    if !info.pv.is_empty() {
+        line.push_str(" pv");
+        for mv in &info.pv {
+            line.push(' ');
+            line.push_str(&mv.get_uci());
+        }
+    }
+    line
+}
+
+/// Finds legal move written in UCI notation, castling notation depends on the board's Chess960 flag
+pub fn parse_move(board: &Board, text: &str) -> Result<Move, String> {
+    board
+        .get_legal_moves()
+        .into_iter()
+        .find(|mv| mv.get_uci() == text)
+        .ok_or_else(|| format!("Illegal move '{}'", text))
+}
+
+/// Parses arguments of the "go" command
+pub fn parse_go(board: &Board, args: &[&str]) -> Result<SearchLimits, String> {
+    let mut limits = SearchLimits::default();
+    let mut args = args.iter().copied().peekable();
+    while let Some(arg) = args.next() {
+        match arg {
+            "wtime" => limits.white_time = Some(parse_time(&mut args)?),
+            "btime" => limits.black_time = Some(parse_time(&mut args)?),
+            "winc" => limits.white_increment = Some(parse_time(&mut args)?),
+            "binc" => limits.black_increment = Some(parse_time(&mut args)?),
+            "movetime" => limits.movetime = Some(parse_time(&mut args)?),
+            "movestogo" => limits.moves_to_go = Some(parse_number(&mut args)?),
+            "depth" => limits.depth = Some(parse_number(&mut args)?),
+            "nodes" => limits.nodes = Some(parse_number(&mut args)?),
+            "mate" => limits.mate = Some(parse_number(&mut args)?),
+            "infinite" => limits.infinite = true,
+            "ponder" => limits.ponder = true,
+            "searchmoves" => {
+                while let Some(mv) = args.peek().and_then(|arg| parse_move(board, arg).ok()) {
+                    limits.search_moves.push(mv);
+                    args.next();
+                }
+            }
+            _ => return Err(format!("Unknown go parameter '{}'", arg)),
+        }
+    }
+    Ok(limits)
+}
+
+fn parse_number<'a, T: std::str::FromStr>(
+    args: &mut Peekable<impl Iterator<Item = &'a str>>,
+) -> Result<T, String> {
+    let arg = args.next().ok_or("Missing number")?;
+    arg.parse().map_err(|_| format!("Invalid number '{}'", arg))
+}
+
+/// Milliseconds, some GUIs send negative times when the clock runs out
+fn parse_time<'a>(args: &mut Peekable<impl Iterator<Item = &'a str>>) -> Result<Duration, String> {
+    let millis: i64 = parse_number(args)?;
+    Ok(Duration::from_millis(millis.max(0) as u64))
+}
+
+/// Search option names in UCI style, e.g. "NullMove" for "null-move"
+fn get_option_name(name: &str) -> String {
+    name.split('-')
+        .map(|word| word[..1].to_uppercase() + &word[1..])
+        .collect()
+}
+
+struct Uci {
+    engine: Engine,
+    output: Output,
+    chess960: bool,
+    /// Network loaded from EvalFile, used by the search only with UseNNUE
+    network: Option<Arc<Network>>,
+    use_nnue: bool,
+    skill_level: u32,
+    /// UCI_Elo replaces Skill Level when set
+    limit_strength: bool,
+    elo: u32,
+}
+
+/// Default of UCI_Elo
+const DEFAULT_ELO: u32 = 1500;
+
+impl Uci {
+    fn new(output: Output) -> Uci {
+        Uci {
+            engine: Engine::new(),
+            output,
+            chess960: false,
+            network: None,
+            use_nnue: false,
+            skill_level: MAX_SKILL_LEVEL,
+            limit_strength: false,
+            elo: DEFAULT_ELO,
+        }
+    }
+
+    /// Errors are reported to the GUI, which otherwise ignores invalid commands
+    fn handle_line(&mut self, line: &str) {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if let Err(e) = self.handle(&tokens) {
+            send(&self.output, &format!("info string {}", e));
+        }
+    }
+
+    fn handle(&mut self, tokens: &[&str]) -> Result<(), String> {
+        let Some((command, args)) = tokens.split_first() else {
+            return Ok(());
+        };
+        match *command {
+            "uci" => self.send_id(),
+            "isready" => send(&self.output, "readyok"),
+            "setoption" => {
+                self.engine.stop();
+                self.set_option(args)?;
+            }
+            "ucinewgame" => {
+                self.engine.stop();
+                self.engine.new_game();
+            }
+            "position" => {
+                self.engine.stop();
+                self.set_position(args)?;
+            }
+            "go" => {
+                self.engine.stop();
+                let limits = parse_go(self.engine.get_board(), args)?;
+                self.go(limits);
+            }
+            "stop" => self.engine.stop(),
+            "ponderhit" => self.engine.ponderhit(),
+            "d" => send(
+                &self.output,
+                &format!(
+                    "{}Fen: {}",
+                    self.engine.get_board(),
+                    self.engine.get_board().get_fen()
+                ),
+            ),
+            _ => return Err(format!("Unknown command '{}'", command)),
+        }
+        Ok(())
+    }
+
+    fn send_id(&mut self) {
+        send(&self.output, &format!("id name {}", ENGINE_NAME));
+        send(&self.output, "id author szpess developers");
+        send(
+            &self.output,
+            &format!(
+                "option name Hash type spin default {} min 1 max 65536",
+                DEFAULT_SIZE_MB
+            ),
+        );
+        send(&self.output, "option name Clear Hash type button");
+        send(
+            &self.output,
+            "option name Threads type spin default 1 min 1 max 256",
+        );
+        send(
+            &self.output,
+            "option name MultiPV type spin default 1 min 1 max 256",
+        );
+        send(
+            &self.output,
+            &format!(
+                "option name Move Overhead type spin default {} min 0 max 5000",
+                DEFAULT_MOVE_OVERHEAD.as_millis()
+            ),
+        );
+        send(&self.output, "option name Ponder type check default false");
+        send(
+            &self.output,
+            "option name EvalFile type string default <empty>",
+        );
+        send(&self.output, "option name UseNNUE type check default false");
+        send(
+            &self.output,
+            &format!(
+                "option name Skill Level type spin default {} min 0 max {}",
+                MAX_SKILL_LEVEL, MAX_SKILL_LEVEL
+            ),
+        );
+        send(
+            &self.output,
+            "option name UCI_LimitStrength type check default false",
+        );
+        send(
+            &self.output,
+            &format!(
+                "option name UCI_Elo type spin default {} min {} max {}",
+                DEFAULT_ELO, MIN_ELO, MAX_ELO
+            ),
+        );
+        let styles: Vec<String> = Style::ALL
+            .iter()
+            .map(|style| format!("var {}", style.get_name()))
+            .collect();
+        send(
+            &self.output,
+            &format!(
+                "option name Style type combo default {} {}",
+                Style::default().get_name(),
+                styles.join(" ")
+            ),
+        );
+        send(
+            &self.output,
+            "option name UCI_Chess960 type check default false",
+        );
+        let defaults = SearchOptions::default();
+        for name in SearchOptions::NAMES {
+            send(
+                &self.output,
+                &format!(
+                    "option name {} type check default {}",
+                    get_option_name(name),
+                    defaults.get(name).unwrap_or_default()
+                ),
+            );
+        }
+        send(&self.output, "uciok");
+    }
+
+    /// Arguments are "name <name> [value <value>]", both might contain spaces
+    fn set_option(&mut self, args: &[&str]) -> Result<(), String> {
+        let value_index = args.iter().position(|arg| *arg == "value");
+        let name = args[..value_index.unwrap_or(args.len())]
+            .iter()
+            .skip_while(|arg| **arg == "name")
+            .copied()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let value = value_index.map(|index| args[index + 1..].join(" "));
+        let value = value.as_deref().unwrap_or("");
+        let parse_bool = || match value {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => Err(format!("Invalid value '{}' for option '{}'", value, name)),
+        };
+        let parse_number = || {
+            value
+                .parse::<u64>()
+                .map_err(|_| format!("Invalid value '{}' for option '{}'", value, name))
+        };
+
+        let search = self.engine.get_search();
+        match name.to_lowercase().as_str() {
+            "hash" => search.set_hash_size(parse_number()? as usize),
+            "clear hash" => search.clear_hash(),
+            "threads" => search.set_threads(parse_number()? as usize),
+            "multipv" => search.set_multi_pv(parse_number()? as usize),
+            "move overhead" => search.set_move_overhead(Duration::from_millis(parse_number()?)),
+            // GUI decides when to ponder, the option only tells it that we can
+            "ponder" => {
+                parse_bool()?;
+            }
+            "uci_chess960" => self.chess960 = parse_bool()?,
+            "evalfile" => {
+                self.network = match value {
+                    "" | "<empty>" => None,
+                    path => Some(Arc::new(Network::load(path)?)),
+                };
+                self.update_network()?;
+            }
+            "usennue" => {
+                self.use_nnue = parse_bool()?;
+                self.update_network()?;
+            }
+            "skill level" => {
+                self.skill_level = parse_number()? as u32;
+                self.update_skill();
+            }
+            "uci_limitstrength" => {
+                self.limit_strength = parse_bool()?;
+                self.update_skill();
+            }
+            "uci_elo" => {
+                self.elo = parse_number()? as u32;
+                self.update_skill();
+            }
+            "style" => {
+                let style = Style::from_name(value)
+                    .ok_or_else(|| format!("Invalid value '{}' for option '{}'", value, name))?;
+                search.set_eval_params(EvalParams::new_with_style(style));
+            }
+            lowercase_name => {
+                let option = SearchOptions::NAMES
+                    .iter()
+                    .find(|option| get_option_name(option).to_lowercase() == lowercase_name)
+                    .ok_or_else(|| format!("Unknown option '{}'", name))?;
+                let mut options = search.get_options().clone();
+                options.set(option, parse_bool()?)?;
+                search.set_options(options);
+            }
+        }
+        Ok(())
+    }
+
+    /// Search evaluates with the network when one is loaded and UseNNUE is on
+    fn update_network(&mut self) -> Result<(), String> {
+        let network = self.network.clone().filter(|_| self.use_nnue);
+        self.engine.get_search().set_network(network);
+        if self.use_nnue && self.network.is_none() {
+            return Err(String::from("UseNNUE has no effect until EvalFile is set"));
+        }
+        Ok(())
+    }
+
+    fn update_skill(&mut self) {
+        let skill = if self.limit_strength {
+            Skill::from_elo(self.elo)
+        } else {
+            Skill::new(self.skill_level)
+        };
+        self.engine.get_search().set_skill(skill);
+    }
+
+    /// Arguments are "startpos" or "fen <fen>", optionally followed by "moves <moves>"
+    fn set_position(&mut self, args: &[&str]) -> Result<(), String> {
+        let moves_index = args.iter().position(|arg| *arg == "moves");
+        let (setup, moves) = args.split_at(moves_index.unwrap_or(args.len()));
+        let mut board = match setup {
+            ["startpos"] => Board::default(),
+            ["fen", fen @ ..] => Board::new_from_fen(&fen.join(" "))?,
+            _ => return Err(String::from("Expected 'startpos' or 'fen <fen>'")),
+        };
+        if self.chess960 {
+            board.set_chess960(true);
+        }
+        self.engine.set_position(board);
+
+        for text in moves.iter().skip(1) {
+            let mv = parse_move(self.engine.get_board(), text)?;
+            self.engine.make_move(&mv)?;
+        }
+        Ok(())
+    }
+
+    fn go(&mut self, limits: SearchLimits) {
+        let (info_output, best_output) = (Arc::clone(&self.output), Arc::clone(&self.output));
+        let mate = limits.mate;
+        self.engine.start_search(
+            limits,
+            move |info| send(&info_output, &format_info(info)),
+            move |info| {
+                if let Some(moves) = mate {
+                    match info.mate_search {
+                        Some(MateSearchStatus::Refuted) => send(
+                            &best_output,
+                            &format!("info string No mate in {} found", moves),
+                        ),
+                        Some(MateSearchStatus::Aborted) => send(
+                            &best_output,
+                            &format!("info string Mate search stopped before mate in {} was proven or refuted", moves),
+                        ),
+                        _ => {}
+                    }
+                }
+                let best_move = info
+                    .get_best_move()
+                    .map_or_else(|| String::from("0000"), |mv| mv.get_uci());
+                let message = match info.get_ponder_move() {
+                    Some(ponder_move) => {
+                        format!("bestmove {} ponder {}", best_move, ponder_move.get_uci())
+                    }
+                    None => format!("bestmove {}", best_move),
+                };
+                send(&best_output, &message);
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    /// Handles commands and waits for the search to finish instead of stopping it
+    fn run_commands(commands: &str) -> String {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let mut uci = Uci::new(output.clone());
+        for line in commands.lines() {
+            uci.handle_line(line);
+        }
+        uci.engine.wait();
+        let output = output.lock().unwrap();
+        String::from_utf8(output.clone()).unwrap()
+    }
+
+    #[test]
+    fn it_identifies_and_lists_options() {
+        let output = run_commands("uci\nisready\n");
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert!(lines[0].starts_with("id name szpess"));
+        assert!(lines.contains(&"option name NullMove type check default true"));
+        assert_eq!(lines[lines.len() - 2..], ["uciok", "readyok"]);
+    }
+
+    #[test]
+    fn it_searches_position_after_moves() {
+        let output = run_commands(
+            "setoption name Hash value 1\n\
+             setoption name LateMoveReductions value false\n\
+             position startpos moves e2e4 e7e5 d1h5 b8c6 f1c4 g8f6\n\
+             go depth 2\n\
+             isready\n",
+        );
+
+        assert!(output.contains("info depth 2 "));
+        assert!(output.contains("bestmove h5f7\n"));
+        assert!(!output.contains("info string"));
+    }
+
+    #[test]
+    fn it_stops_infinite_search_on_quit() {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        run("go infinite\nquit\n".as_bytes(), output.clone()).unwrap();
+        let output = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+        assert!(output.ends_with("\n") && output.contains("bestmove "));
+    }
+
+    #[test]
+    fn it_searches_mates() {
+        let output = run_commands("position fen k7/8/2K5/8/8/8/8/7R w - - 0 1\ngo mate 2\n");
+        assert!(output.contains("info depth 3 seldepth 3 multipv 1 score mate 2 "));
+        assert!(output.contains("bestmove "));
+
+        let output = run_commands("position fen k7/8/2K5/8/8/8/8/7R w - - 0 1\ngo mate 1\n");
+        assert!(output.contains("info string No mate in 1 found\n"));
+        assert!(output.contains("info depth 2 "));
+
+        // Mate search keeps to the move time and still plays a move
+        let start = Instant::now();
+        let output = run_commands(
+            "position fen r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4\n\
+             go mate 5 movetime 300\n",
+        );
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(output
+            .contains("info string Mate search stopped before mate in 5 was proven or refuted\n"));
+        assert!(!output.contains("No mate"));
+        assert!(output.contains("bestmove "));
+    }
+
+    #[test]
+    fn it_ponders_until_ponderhit() {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let mut uci = Uci::new(output.clone());
+        uci.handle_line("position startpos moves e2e4 e7e5");
+        uci.handle_line("go ponder depth 3 wtime 60000 btime 60000");
+        std::thread::sleep(Duration::from_millis(100));
+        let get_output = || String::from_utf8(output.lock().unwrap().clone()).unwrap();
+        assert!(!get_output().contains("bestmove"));
+
+        uci.handle_line("ponderhit");
+        uci.engine.wait();
+        let output = get_output();
+        let bestmove = output.lines().last().unwrap();
+        assert!(bestmove.starts_with("bestmove "));
+        assert!(bestmove.contains(" ponder "));
+    }
+
+    #[test]
+    fn it_limits_strength() {
+        let mut uci = Uci::new(Arc::new(Mutex::new(Vec::new())));
+        for command in [
+            "setoption name Skill Level value 3",
+            "setoption name Style value Aggressive",
+        ] {
+            uci.handle_line(command);
+        }
+        assert_eq!(uci.engine.get_search().get_skill(), Skill::new(3));
+
+        uci.handle_line(&format!("setoption name UCI_Elo value {}", MAX_ELO));
+        uci.handle_line("setoption name UCI_LimitStrength value true");
+        assert_eq!(
+            uci.engine.get_search().get_skill(),
+            Skill::new(MAX_SKILL_LEVEL - 1)
+        );
+        uci.handle_line("setoption name UCI_LimitStrength value false");
+        assert_eq!(uci.engine.get_search().get_skill(), Skill::new(3));
+
+        let output = run_commands("setoption name Skill Level value 0\ngo depth 5");
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines.iter().all(|line| !line.contains(" depth 2 ")));
+        assert!(lines.last().unwrap().starts_with("bestmove "));
+    }
+
+    #[test]
+    fn it_reports_errors_as_info_strings() {
+        let output = run_commands(
+            "position startpos moves e2e5\n\
+             setoption name Foo value 1\n\
+             setoption name UseNNUE value true\n\
+             setoption name Style value Wild\n\
+             foo\n",
+        );
+        assert_eq!(
+            output,
+            "info string Illegal move 'e2e5'\n\
+             info string Unknown option 'Foo'\n\
+             info string UseNNUE has no effect until EvalFile is set\n\
+             info string Invalid value 'Wild' for option 'Style'\n\
+             info string Unknown command 'foo'\n"
+        );
+    }
+
+    #[test]
+    fn it_parses_go_limits() {
+        let board = Board::default();
+        let limits = parse_go(
+            &board,
+            &"wtime 1000 btime -20 winc 10 movestogo 5 searchmoves e2e4 d2d4 depth 7 ponder"
+                .split(' ')
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+
+        assert_eq!(limits.white_time, Some(Duration::from_millis(1000)));
+        assert_eq!(limits.black_time, Some(Duration::ZERO));
+        assert_eq!(limits.white_increment, Some(Duration::from_millis(10)));
+        assert_eq!(limits.moves_to_go, Some(5));
+        assert_eq!(
+            limits.search_moves,
+            vec![
+                Move::from_uci("e2e4").unwrap(),
+                Move::from_uci("d2d4").unwrap()
+            ]
+        );
+        assert_eq!(limits.depth, Some(7));
+        assert!(limits.ponder);
+        assert!(parse_go(&board, &["depth", "x"]).is_err());
+    }
+
+    #[test]
+    fn it_formats_info_lines() {
+        let info = SearchInfo {
+            depth: 3,
+            seldepth: 5,
+            multipv: 1,
+            score: Score::Mate(-2),
+            nodes: 500,
+            time: Duration::from_millis(250),
+            pv: vec![Move::from_uci("e2e4").unwrap()],
+            hashfull: 7,
+            mate_search: None,
+        };
+        assert_eq!(
+            format_info(&info),
+            "info depth 3 seldepth 5 multipv 1 score mate -2 nodes 500 nps 2000 hashfull 7 time 250 pv e2e4"
+        );
+    }
+}
@@ -1,8 +1,7 @@
-use iced::{Element, widget::Button};
-
 use crate::piece::Piece;
 
-pub struct chess_square {
+#[allow(dead_code)]
+pub struct ChessSquare {
     piece: Piece,
     element: ()
-}
\ No newline at end of file
+}
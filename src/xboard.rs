@@ -0,0 +1,732 @@
+use std::{
+    io::BufRead,
+    sync::{
+        mpsc::{self, Sender},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    board::Board,
+    castling_rights::CastlingSide,
+    chess_move::Move,
+    engine::Engine,
+    eval::{EvalParams, Style},
+    nnue::Network,
+    piece::PieceColor,
+    san::san_to_move,
+    search::{Score, SearchInfo, SearchLimits, SearchOptions},
+    skill::{Skill, MAX_SKILL_LEVEL},
+    uci::{self, send, Output, ENGINE_NAME},
+};
+
+/// Mate scores are reported as this plus moves to mate
+const MATE_SCORE: i32 = 100000;
+
+/// Runs XBoard protocol (CECP) until "quit" or end of input
+pub fn run(input: impl BufRead + Send + 'static, output: Output) -> Result<(), String> {
+    let (sender, receiver) = mpsc::channel();
+    let line_sender = sender.clone();
+    // Input is read on its own thread, so search results and commands arrive in one queue
+    thread::spawn(move || {
+        for line in input.lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if line_sender.send(Event::Line(line)).is_err() {
+                return;
+            }
+        }
+        let _ = line_sender.send(Event::Line(String::from("quit")));
+    });
+
+    let mut xboard = XBoard::new(output, sender);
+    for event in receiver {
+        match event {
+            Event::Line(line) if line.trim() == "quit" => break,
+            event => xboard.handle_event(event),
+        }
+    }
+    xboard.engine.stop();
+    Ok(())
+}
+
+enum Event {
+    Line(String),
+    /// Search with given id finished, with the best move and the expected reply
+    BestMove(u64, Option<Move>, Option<Move>),
+}
+
+/// Time control set by "level", clock times are updated by "time" and "otim"
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Clock {
+    /// Moves per time control, 0 for the whole game
+    moves_per_session: u32,
+    increment: Duration,
+    time: Duration,
+    opponent_time: Duration,
+}
+
+struct XBoard {
+    engine: Engine,
+    output: Output,
+    events: Sender<Event>,
+    /// Engine only tracks the game and doesn't think
+    force: bool,
+    analyzing: bool,
+    /// Side the engine plays, set by "go"
+    engine_color: Option<PieceColor>,
+    /// Print thinking output
+    post: bool,
+    chess960: bool,
+    clock: Option<Clock>,
+    move_time: Option<Duration>,
+    max_depth: Option<u32>,
+    /// Id of the search whose move will be played, ignoring results of cancelled ones
+    search_id: u64,
+    thinking: bool,
+    /// Think on the opponent's time, set by "hard" and cleared by "easy"
+    ponder: bool,
+    /// Expected opponent move, made on the board while pondering
+    ponder_move: Option<Move>,
+    /// Network loaded from EvalFile, used by the search only with UseNNUE
+    network: Option<Arc<Network>>,
+    use_nnue: bool,
+}
+
+impl XBoard {
+    fn new(output: Output, events: Sender<Event>) -> XBoard {
+        XBoard {
+            engine: Engine::new(),
+            output,
+            events,
+            force: false,
+            analyzing: false,
+            engine_color: Some(PieceColor::Black),
+            post: false,
+            chess960: false,
+            clock: None,
+            move_time: None,
+            max_depth: None,
+            search_id: 0,
+            thinking: false,
+            ponder: false,
+            ponder_move: None,
+            network: None,
+            use_nnue: false,
+        }
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        match event {
+            Event::Line(line) => {
+                let tokens: Vec<&str> = line.split_whitespace().collect();
+                if let Err(e) = self.handle(&tokens) {
+                    send(&self.output, &e);
+                }
+            }
+            Event::BestMove(id, mv, ponder_move) => self.on_best_move(id, mv, ponder_move),
+        }
+    }
+
+    /// Errors are already formatted as protocol messages
+    fn handle(&mut self, tokens: &[&str]) -> Result<(), String> {
+        let Some((command, args)) = tokens.split_first() else {
+            return Ok(());
+        };
+        let error = |reason: &str| format!("Error ({}): {}", reason, tokens.join(" "));
+        let argument = |index: usize| {
+            args.get(index)
+                .copied()
+                .ok_or_else(|| error("missing argument"))
+        };
+
+        match *command {
+            "xboard" | "accepted" | "rejected" | "random" | "computer" | "name" | "rating"
+            | "draw" | "hint" | "bk" | "." | "white" | "black" => {}
+            "protover" => self.send_features(),
+            "ping" => send(&self.output, &format!("pong {}", argument(0)?)),
+            "new" => {
+                self.cancel_search();
+                self.engine.new_game();
+                self.force = false;
+                self.engine_color = Some(PieceColor::Black);
+                self.move_time = None;
+                self.max_depth = None;
+            }
+            "variant" => {
+                self.chess960 = match argument(0)? {
+                    "normal" => false,
+                    "fischerandom" => true,
+                    _ => return Err(error("unsupported variant")),
+                };
+                let mut board = Board::default();
+                board.set_chess960(self.chess960);
+                self.set_position(board);
+            }
+            "setboard" => {
+                let mut board =
+                    Board::new_from_fen(&args.join(" ")).map_err(|_| error("invalid FEN"))?;
+                if self.chess960 {
+                    board.set_chess960(true);
+                }
+                self.set_position(board);
+            }
+            "force" => {
+                self.cancel_search();
+                self.force = true;
+            }
+            "go" => {
+                self.cancel_search();
+                self.force = false;
+                self.engine_color = Some(self.engine.get_board().get_to_move());
+                self.think();
+            }
+            "playother" => {
+                self.cancel_search();
+                self.force = false;
+                self.engine_color = Some(self.engine.get_board().get_to_move().opposite());
+            }
+            "usermove" => {
+                let text = argument(0)?;
+                if let Some(ponder_move) = self.ponder_move {
+                    if parse_move(&self.get_board_before_ponder_move(), text) == Ok(ponder_move) {
+                        // Ponder hit, the search continues on our clock
+                        self.ponder_move = None;
+                        self.thinking = true;
+                        self.engine.ponderhit();
+                        return Ok(());
+                    }
+                }
+                self.cancel_search();
+                let mv = parse_move(self.engine.get_board(), text)
+                    .map_err(|_| format!("Illegal move: {}", text))?;
+                self.engine.make_move(&mv)?;
+                self.on_position_changed();
+            }
+            "?" => {
+                // While pondering there is no move of ours to play yet
+                if self.ponder_move.is_none() {
+                    self.engine.stop();
+                }
+            }
+            "undo" => self.take_back(1),
+            "remove" => self.take_back(2),
+            "result" => {
+                self.cancel_search();
+                self.force = true;
+            }
+            "level" => {
+                let moves_per_session = argument(0)?.parse().map_err(|_| error("invalid level"))?;
+                let base = parse_base_time(argument(1)?).ok_or_else(|| error("invalid level"))?;
+                let increment = argument(2)?
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|increment| *increment >= 0.0)
+                    .ok_or_else(|| error("invalid level"))?;
+                self.clock = Some(Clock {
+                    moves_per_session,
+                    increment: Duration::from_secs_f64(increment),
+                    time: base,
+                    opponent_time: base,
+                });
+                self.move_time = None;
+            }
+            "st" => {
+                let seconds = argument(0)?
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|seconds| *seconds > 0.0)
+                    .ok_or_else(|| error("invalid time"))?;
+                self.move_time = Some(Duration::from_secs_f64(seconds));
+            }
+            "sd" => {
+                self.max_depth = Some(argument(0)?.parse().map_err(|_| error("invalid depth"))?)
+            }
+            "time" | "otim" => {
+                let centiseconds: i64 = argument(0)?.parse().map_err(|_| error("invalid time"))?;
+                let time = Duration::from_millis(centiseconds.max(0) as u64 * 10);
+                let clock = self.clock.get_or_insert(Clock {
+                    moves_per_session: 0,
+                    increment: Duration::ZERO,
+                    time,
+                    opponent_time: time,
+                });
+                if *command == "time" {
+                    clock.time = time;
+                } else {
+                    clock.opponent_time = time;
+                }
+            }
+            "hard" => self.ponder = true,
+            "easy" => {
+                self.ponder = false;
+                if self.ponder_move.is_some() {
+                    self.cancel_search();
+                }
+            }
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "analyze" => {
+                self.cancel_search();
+                self.analyzing = true;
+                self.think();
+            }
+            "exit" => {
+                self.cancel_search();
+                self.analyzing = false;
+            }
+            "memory" => {
+                let size_mb = argument(0)?.parse().map_err(|_| error("invalid size"))?;
+                self.cancel_search();
+                self.engine.get_search().set_hash_size(size_mb);
+                self.restart_analysis();
+            }
+            "cores" => {
+                let threads = argument(0)?.parse().map_err(|_| error("invalid count"))?;
+                self.cancel_search();
+                self.engine.get_search().set_threads(threads);
+                self.restart_analysis();
+            }
+            "option" => {
+                self.cancel_search();
+                self.set_option(&args.join(" "))
+                    .map_err(|_| error("invalid option"))?;
+                self.restart_analysis();
+            }
+            _ => return Err(error("unknown command")),
+        }
+        Ok(())
+    }
+
+    fn send_features(&self) {
+        let mut features = format!(
+            "feature done=0 myname=\"{}\" ping=1 setboard=1 usermove=1 san=0 colors=0 sigint=0 \
+             sigterm=0 reuse=1 analyze=1 memory=1 smp=1 variants=\"normal,fischerandom\"",
+            ENGINE_NAME
+        );
+        features.push_str(" option=\"EvalFile -file \" option=\"UseNNUE -check 0\"");
+        features.push_str(&format!(
+            " option=\"Skill Level -spin {} 0 {}\"",
+            MAX_SKILL_LEVEL, MAX_SKILL_LEVEL
+        ));
+        // Default choice of a combo option is marked with a star
+        let styles: Vec<String> = Style::ALL
+            .iter()
+            .map(|style| match style {
+                Style::Normal => format!("*{}", style.get_name()),
+                _ => String::from(style.get_name()),
+            })
+            .collect();
+        features.push_str(&format!(
+            " option=\"Style -combo {}\"",
+            styles.join(" /// ")
+        ));
+        let defaults = SearchOptions::default();
+        for name in SearchOptions::NAMES {
+            features.push_str(&format!(
+                " option=\"{} -check {}\"",
+                name,
+                defaults.get(name).unwrap_or_default() as u8
+            ));
+        }
+        send(&self.output, &features);
+        send(&self.output, "feature done=1");
+    }
+
+    /// Option is sent as "NAME=VALUE", check options have value 0 or 1
+    fn set_option(&mut self, option: &str) -> Result<(), String> {
+        let (name, value) = option.split_once('=').ok_or("Missing value")?;
+        match name {
+            "EvalFile" => {
+                self.network = match value {
+                    "" => None,
+                    path => Some(Arc::new(Network::load(path)?)),
+                };
+            }
+            "UseNNUE" => self.use_nnue = value == "1",
+            "Skill Level" => {
+                let level = value
+                    .parse()
+                    .map_err(|_| format!("Invalid skill level '{}'", value))?;
+                self.engine.get_search().set_skill(Skill::new(level));
+                return Ok(());
+            }
+            "Style" => {
+                let style =
+                    Style::from_name(value).ok_or_else(|| format!("Invalid style '{}'", value))?;
+                self.engine
+                    .get_search()
+                    .set_eval_params(EvalParams::new_with_style(style));
+                return Ok(());
+            }
+            _ => {
+                let search = self.engine.get_search();
+                let mut options = search.get_options().clone();
+                options.set(name, value == "1")?;
+                search.set_options(options);
+                return Ok(());
+            }
+        }
+        // Search evaluates with the network when one is loaded and UseNNUE is on
+        let network = self.network.clone().filter(|_| self.use_nnue);
+        self.engine.get_search().set_network(network);
+        Ok(())
+    }
+
+    fn set_position(&mut self, board: Board) {
+        self.cancel_search();
+        self.engine.set_position(board);
+        self.restart_analysis();
+    }
+
+    fn take_back(&mut self, moves: usize) {
+        self.cancel_search();
+        for _ in 0..moves {
+            self.engine.undo_move();
+        }
+        self.restart_analysis();
+    }
+
+    /// After a move the engine replies, or analyzes the new position
+    fn on_position_changed(&mut self) {
+        if self.send_result_if_over() {
+            return;
+        }
+        let is_engine_turn =
+            !self.force && self.engine_color == Some(self.engine.get_board().get_to_move());
+        if self.analyzing || is_engine_turn {
+            self.think();
+        }
+    }
+
+    fn restart_analysis(&mut self) {
+        if self.analyzing {
+            self.think();
+        }
+    }
+
+    fn think(&mut self) {
+        self.engine.stop();
+        self.search_id += 1;
+        self.thinking = !self.analyzing;
+        let limits = self.get_limits();
+        let (id, events) = (self.search_id, self.events.clone());
+        let (output, post) = (self.output.clone(), self.post || self.analyzing);
+        self.engine.start_search(
+            limits,
+            move |info| {
+                if post && info.multipv == 1 {
+                    send(&output, &format_thinking(info));
+                }
+            },
+            move |info| {
+                let _ = events.send(Event::BestMove(
+                    id,
+                    info.get_best_move(),
+                    info.get_ponder_move(),
+                ));
+            },
+        );
+    }
+
+    /// Makes the expected opponent move and searches the position until the opponent moves
+    fn start_pondering(&mut self, ponder_move: Move) {
+        if self.engine.make_move(&ponder_move).is_err() {
+            return;
+        }
+        if get_result(self.engine.get_board(), self.engine.get_previous_boards()).is_some() {
+            self.engine.undo_move();
+            return;
+        }
+        self.ponder_move = Some(ponder_move);
+        self.think();
+        // Move is played only after a ponder hit
+        self.thinking = false;
+    }
+
+    fn get_board_before_ponder_move(&self) -> Board {
+        self.engine
+            .get_previous_boards()
+            .last()
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Stops search so that its move isn't played, takes back the expected move when pondering
+    fn cancel_search(&mut self) {
+        self.search_id += 1;
+        self.thinking = false;
+        self.engine.stop();
+        if self.ponder_move.take().is_some() {
+            self.engine.undo_move();
+        }
+    }
+
+    fn get_limits(&self) -> SearchLimits {
+        let mut limits = SearchLimits {
+            depth: self.max_depth,
+            ponder: self.ponder_move.is_some(),
+            ..SearchLimits::default()
+        };
+        if self.analyzing {
+            limits.infinite = true;
+            return limits;
+        }
+        if let Some(move_time) = self.move_time {
+            limits.movetime = Some(move_time);
+        } else if let Some(clock) = self.clock {
+            let board = self.engine.get_board();
+            let (white_time, black_time) = match board.get_to_move() {
+                PieceColor::White => (clock.time, clock.opponent_time),
+                PieceColor::Black => (clock.opponent_time, clock.time),
+            };
+            limits.white_time = Some(white_time);
+            limits.black_time = Some(black_time);
+            limits.white_increment = Some(clock.increment);
+            limits.black_increment = Some(clock.increment);
+            if clock.moves_per_session > 0 {
+                let moves_played = board.get_fullmove_number().saturating_sub(1);
+                limits.moves_to_go =
+                    Some(clock.moves_per_session - moves_played % clock.moves_per_session);
+            }
+        }
+        limits
+    }
+
+    fn on_best_move(&mut self, id: u64, mv: Option<Move>, ponder_move: Option<Move>) {
+        if id != self.search_id || !self.thinking {
+            return;
+        }
+        self.thinking = false;
+        let Some(mv) = mv else {
+            return;
+        };
+        let text = format_move(self.engine.get_board(), &mv);
+        if self.engine.make_move(&mv).is_ok() {
+            send(&self.output, &format!("move {}", text));
+            if self.send_result_if_over() {
+                return;
+            }
+            if let Some(ponder_move) = ponder_move.filter(|_| self.ponder && !self.force) {
+                self.start_pondering(ponder_move);
+            }
+        }
+    }
+
+    fn send_result_if_over(&mut self) -> bool {
+        let Some(result) = get_result(self.engine.get_board(), self.engine.get_previous_boards())
+        else {
+            return false;
+        };
+        send(&self.output, result);
+        true
+    }
+
+    /// Test helper, waits until the engine has played its move
+    #[cfg(test)]
+    fn wait_for_move(&mut self, receiver: &mpsc::Receiver<Event>) {
+        while self.thinking {
+            let event = receiver
+                .recv_timeout(Duration::from_secs(60))
+                .expect("search finishes");
+            self.handle_event(event);
+        }
+    }
+}
+
+/// Coordinate notation, or SAN castling which XBoard expects in Chess960
+fn parse_move(board: &Board, text: &str) -> Result<Move, String> {
+    uci::parse_move(board, text).or_else(|_| san_to_move(board, text))
+}
+
+fn format_move(board: &Board, mv: &Move) -> String {
+    match board.get_castling_side(mv) {
+        Some(CastlingSide::Kingside) if board.is_chess960() => String::from("O-O"),
+        Some(CastlingSide::Queenside) if board.is_chess960() => String::from("O-O-O"),
+        _ => mv.get_uci(),
+    }
+}
+
+/// Base time is minutes, optionally followed by seconds as "MINUTES:SECONDS"
+fn parse_base_time(text: &str) -> Option<Duration> {
+    let (minutes, seconds) = text.split_once(':').unwrap_or((text, "0"));
+    let seconds = minutes.parse::<u64>().ok()? * 60 + seconds.parse::<u64>().ok()?;
+    Some(Duration::from_secs(seconds))
+}
+
+/// Thinking output, "depth score time nodes pv" with time in centiseconds
+fn format_thinking(info: &SearchInfo) -> String {
+    let score = match info.score {
+        Score::Centipawns(cp) => cp,
+        Score::Mate(moves) if moves > 0 => MATE_SCORE + moves,
+        Score::Mate(moves) => -MATE_SCORE + moves,
+    };
+    let pv: Vec<String> = info.pv.iter().map(Move::get_uci).collect();
+    format!(
+        "{} {} {} {} {}",
+        info.depth,
+        score,
+        info.time.as_millis() / 10,
+        info.nodes,
+        pv.join(" ")
+    )
+    .trim_end()
+    .to_string()
+}
+
+/// Result message when the game is over, `previous` are positions before the current one
+fn get_result(board: &Board, previous: &[Board]) -> Option<&'static str> {
+    if board.is_checkmate() {
+        return Some(match board.get_to_move() {
+            PieceColor::White => "0-1 {Black mates}",
+            PieceColor::Black => "1-0 {White mates}",
+        });
+    }
+    if board.is_stalemate() {
+        return Some("1/2-1/2 {Stalemate}");
+    }
+    if board.get_halfmove_clock() >= 100 {
+        return Some("1/2-1/2 {Fifty move rule}");
+    }
+    let hash = board.get_hash();
+    let repetitions = previous
+        .iter()
+        .filter(|previous| previous.get_hash() == hash)
+        .count();
+    if repetitions >= 2 {
+        return Some("1/2-1/2 {Threefold repetition}");
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Handles commands in order, waiting for the engine to move after each one
+    fn run_commands(commands: &str) -> String {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let (sender, receiver) = mpsc::channel();
+        let mut xboard = XBoard::new(output.clone(), sender);
+        for line in commands.lines() {
+            xboard.handle_event(Event::Line(String::from(line)));
+            xboard.wait_for_move(&receiver);
+        }
+        xboard.engine.stop();
+        let output = output.lock().unwrap();
+        String::from_utf8(output.clone()).unwrap()
+    }
+
+    #[test]
+    fn it_negotiates_features() {
+        let output = run_commands("xboard\nprotover 2\nping 7");
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert!(lines[0].starts_with("feature done=0 myname=\"szpess"));
+        assert!(lines[0].contains(" option=\"null-move -check 1\""));
+        assert!(lines[0].contains(" option=\"Style -combo *Normal /// Aggressive /// Positional\""));
+        assert_eq!(lines[1..], ["feature done=1", "pong 7"]);
+    }
+
+    #[test]
+    fn it_replies_to_user_moves() {
+        let output = run_commands("new\nsd 2\nusermove e2e4\nforce\nusermove g1f3\ngo\n");
+        let moves: Vec<&str> = output
+            .lines()
+            .filter(|line| line.starts_with("move "))
+            .collect();
+        assert_eq!(moves.len(), 2);
+    }
+
+    #[test]
+    fn it_takes_back_moves_and_reports_errors() {
+        let output =
+            run_commands("force\nusermove e2e4\nusermove e7e5\nremove\nusermove e2e5\nfoo");
+        assert_eq!(output, "Illegal move: e2e5\nError (unknown command): foo\n");
+    }
+
+    #[test]
+    fn it_announces_checkmate() {
+        let output =
+            run_commands("new\nforce\nusermove f2f3\nusermove e7e5\nusermove g2g4\nusermove d8h4");
+        assert_eq!(output, "0-1 {Black mates}\n");
+
+        // Engine finds the mate and announces it
+        let output = run_commands("setboard 6k1/5ppp/8/8/8/8/8/R3K3 w Q - 0 1\nsd 3\npost\ngo");
+        assert!(output.contains("move a1a8\n1-0 {White mates}\n"));
+        assert!(output.contains("3 100001 "));
+    }
+
+    #[test]
+    fn it_sets_skill_level_and_style() {
+        let (sender, _receiver) = mpsc::channel();
+        let mut xboard = XBoard::new(Arc::new(Mutex::new(Vec::new())), sender);
+        xboard.handle_event(Event::Line(String::from("option Skill Level=5")));
+        assert_eq!(xboard.engine.get_search().get_skill(), Skill::new(5));
+
+        let output = run_commands("option Style=Positional\noption Style=Wild");
+        assert_eq!(output, "Error (invalid option): option Style=Wild\n");
+    }
+
+    #[test]
+    fn it_ponders_on_expected_move() {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let (sender, receiver) = mpsc::channel();
+        let mut xboard = XBoard::new(output.clone(), sender);
+        let handle = |xboard: &mut XBoard, line: &str| {
+            xboard.handle_event(Event::Line(String::from(line)));
+            xboard.wait_for_move(&receiver);
+        };
+        for line in ["new", "hard", "sd 3", "usermove e2e4"] {
+            handle(&mut xboard, line);
+        }
+        let ponder_move = xboard.ponder_move.expect("engine ponders after its move");
+        assert_eq!(xboard.engine.get_previous_boards().len(), 3);
+
+        // Pondering search goes on as a normal one
+        handle(&mut xboard, &format!("usermove {}", ponder_move.get_uci()));
+        assert_eq!(xboard.engine.get_previous_boards().len(), 5);
+
+        // Unexpected move takes back the expected one first
+        let ponder_move = xboard.ponder_move.unwrap();
+        let other = xboard
+            .get_board_before_ponder_move()
+            .get_legal_moves()
+            .into_iter()
+            .find(|mv| *mv != ponder_move)
+            .unwrap();
+        handle(&mut xboard, &format!("usermove {}", other.get_uci()));
+        handle(&mut xboard, "force");
+        assert_eq!(xboard.engine.get_previous_boards().len(), 6);
+        xboard.engine.stop();
+
+        let output = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+        assert_eq!(output.lines().filter(|l| l.starts_with("move ")).count(), 3);
+    }
+
+    #[test]
+    fn it_converts_time_controls() {
+        let (sender, _receiver) = mpsc::channel();
+        let mut xboard = XBoard::new(Arc::new(Mutex::new(Vec::new())), sender);
+        for line in ["level 40 0:30 2", "time 1500", "otim 2000"] {
+            xboard.handle_event(Event::Line(String::from(line)));
+        }
+        let limits = xboard.get_limits();
+
+        assert_eq!(limits.white_time, Some(Duration::from_secs(15)));
+        assert_eq!(limits.black_time, Some(Duration::from_secs(20)));
+        assert_eq!(limits.white_increment, Some(Duration::from_secs(2)));
+        assert_eq!(limits.moves_to_go, Some(40));
+
+        // Fullmove number 0 counts as the first move
+        xboard.handle_event(Event::Line(String::from(
+            "setboard 4k3/8/8/8/8/8/8/4K2R w K - 0 0",
+        )));
+        assert_eq!(xboard.get_limits().moves_to_go, Some(40));
+        assert_eq!(parse_base_time("5"), Some(Duration::from_secs(300)));
+    }
+}
@@ -0,0 +1,140 @@
+use crate::{
+    board::Board,
+    castling_rights::CastlingSide,
+    piece::{PieceColor, PieceKind},
+    position::{BoardIndex, Position},
+};
+
+const PIECE_KEYS: usize = 12 * 64;
+const SIDE_KEY: usize = PIECE_KEYS;
+/// One key for every color, castling side and rook file, so Chess960 rights hash differently
+const CASTLING_KEYS: usize = SIDE_KEY + 1;
+const EN_PASSANT_KEYS: usize = CASTLING_KEYS + 2 * 2 * 8;
+const KEY_COUNT: usize = EN_PASSANT_KEYS + 8;
+
+const KEYS: [u64; KEY_COUNT] = generate_keys();
+
+/// Fills key table with SplitMix64, fixed seed keeps hashes stable between runs
+const fn generate_keys() -> [u64; KEY_COUNT] {
+    let mut keys = [0; KEY_COUNT];
+    let mut state: u64 = 0x5a5a_2b2b_c0ff_ee00;
+    let mut i = 0;
+    while i < KEY_COUNT {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+}
+
+/// Zobrist hash of a position
+///
+/// En passant square is hashed only if a pawn can capture on it, so positions that
+/// differ just by an unusable en passant square are treated as repetitions
+pub fn get_hash(board: &Board) -> u64 {
+    let mut hash = 0;
+    for piece in board.get_flat_pieces() {
+        let BoardIndex(file, rank) = piece.get_position().get_indices();
+        let color = match piece.get_color() {
+            PieceColor::White => 0,
+            PieceColor::Black => 6,
+        };
+        let kind = match piece.get_kind() {
+            PieceKind::Pawn => 0,
+            PieceKind::Knight => 1,
+            PieceKind::Bishop => 2,
+            PieceKind::Rook => 3,
+            PieceKind::Queen => 4,
+            PieceKind::King => 5,
+        };
+        hash ^= KEYS[(color + kind) * 64 + rank * 8 + file];
+    }
+
+    if board.get_to_move() == PieceColor::Black {
+        hash ^= KEYS[SIDE_KEY];
+    }
+
+    let rights = board.get_castling_rights();
+    for (color_index, color) in [PieceColor::White, PieceColor::Black].iter().enumerate() {
+        for (side_index, side) in [CastlingSide::Kingside, CastlingSide::Queenside]
+            .iter()
+            .enumerate()
+        {
+            if let Some(file) = rights.get_rook_file(*color, *side) {
+                let file = Position(file, '1').get_indices().0;
+                hash ^= KEYS[CASTLING_KEYS + (color_index * 2 + side_index) * 8 + file];
+            }
+        }
+    }
+
+    if let Some(square) = board.get_en_passant() {
+        if can_capture_en_passant(board, square) {
+            hash ^= KEYS[EN_PASSANT_KEYS + square.get_indices().0];
+        }
+    }
+    hash
+}
+
+fn can_capture_en_passant(board: &Board, square: Position) -> bool {
+    let color = board.get_to_move();
+    [-1, 1].iter().any(
+        |file_delta| match square.offset(*file_delta, -color.pawn_direction()) {
+            Some(from) => matches!(
+                board.get_piece_by_position(from),
+                Some(piece) if piece.get_kind() == PieceKind::Pawn && *piece.get_color() == color
+            ),
+            None => false,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_move::Move;
+
+    fn play(board: &Board, moves: &[&str]) -> Board {
+        let mut board = board.clone();
+        for mv in moves {
+            board.make_move(&Move::from_uci(mv).unwrap()).unwrap();
+        }
+        board
+    }
+
+    #[test]
+    fn it_hashes_transpositions_equally() {
+        let board = Board::default();
+        let first = play(&board, &["g1f3", "g8f6", "b1c3"]);
+        let second = play(&board, &["b1c3", "g8f6", "g1f3"]);
+
+        assert_eq!(get_hash(&first), get_hash(&second));
+        assert_eq!(
+            get_hash(&board),
+            get_hash(&play(&board, &["g1f3", "g8f6", "f3g1", "f6g8"]))
+        );
+    }
+
+    #[test]
+    fn it_hashes_side_to_move_and_castling_rights() {
+        let white = Board::new_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let black = Board::new_from_fen("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1").unwrap();
+        let no_castling = Board::new_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w Kkq - 0 1").unwrap();
+
+        assert_ne!(get_hash(&white), get_hash(&black));
+        assert_ne!(get_hash(&white), get_hash(&no_castling));
+    }
+
+    #[test]
+    fn it_hashes_only_capturable_en_passant_square() {
+        let without = Board::new_from_fen("4k3/8/8/8/4P3/8/8/4K3 b - - 0 1").unwrap();
+        let unusable = Board::new_from_fen("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1").unwrap();
+        let usable = Board::new_from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").unwrap();
+        let usable_without = Board::new_from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1").unwrap();
+
+        assert_eq!(get_hash(&without), get_hash(&unusable));
+        assert_ne!(get_hash(&usable), get_hash(&usable_without));
+    }
+}